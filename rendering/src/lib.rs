@@ -1,11 +1,12 @@
-use bevy::{
-    light::{DirectionalLightShadowMap, PointLightShadowMap},
-    prelude::*,
-    window::PrimaryWindow,
-};
+use std::collections::VecDeque;
+
+use bevy::{light::DirectionalLightShadowMap, prelude::*, window::PrimaryWindow};
 use simulation::{
-    driver::{Blinker, PlayerControlled, Vehicle, YieldResolver},
-    Id, Road, Segment, SegmentGeometry, SimulationPlugin,
+    driver::{
+        player_max_speed, reattach, Blinker, FreeDrive, Frozen, PlayerControlled, SidePriority,
+        Vehicle, YieldResolver,
+    },
+    Id, Road, Segment, SegmentGeometry, SimulationPlugin, SimulationSpeed, LANE_WIDTH,
 };
 use wasm_bindgen::prelude::*;
 
@@ -17,15 +18,158 @@ struct SelectedVehicle(Option<Entity>);
 #[derive(Resource, Default)]
 struct SelectedSegment(Option<Id<Segment>>);
 
-/// Road width in meters (single lane)
-const LANE_WIDTH: f32 = 3.5;
+/// Resource toggling the acceleration trail gizmos (key T)
+#[derive(Resource, Default)]
+struct ShowAccelTrails(bool);
+
+/// Number of samples kept per vehicle trail
+const TRAIL_SAMPLES: usize = 40;
+/// Seconds between trail samples
+const TRAIL_INTERVAL: f32 = 0.1;
+/// Acceleration (m/s²) at which trail color saturates
+const TRAIL_FULL_ACCEL: f32 = 3.0;
+
+/// Range of simulation speeds reachable with the [ and ] keys
+const MIN_SIMULATION_SPEED: f32 = 0.125;
+const MAX_SIMULATION_SPEED: f32 = 8.0;
+
 /// Vehicle height in meters
 const CAR_HEIGHT: f32 = 1.2;
 
+/// Orthographic scale (world units per pixel) before the camera is fitted to the road
+const DEFAULT_CAMERA_SCALE: f32 = 0.18;
+/// Closest the camera sits to the point it looks at
+const MIN_CAMERA_DISTANCE: f32 = 120.0;
+/// Extra room around the road when framing it
+const CAMERA_MARGIN: f32 = 1.2;
+/// Orthographic scale while following the player vehicle
+const FOLLOW_CAMERA_SCALE: f32 = 0.06;
+/// How quickly (1/s) the camera closes in on its target; lower lags further behind
+const CAMERA_DAMPING: f32 = 4.0;
+
+/// How the camera frames the scene (key C toggles)
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum CameraMode {
+    /// The whole road, as fitted by `fit_camera_to_road`
+    #[default]
+    Overview,
+    /// Zoomed in on the player vehicle, falling back to the overview while there is none
+    Follow,
+}
+
+/// Camera pose and zoom that frame the whole road
+#[derive(Resource, Clone, Copy)]
+struct OverviewCamera {
+    transform: Transform,
+    scale: f32,
+}
+
+impl Default for OverviewCamera {
+    fn default() -> Self {
+        Self {
+            transform: isometric_camera(Vec3::ZERO, MIN_CAMERA_DISTANCE),
+            scale: DEFAULT_CAMERA_SCALE,
+        }
+    }
+}
+
 /// Marker component for vehicles that have render meshes attached
 #[derive(Component)]
 struct VehicleRender;
 
+/// Vehicle pose before the latest simulation step, for interpolating between steps
+#[derive(Component)]
+struct PreviousPose {
+    translation: Vec3,
+    rotation: Quat,
+}
+
+/// Recent positions and accelerations of a vehicle, newest last
+#[derive(Component, Default)]
+struct AccelTrail {
+    samples: VecDeque<(Vec3, f32)>,
+    last_speed: f32,
+    last_segment: Option<Id<Segment>>,
+    since_sample: f32,
+}
+
+/// Classic isometric view: 45° around the vertical, ~30° elevation, `distance` back from
+/// `look_at`
+fn isometric_camera(look_at: Vec3, distance: f32) -> Transform {
+    Transform::from_xyz(look_at.x + distance, look_at.y - distance, distance * 0.7)
+        .looking_at(look_at, Vec3::Z)
+}
+
+/// Center the camera on the road and zoom out until the whole network fits the window.
+/// An empty road keeps the default framing.
+fn fit_camera_to_road(
+    road: Res<Road>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut overview: ResMut<OverviewCamera>,
+    mut cameras: Query<(&mut Transform, &mut Projection), With<Camera3d>>,
+) {
+    let Some((min, max)) = road.bounds() else {
+        return;
+    };
+
+    let look_at = ((min + max) / 2.0).with_z(0.0);
+    let size = max - min;
+    // Seen from 45°, the footprint's widest screen extent is its diagonal across both axes
+    let span = (size.x + size.y) / std::f32::consts::SQRT_2 * CAMERA_MARGIN;
+    let pixels = window
+        .single()
+        .map(|window| window.width().min(window.height()))
+        .unwrap_or(720.0)
+        .max(1.0);
+
+    // Stay far enough back that no part of the road is behind the near plane
+    overview.transform = isometric_camera(look_at, span.max(MIN_CAMERA_DISTANCE));
+    overview.scale = (span / pixels).max(DEFAULT_CAMERA_SCALE / 10.0);
+
+    for (mut transform, mut projection) in &mut cameras {
+        *transform = overview.transform;
+        if let Projection::Orthographic(orthographic) = projection.as_mut() {
+            orthographic.scale = overview.scale;
+        }
+    }
+}
+
+/// Ease the camera towards the framing of the current [`CameraMode`]. Only the position and
+/// zoom change, so the isometric angle is kept while following.
+fn update_camera(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<CameraMode>,
+    overview: Res<OverviewCamera>,
+    player: Query<&Transform, (With<PlayerControlled>, Without<Camera3d>)>,
+    mut cameras: Query<(&mut Transform, &mut Projection), With<Camera3d>>,
+    time: Res<Time>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        *mode = match *mode {
+            CameraMode::Overview => CameraMode::Follow,
+            CameraMode::Follow => CameraMode::Overview,
+        };
+    }
+
+    let (target, scale) = match (*mode, player.single()) {
+        (CameraMode::Follow, Ok(player)) => (
+            isometric_camera(player.translation.with_z(0.0), MIN_CAMERA_DISTANCE),
+            FOLLOW_CAMERA_SCALE,
+        ),
+        _ => (overview.transform, overview.scale),
+    };
+
+    // Frame-rate independent exponential smoothing
+    let t = 1.0 - (-CAMERA_DAMPING * time.delta_secs()).exp();
+    for (mut transform, mut projection) in &mut cameras {
+        transform.translation = transform.translation.lerp(target.translation, t);
+        transform.rotation = transform.rotation.slerp(target.rotation, t);
+        if let Projection::Orthographic(orthographic) = projection.as_mut() {
+            orthographic.scale += (scale - orthographic.scale) * t;
+        }
+    }
+}
+
 /// Resource holding shared vehicle mesh and materials
 #[derive(Resource)]
 struct VehicleAssets {
@@ -47,11 +191,18 @@ pub fn start() {
             }),
             ..default()
         }))
-        .add_plugins(SimulationPlugin)
+        .add_plugins(SimulationPlugin::default())
         .init_resource::<SelectedVehicle>()
         .init_resource::<SelectedSegment>()
+        .init_resource::<ShowAccelTrails>()
+        .init_resource::<CameraMode>()
+        .init_resource::<OverviewCamera>()
         .add_systems(Startup, (setup, test_intersection))
-        .add_systems(Startup, spawn_road_meshes.after(test_intersection))
+        .add_systems(
+            Startup,
+            (spawn_road_meshes, fit_camera_to_road.after(setup)).after(test_intersection),
+        )
+        .add_systems(FixedPreUpdate, record_previous_poses)
         .add_systems(
             Update,
             (
@@ -59,7 +210,11 @@ pub fn start() {
                 spawn_vehicle_meshes,
                 update_vehicle_transforms,
                 draw_vehicle_lights,
+                record_accel_trails.after(update_vehicle_transforms),
+                draw_accel_trails,
+                update_camera.after(update_vehicle_transforms),
                 player_input,
+                adjust_simulation_speed,
                 handle_selection,
                 draw_selected_vehicle_debug,
                 draw_selected_segment_debug,
@@ -73,20 +228,14 @@ fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // Isometric camera setup
-    // Classic isometric: 45° rotation around vertical, ~30° elevation angle
-    let distance = 120.0;
-    let look_at = Vec3::new(80.0, 0.0, 0.0); // Center between roundabout and intersection
-
+    // Isometric camera, refitted to the road by fit_camera_to_road once it exists
     commands.spawn((
         Camera3d::default(),
         Projection::from(OrthographicProjection {
-            scale: 0.18, // Zoomed out to see both intersections
+            scale: DEFAULT_CAMERA_SCALE,
             ..OrthographicProjection::default_3d()
         }),
-        // Position camera at isometric angle: (d, -d, d*0.7) looking at center
-        Transform::from_xyz(look_at.x + distance, look_at.y - distance, distance * 0.7)
-            .looking_at(look_at, Vec3::Z),
+        isometric_camera(Vec3::ZERO, MIN_CAMERA_DISTANCE),
     ));
 
     // Simulate directional light with distant point light
@@ -144,8 +293,10 @@ pub fn test_intersection(mut commands: Commands) {
         road.add_intersection_node(Vec3::new(0.0, 0.0, 0.0), YieldResolver::Roundabout);
 
     // Regular intersection to the east
-    let intersection =
-        road.add_intersection_node(Vec3::new(spacing * 2.0, 0.0, 0.0), YieldResolver::RightOfWay);
+    let intersection = road.add_intersection_node(
+        Vec3::new(spacing * 2.0, 0.0, 0.0),
+        YieldResolver::RightOfWay(SidePriority::RIGHT_HAND),
+    );
 
     // Edge nodes around the roundabout
     let edge_n = road.add_edge_node(Vec3::new(0.0, spacing, 0.0));
@@ -192,7 +343,12 @@ fn spawn_road_meshes(
         let from = road.nodes.get(&segment.from);
         let to = road.nodes.get(&segment.to);
 
-        let mesh = build_segment_mesh(&segment.geometry, from.position, to.position, LANE_WIDTH);
+        let mesh = build_segment_mesh(
+            &segment.geometry,
+            from.position,
+            to.position,
+            LANE_WIDTH * segment.lanes.max(1) as f32,
+        );
 
         commands.spawn((
             Mesh3d(meshes.add(mesh)),
@@ -207,7 +363,7 @@ fn spawn_road_meshes(
 fn build_segment_mesh(geometry: &SegmentGeometry, from: Vec3, to: Vec3, width: f32) -> Mesh {
     let steps = match geometry {
         SegmentGeometry::Straight => 1,
-        SegmentGeometry::Curved { .. } => 16,
+        SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => 16,
     };
 
     let mut positions: Vec<[f32; 3]> = Vec::with_capacity((steps + 1) * 2);
@@ -217,17 +373,12 @@ fn build_segment_mesh(geometry: &SegmentGeometry, from: Vec3, to: Vec3, width: f
 
     let half_width = width / 2.0;
 
-    for i in 0..=steps {
+    for (i, (center, tangent)) in geometry
+        .sample_with_tangents(from, to, steps)
+        .into_iter()
+        .enumerate()
+    {
         let t = i as f32 / steps as f32;
-        let center = geometry.position_at(from, to, t);
-
-        // Calculate tangent direction
-        let epsilon = 0.001;
-        let t0 = (t - epsilon).max(0.0);
-        let t1 = (t + epsilon).min(1.0);
-        let p0 = geometry.position_at(from, to, t0);
-        let p1 = geometry.position_at(from, to, t1);
-        let tangent = (p1 - p0).normalize_or_zero();
 
         // Perpendicular (90° rotation in XY plane)
         let perp = Vec3::new(-tangent.y, tangent.x, 0.0);
@@ -274,15 +425,8 @@ fn draw_edge_lines(mut gizmos: Gizmos, road: Res<Road>) {
     let half_width = LANE_WIDTH / 2.0;
     let edge_color = Color::linear_rgb(0.9, 0.9, 0.9); // White
 
-    // Collect all intersection edge nodes for checking
-    let intersection_nodes: Vec<_> = road
-        .intersections
-        .iter()
-        .flat_map(|i| i.edge_nodes.iter().copied())
-        .collect();
-
     // Draw all segment edge lines
-    for (seg_id, segment) in road.segments.iter_with_ids() {
+    for segment in road.segments.iter() {
         let from = road.nodes.get(&segment.from);
         let to = road.nodes.get(&segment.to);
 
@@ -329,15 +473,12 @@ fn draw_edge_lines(mut gizmos: Gizmos, road: Res<Road>) {
 
         let steps = match segment.geometry {
             SegmentGeometry::Straight => 1,
-            SegmentGeometry::Curved { .. } => 16,
+            SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => 16,
         };
 
-        for i in 0..steps {
-            let t0 = i as f32 / steps as f32;
-            let t1 = (i + 1) as f32 / steps as f32;
-
-            let c0 = segment.geometry.position_at(from.position, to.position, t0);
-            let c1 = segment.geometry.position_at(from.position, to.position, t1);
+        let points = segment.geometry.sample(from.position, to.position, steps);
+        for (i, pair) in points.windows(2).enumerate() {
+            let (c0, c1) = (pair[0], pair[1]);
 
             let tangent = (c1 - c0).normalize_or_zero();
             let perp = Vec3::new(-tangent.y, tangent.x, 0.0);
@@ -398,6 +539,7 @@ fn spawn_vehicle_meshes(
 
         commands.entity(entity).insert((
             VehicleRender,
+            AccelTrail::default(),
             Mesh3d(assets.mesh.clone()),
             MeshMaterial3d(material),
             Transform::default(),
@@ -406,36 +548,92 @@ fn spawn_vehicle_meshes(
     }
 }
 
-/// Update vehicle mesh transforms based on simulation state
+type SimulatedVehicles<'w, 's> =
+    Query<'w, 's, (Entity, &'static Vehicle), (With<VehicleRender>, Without<FreeDrive>)>;
+
+/// Remember where each vehicle was before the simulation steps it
+fn record_previous_poses(mut commands: Commands, vehicles: SimulatedVehicles, road: Res<Road>) {
+    for (entity, vehicle) in &vehicles {
+        let (translation, rotation) = vehicle_pose(&road, vehicle);
+        commands.entity(entity).insert(PreviousPose {
+            translation,
+            rotation,
+        });
+    }
+}
+
+type RenderedVehicles<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Vehicle,
+        &'static mut Transform,
+        Option<&'static FreeDrive>,
+        Option<&'static PreviousPose>,
+    ),
+    With<VehicleRender>,
+>;
+
+/// Update vehicle mesh transforms, interpolating between the last two simulation steps
 fn update_vehicle_transforms(
-    mut vehicles: Query<(&Vehicle, &mut Transform), With<VehicleRender>>,
+    mut vehicles: RenderedVehicles,
     road: Res<Road>,
+    fixed_time: Res<Time<Fixed>>,
 ) {
-    for (vehicle, mut transform) in &mut vehicles {
-        let segment = road.segments.get(&vehicle.segment);
-        let from = road.nodes.get(&segment.from);
-        let to = road.nodes.get(&segment.to);
+    let alpha = fixed_time.overstep_fraction();
+    for (vehicle, mut transform, free_drive, previous) in &mut vehicles {
+        let scale = Vec3::new(vehicle.length, vehicle.width, CAR_HEIGHT);
+        if let Some(free) = free_drive {
+            *transform = Transform::from_translation(free.position + Vec3::Z * (CAR_HEIGHT / 2.0))
+                .with_rotation(Quat::from_rotation_z(free.heading.y.atan2(free.heading.x)))
+                .with_scale(scale);
+            continue;
+        }
 
-        let position = segment
-            .geometry
-            .position_at(from.position, to.position, vehicle.progress);
+        let (mut translation, mut rotation) = vehicle_pose(&road, vehicle);
+        if let Some(previous) = previous {
+            translation = previous.translation.lerp(translation, alpha);
+            rotation = previous.rotation.slerp(rotation, alpha);
+        }
+        *transform = Transform::from_translation(translation)
+            .with_rotation(rotation)
+            .with_scale(scale);
+    }
+}
 
-        // Calculate heading
-        let epsilon = 0.01;
-        let t0 = (vehicle.progress - epsilon).max(0.0);
-        let t1 = (vehicle.progress + epsilon).min(1.0);
-        let p0 = segment.geometry.position_at(from.position, to.position, t0);
-        let p1 = segment.geometry.position_at(from.position, to.position, t1);
-        let direction = (p1 - p0).normalize_or_zero();
-        let angle = direction.y.atan2(direction.x);
+/// Center and orientation of a vehicle's mesh on the road
+fn vehicle_pose(road: &Road, vehicle: &Vehicle) -> (Vec3, Quat) {
+    let segment = road.segments.get(&vehicle.segment);
+    let from = road.nodes.get(&segment.from);
+    let to = road.nodes.get(&segment.to);
+
+    let position = segment.geometry.position_in_lane(
+        from.position,
+        to.position,
+        vehicle.progress,
+        vehicle.lane,
+        segment.lanes,
+    );
 
-        // Position at center of car (raised by half height)
-        let car_center = position + Vec3::Z * (CAR_HEIGHT / 2.0);
+    // Calculate heading
+    let epsilon = 0.01;
+    let t0 = (vehicle.progress - epsilon).max(0.0);
+    let t1 = (vehicle.progress + epsilon).min(1.0);
+    let p0 = segment.geometry.position_at(from.position, to.position, t0);
+    let p1 = segment.geometry.position_at(from.position, to.position, t1);
+    let direction = (p1 - p0).normalize_or_zero();
+    let angle = direction.y.atan2(direction.x);
 
-        *transform = Transform::from_translation(car_center)
-            .with_rotation(Quat::from_rotation_z(angle))
-            .with_scale(Vec3::new(vehicle.length, vehicle.width, CAR_HEIGHT));
-    }
+    // Position at center of car (raised by half height)
+    let car_center = position + Vec3::Z * (CAR_HEIGHT / 2.0);
+
+    // Lean into curves
+    let bank = segment.geometry.bank_angle(vehicle.speed);
+
+    (
+        car_center,
+        Quat::from_rotation_z(angle) * Quat::from_rotation_x(bank),
+    )
 }
 
 /// Draw vehicle lights (blinkers, brake lights) using gizmos
@@ -485,21 +683,132 @@ fn draw_vehicle_lights(
                     gizmos.sphere(front_right, light_size, blinker_color);
                     gizmos.sphere(rear_right, light_size, blinker_color);
                 }
+                Blinker::Hazard => {
+                    for light in [front_left, front_right, rear_left, rear_right] {
+                        gizmos.sphere(light, light_size, blinker_color);
+                    }
+                }
                 Blinker::None => {}
             }
         }
     }
 }
 
+/// Sample each vehicle's position and acceleration into its trail
+fn record_accel_trails(
+    mut vehicles: Query<(&Vehicle, &Transform, &mut AccelTrail)>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut show: ResMut<ShowAccelTrails>,
+    time: Res<Time>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        show.0 = !show.0;
+    }
+
+    let dt = time.delta_secs();
+    for (vehicle, transform, mut trail) in &mut vehicles {
+        trail.since_sample += dt;
+        if trail.since_sample < TRAIL_INTERVAL {
+            continue;
+        }
+
+        let elapsed = std::mem::take(&mut trail.since_sample);
+        let accel = (vehicle.speed - trail.last_speed) / elapsed;
+        trail.last_speed = vehicle.speed;
+
+        let position = transform.translation - Vec3::Z * (CAR_HEIGHT / 2.0);
+
+        // Segment transitions can snap the vehicle between lane geometries; don't draw
+        // that jump as if the vehicle drove it
+        if trail.last_segment != Some(vehicle.segment) {
+            trail.last_segment = Some(vehicle.segment);
+            let jumped = trail.samples.back().is_some_and(|(last, _)| {
+                last.distance(position) > vehicle.speed.max(1.0) * elapsed * 2.0
+            });
+            if jumped {
+                trail.samples.clear();
+            }
+        }
+
+        if trail.samples.len() == TRAIL_SAMPLES {
+            trail.samples.pop_front();
+        }
+        trail.samples.push_back((position, accel));
+    }
+}
+
+/// Draw fading trails: green while accelerating, red while braking
+fn draw_accel_trails(mut gizmos: Gizmos, vehicles: Query<&AccelTrail>, show: Res<ShowAccelTrails>) {
+    if !show.0 {
+        return;
+    }
+
+    let lift = Vec3::Z * 0.1;
+    for trail in &vehicles {
+        let count = trail.samples.len();
+        for (i, pair) in trail
+            .samples
+            .iter()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .enumerate()
+        {
+            let (&(p0, _), &(p1, accel)) = (pair[0], pair[1]);
+            let alpha = (i + 1) as f32 / count as f32;
+            let t = (accel / TRAIL_FULL_ACCEL).clamp(-1.0, 1.0);
+            let color = if t >= 0.0 {
+                Color::linear_rgba(1.0 - t, 1.0, 1.0 - t, alpha)
+            } else {
+                Color::linear_rgba(1.0, 1.0 + t, 1.0 + t, alpha)
+            };
+            gizmos.line(p0 + lift, p1 + lift, color);
+        }
+    }
+}
+
+/// Drive the player vehicle. Key F toggles free-drive, where WASD moves it in world space
+/// off the road; toggling back snaps it to the nearest segment.
 fn player_input(
+    mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut player: Query<&mut Vehicle, With<PlayerControlled>>,
+    mut player: Query<(Entity, &mut Vehicle, Option<&mut FreeDrive>), With<PlayerControlled>>,
+    road: Res<Road>,
     time: Res<Time>,
 ) {
-    let Ok(mut vehicle) = player.single_mut() else {
+    let Ok((entity, mut vehicle, free_drive)) = player.single_mut() else {
         return;
     };
 
+    if let Some(mut free) = free_drive {
+        if keyboard.just_pressed(KeyCode::KeyF) {
+            if reattach(&road, &mut vehicle, free.position) {
+                commands.entity(entity).remove::<FreeDrive>();
+            }
+            return;
+        }
+
+        let mut direction = Vec3::ZERO;
+        for (key, step) in [
+            (KeyCode::KeyW, Vec3::Y),
+            (KeyCode::KeyS, Vec3::NEG_Y),
+            (KeyCode::KeyA, Vec3::NEG_X),
+            (KeyCode::KeyD, Vec3::X),
+        ] {
+            if keyboard.pressed(key) {
+                direction += step;
+            }
+        }
+        free.drive(direction, time.delta_secs());
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        commands
+            .entity(entity)
+            .insert(FreeDrive::detach(&road, &vehicle));
+        return;
+    }
+
     if keyboard.pressed(KeyCode::KeyW) || keyboard.pressed(KeyCode::ArrowUp) {
         vehicle.speed += 5.0 * time.delta_secs(); // Accelerate
     }
@@ -507,16 +816,48 @@ fn player_input(
         vehicle.speed -= 8.0 * time.delta_secs(); // Brake
     }
 
-    vehicle.speed = vehicle.speed.clamp(0.0, 10.0);
+    vehicle.speed = vehicle.speed.clamp(0.0, player_max_speed(&road, &vehicle));
+}
+
+/// Halve or double the simulation speed with [ and ]; Space pauses and resumes
+fn adjust_simulation_speed(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut speed: ResMut<SimulationSpeed>,
+    mut paused_at: Local<Option<f32>>,
+) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        match paused_at.take() {
+            Some(previous) => speed.0 = previous,
+            None => {
+                *paused_at = Some(speed.0);
+                speed.0 = 0.0;
+            }
+        }
+    }
+    if paused_at.is_some() {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        speed.0 = (speed.0 / 2.0).max(MIN_SIMULATION_SPEED);
+    }
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        speed.0 = (speed.0 * 2.0).min(MAX_SIMULATION_SPEED);
+    }
 }
 
-/// Handle mouse clicks to select vehicles or segments for debug inspection
+/// Handle mouse clicks to select vehicles or segments for debug inspection.
+/// Shift-clicking a vehicle also freezes or unfreezes it in place.
+#[allow(clippy::too_many_arguments)]
 fn handle_selection(
+    mut commands: Commands,
     mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
     window: Query<&Window, With<PrimaryWindow>>,
     camera: Query<(&Camera, &GlobalTransform)>,
     vehicles: Query<(Entity, &Vehicle, &Transform), With<VehicleRender>>,
     all_vehicles: Query<(Entity, &Vehicle)>,
+    frozen: Query<Has<Frozen>>,
     mut selected_vehicle: ResMut<SelectedVehicle>,
     mut selected_segment: ResMut<SelectedSegment>,
     road: Res<Road>,
@@ -563,6 +904,14 @@ fn handle_selection(
         selected_vehicle.0 = Some(entity);
         selected_segment.0 = None;
 
+        if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            if frozen.get(entity).unwrap_or(false) {
+                commands.entity(entity).remove::<Frozen>();
+            } else {
+                commands.entity(entity).insert(Frozen);
+            }
+        }
+
         // Log debug info to console
         if let Ok((_, vehicle)) = all_vehicles.get(entity) {
             log_vehicle_debug(entity, vehicle, &all_vehicles, &road);
@@ -571,40 +920,24 @@ fn handle_selection(
     }
 
     // No vehicle clicked - check for segment
-    let mut nearest_segment: Option<(Id<Segment>, f32)> = None;
-    for (seg_id, segment) in road.segments.iter_with_ids() {
-        let from = road.nodes.get(&segment.from).position;
-        let to = road.nodes.get(&segment.to).position;
+    let nearest_segment = road
+        .nearest_segment(world_pos.with_z(0.0))
+        .filter(|(_, _, distance)| *distance < LANE_WIDTH);
 
-        // Sample points along the segment to find distance
-        let steps = match segment.geometry {
-            SegmentGeometry::Straight => 4,
-            SegmentGeometry::Curved { .. } => 16,
-        };
-
-        for i in 0..=steps {
-            let t = i as f32 / steps as f32;
-            let pos = segment.geometry.position_at(from, to, t);
-            let dist = ((world_pos.x - pos.x).powi(2) + (world_pos.y - pos.y).powi(2)).sqrt();
-
-            if dist < LANE_WIDTH {
-                if nearest_segment.is_none() || dist < nearest_segment.unwrap().1 {
-                    nearest_segment = Some((seg_id, dist));
-                }
-            }
-        }
-    }
-
-    if let Some((seg_id, _)) = nearest_segment {
+    if let Some((seg_id, _, _)) = nearest_segment {
         selected_segment.0 = Some(seg_id);
         selected_vehicle.0 = None;
 
         // Log segment info
         let segment = road.segments.get(&seg_id);
-        let mut output = format!("\n=== Segment Debug ===\nSegment: {:?}\n", seg_id);
+        let mut output = format!(
+            "\n=== Segment Debug ===\nSegment: {}\n",
+            road.segment_label(seg_id)
+        );
         output.push_str(&format!(
-            "From: {:?} -> To: {:?}\n",
-            segment.from, segment.to
+            "From: {} -> To: {}\n",
+            road.node_label(segment.from),
+            road.node_label(segment.to)
         ));
         output.push_str(&format!("Length: {:.1}m\n", segment.length));
 
@@ -638,16 +971,19 @@ fn log_vehicle_debug(
     output.push_str(&format!("Entity: {:?}\n", entity));
     output.push_str(&format!("Speed: {:.2} m/s\n", vehicle.speed));
     output.push_str(&format!("Progress: {:.2}\n", vehicle.progress));
-    output.push_str(&format!("Segment: {:?}", vehicle.segment));
+    output.push_str(&format!("Segment: {}", road.segment_label(vehicle.segment)));
 
     if let Some(next_seg) = vehicle.route.get(1) {
-        output.push_str(&format!(" -> Next: {:?}\n", next_seg));
+        output.push_str(&format!(" -> Next: {}\n", road.segment_label(*next_seg)));
     } else {
         output.push_str(" -> (no next segment)\n");
     }
 
     // Log full route with segment details
-    output.push_str(&format!("Destination: {:?}\n", vehicle.destination));
+    output.push_str(&format!(
+        "Destination: {}\n",
+        road.node_label(vehicle.destination)
+    ));
     output.push_str(&format!("\nRoute ({} segments):\n", vehicle.route.len()));
     for (i, seg_id) in vehicle.route.iter().enumerate() {
         let seg = road.segments.get(seg_id);
@@ -655,9 +991,9 @@ fn log_vehicle_debug(
         let to_pos = road.nodes.get(&seg.to).position;
         let marker = if i == 0 { " <-- current" } else { "" };
         output.push_str(&format!(
-            "  [{}] {:?}: {:?} ({:.0},{:.0}) -> ({:.0},{:.0}), len={:.1}m{}\n",
+            "  [{}] {}: {:?} ({:.0},{:.0}) -> ({:.0},{:.0}), len={:.1}m{}\n",
             i,
-            seg_id,
+            road.segment_label(*seg_id),
             seg.turn_type,
             from_pos.x,
             from_pos.y,
@@ -686,7 +1022,6 @@ fn log_vehicle_debug(
                         output.push_str("\nConflicts:\n");
 
                         let my_arrival = vehicle.gap.arrival_order.unwrap_or(u32::MAX);
-                        let my_waiting = vehicle.gap.waiting_time.unwrap_or(0.0);
 
                         for (other_entity, other_vehicle) in all_vehicles.iter() {
                             if other_entity == entity {
@@ -786,15 +1121,11 @@ fn draw_selected_vehicle_debug(
         // Draw the segment path
         let steps = match seg.geometry {
             SegmentGeometry::Straight => 1,
-            SegmentGeometry::Curved { .. } => 12,
+            SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => 12,
         };
 
-        for j in 0..steps {
-            let t0 = j as f32 / steps as f32;
-            let t1 = (j + 1) as f32 / steps as f32;
-            let p0 = seg.geometry.position_at(from_pos, to_pos, t0) + route_z;
-            let p1 = seg.geometry.position_at(from_pos, to_pos, t1) + route_z;
-            gizmos.line(p0, p1, color);
+        for pair in seg.geometry.sample(from_pos, to_pos, steps).windows(2) {
+            gizmos.line(pair[0] + route_z, pair[1] + route_z, color);
         }
 
         // Draw segment index number at midpoint
@@ -924,18 +1255,14 @@ fn draw_segment_gizmo(
 ) {
     let steps = match geometry {
         SegmentGeometry::Straight => 1,
-        SegmentGeometry::Curved { .. } => 16,
+        SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => 16,
     };
 
     let z_offset = Vec3::Z * 0.5;
     let half_width = LANE_WIDTH / 2.0;
 
-    for i in 0..steps {
-        let t0 = i as f32 / steps as f32;
-        let t1 = (i + 1) as f32 / steps as f32;
-
-        let c0 = geometry.position_at(from, to, t0);
-        let c1 = geometry.position_at(from, to, t1);
+    for pair in geometry.sample(from, to, steps).windows(2) {
+        let (c0, c1) = (pair[0], pair[1]);
 
         let tangent = (c1 - c0).normalize_or_zero();
         let perp = Vec3::new(-tangent.y, tangent.x, 0.0);
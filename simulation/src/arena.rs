@@ -2,24 +2,40 @@ use std::fmt::{Debug, Display};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Arena<T> {
-    items: Vec<T>,
+    /// Slots are `None` once their item has been removed
+    items: Vec<Option<T>>,
+    /// Per slot, bumped every time its item is removed so ids handed out earlier go stale
+    generations: Vec<u32>,
+    /// Vacant slots, reused by `alloc` before growing
+    free: Vec<usize>,
 }
 
 pub struct Id<T> {
     _marker: PhantomData<T>,
     pub id: usize,
+    /// Generation of the slot when the id was handed out
+    pub generation: u32,
 }
 
 impl<T> Display for Id<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "#{}", self.id)
+        write!(f, "#{}", self.id)?;
+        if self.generation > 0 {
+            write!(f, "v{}", self.generation)?;
+        }
+        Ok(())
     }
 }
 
 impl<T> Debug for Id<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Id({})", self.id)
+        if self.generation > 0 {
+            write!(f, "Id({}v{})", self.id, self.generation)
+        } else {
+            write!(f, "Id({})", self.id)
+        }
     }
 }
 
@@ -34,21 +50,43 @@ impl<T> Eq for Id<T> {}
 
 impl<T> PartialEq for Id<T> {
     fn eq(&self, other: &Self) -> bool {
-        self._marker == other._marker && self.id == other.id
+        self._marker == other._marker && self.id == other.id && self.generation == other.generation
     }
 }
 
 impl<T> Hash for Id<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+/// Serialized as an `(index, generation)` pair
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Id<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.id, self.generation).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Id<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (id, generation) = <(usize, u32)>::deserialize(deserializer)?;
+        Ok(Self::with_generation(id, generation))
     }
 }
 
 impl<T> Id<T> {
     pub const fn new(id: usize) -> Self {
+        Self::with_generation(id, 0)
+    }
+
+    pub const fn with_generation(id: usize, generation: u32) -> Self {
         Self {
             _marker: PhantomData,
             id,
+            generation,
         }
     }
 }
@@ -61,70 +99,233 @@ impl<T> Default for Arena<T> {
 
 impl<T> Arena<T> {
     pub fn new() -> Self {
-        Self { items: vec![] }
+        Self {
+            items: vec![],
+            generations: vec![],
+            free: vec![],
+        }
     }
 
+    /// Store an item, reusing a vacant slot if there is one
     pub fn alloc(&mut self, item: T) -> Id<T> {
-        let id = Id::new(self.items.len());
-        self.items.push(item);
-        id
+        if let Some(index) = self.free.pop() {
+            self.items[index] = Some(item);
+            return Id::with_generation(index, self.generations[index]);
+        }
+
+        self.items.push(Some(item));
+        self.generations.push(0);
+        Id::new(self.items.len() - 1)
+    }
+
+    /// Remove an item, leaving its slot vacant. Returns `None` if it was already removed.
+    pub fn remove(&mut self, id: &Id<T>) -> Option<T> {
+        if self.generations.get(id.id) != Some(&id.generation) {
+            return None;
+        }
+
+        let item = self.items[id.id].take()?;
+        self.generations[id.id] += 1;
+        self.free.push(id.id);
+        Some(item)
     }
 
+    /// Put a removed item back under the id it had, so ids handed out before the removal
+    /// resolve to it again. The slot may have been reused and freed again in between, as
+    /// when undoing a series of edits; panics if it is occupied.
+    pub fn restore(&mut self, id: &Id<T>, item: T) {
+        assert!(
+            self.items.get(id.id).is_some_and(Option::is_none)
+                && self.generations[id.id] >= id.generation,
+            "cannot restore {id:?}: its slot is taken"
+        );
+        self.generations[id.id] = id.generation;
+        self.free.retain(|&index| index != id.id);
+        self.items[id.id] = Some(item);
+    }
+
+    /// Panics if the item was removed, including when its slot has since been reused
     pub fn get(&self, id: &Id<T>) -> &T {
-        self.items.get(id.id).unwrap()
+        self.get_checked(id)
+            .unwrap_or_else(|| panic!("stale or unknown id {id:?}"))
     }
 
+    /// Panics if the item was removed, including when its slot has since been reused
     pub fn get_mut(&mut self, id: &Id<T>) -> &mut T {
-        self.items.get_mut(id.id).unwrap()
+        self.get_mut_checked(id)
+            .unwrap_or_else(|| panic!("stale or unknown id {id:?}"))
+    }
+
+    /// The item, or `None` if it was removed
+    pub fn get_checked(&self, id: &Id<T>) -> Option<&T> {
+        if self.generations.get(id.id) != Some(&id.generation) {
+            return None;
+        }
+        self.items[id.id].as_ref()
+    }
+
+    pub fn get_mut_checked(&mut self, id: &Id<T>) -> Option<&mut T> {
+        if self.generations.get(id.id) != Some(&id.generation) {
+            return None;
+        }
+        self.items[id.id].as_mut()
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &T> {
-        self.items.iter()
+        self.items.iter().flatten()
     }
 
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
-        self.items.iter_mut()
+        self.items.iter_mut().flatten()
     }
 
     pub fn iter_with_ids(&self) -> impl Iterator<Item = (Id<T>, &T)> {
         self.items
             .iter()
+            .zip(&self.generations)
             .enumerate()
-            .map(|(i, item)| (Id::new(i), item))
+            .filter_map(|(i, (item, &generation))| {
+                item.as_ref()
+                    .map(|item| (Id::with_generation(i, generation), item))
+            })
     }
 
+    /// Number of live items
     pub fn len(&self) -> usize {
-        self.items.len()
+        self.items.len() - self.free.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.items.is_empty()
+        self.len() == 0
     }
 }
 
 impl<T> IntoIterator for Arena<T> {
     type Item = T;
-    type IntoIter = std::vec::IntoIter<T>;
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Option<T>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.items.into_iter()
+        self.items.into_iter().flatten()
     }
 }
 
 impl<'a, T> IntoIterator for &'a Arena<T> {
     type Item = &'a T;
-    type IntoIter = std::slice::Iter<'a, T>;
+    type IntoIter = std::iter::Flatten<std::slice::Iter<'a, Option<T>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.items.iter()
+        self.items.iter().flatten()
     }
 }
 
 impl<'a, T> IntoIterator for &'a mut Arena<T> {
     type Item = &'a mut T;
-    type IntoIter = std::slice::IterMut<'a, T>;
+    type IntoIter = std::iter::Flatten<std::slice::IterMut<'a, Option<T>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.items.iter_mut()
+        self.items.iter_mut().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reused_slot_invalidates_old_id() {
+        let mut arena = Arena::new();
+        let first = arena.alloc("first");
+        let kept = arena.alloc("kept");
+
+        assert_eq!(arena.remove(&first), Some("first"));
+        assert_eq!(arena.get_checked(&first), None);
+        assert_eq!(arena.remove(&first), None);
+
+        // The freed slot is reused under a new generation
+        let second = arena.alloc("second");
+        assert_eq!(second.id, first.id);
+        assert_ne!(second, first);
+        assert_eq!(arena.get_checked(&first), None);
+        assert_eq!(arena.remove(&first), None);
+        assert_eq!(arena.get(&second), &"second");
+        assert_eq!(arena.get(&kept), &"kept");
+        assert_eq!(arena.len(), 2);
+
+        let ids: Vec<_> = arena.iter_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![second, kept]);
+    }
+
+    #[test]
+    fn test_len_and_iteration_skip_removed_items() {
+        let mut arena = Arena::new();
+        let ids: Vec<_> = (0..5).map(|i| arena.alloc(i)).collect();
+        arena.remove(&ids[1]);
+        arena.remove(&ids[3]);
+
+        assert_eq!(arena.len(), 3);
+        assert_eq!(arena.iter().copied().collect::<Vec<_>>(), vec![0, 2, 4]);
+        assert_eq!(
+            arena.iter_with_ids().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![ids[0], ids[2], ids[4]]
+        );
+
+        // Both vacant slots are refilled before the arena grows
+        let refilled = [arena.alloc(10), arena.alloc(11)];
+        let mut slots: Vec<_> = refilled.iter().map(|id| id.id).collect();
+        slots.sort();
+        assert_eq!(slots, vec![1, 3]);
+        assert_eq!(arena.alloc(12).id, 5);
+        assert_eq!(arena.len(), 6);
+        assert_eq!(arena.into_iter().count(), 6);
+    }
+
+    #[test]
+    fn test_restore_revives_old_id() {
+        let mut arena = Arena::new();
+        let first = arena.alloc("first");
+        let second = arena.alloc("second");
+        arena.remove(&first);
+
+        arena.restore(&first, "first again");
+        assert_eq!(arena.get(&first), &"first again");
+        assert_eq!(arena.get(&second), &"second");
+        // The slot is live again, so alloc grows instead of reusing it
+        assert_eq!(arena.alloc("third").id, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "taken")]
+    fn test_restore_panics_while_slot_is_taken() {
+        let mut arena = Arena::new();
+        let first = arena.alloc("first");
+        arena.remove(&first);
+        arena.alloc("reused");
+        arena.restore(&first, "first");
+    }
+
+    #[test]
+    fn test_checked_access_rejects_unknown_and_freed_ids() {
+        let mut arena = Arena::new();
+        let id = arena.alloc(1);
+        let out_of_range = Id::<i32>::new(7);
+        assert_eq!(arena.get_checked(&out_of_range), None);
+        assert_eq!(arena.get_mut_checked(&out_of_range), None);
+
+        *arena.get_mut_checked(&id).unwrap() += 1;
+        assert_eq!(arena.get_checked(&id), Some(&2));
+
+        arena.remove(&id);
+        assert_eq!(arena.get_checked(&id), None);
+        assert_eq!(arena.get_mut_checked(&id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "stale")]
+    fn test_get_panics_on_stale_id() {
+        let mut arena = Arena::new();
+        let id = arena.alloc(1);
+        arena.remove(&id);
+        arena.alloc(2);
+        arena.get(&id);
     }
 }
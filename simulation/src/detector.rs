@@ -0,0 +1,126 @@
+//! Virtual loop detectors reporting presence and counting vehicle crossings.
+//!
+//! Units:
+//! - Position along a segment: progress (0.0 to 1.0)
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::prelude::*;
+
+use crate::{
+    driver::{FreeDrive, Vehicle},
+    Id, Road, Segment,
+};
+
+/// A detection point on a segment, see [`Road::add_detector`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Detector {
+    pub segment: Id<Segment>,
+    pub progress: f32,
+}
+
+#[derive(Default, Debug)]
+pub struct DetectorState {
+    /// A vehicle body currently covers the detector
+    pub occupied: bool,
+    /// Number of vehicles that have reached the detector so far
+    pub count: u32,
+    /// Vehicles covering the detector as of the last update
+    vehicles: HashSet<Entity>,
+}
+
+#[derive(Resource, Default)]
+pub struct DetectorStates {
+    pub states: HashMap<Id<Detector>, DetectorState>,
+}
+
+impl DetectorStates {
+    pub fn get(&self, detector: &Id<Detector>) -> Option<&DetectorState> {
+        self.states.get(detector)
+    }
+}
+
+impl Road {
+    /// Place a detector at `progress` along a segment
+    pub fn add_detector(&mut self, segment: Id<Segment>, progress: f32) -> Id<Detector> {
+        self.detectors.alloc(Detector {
+            segment,
+            progress: progress.clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// Mark detectors covered by a vehicle body; each vehicle is counted once when it first covers one.
+/// Only vehicles on the detector's segment are considered.
+pub fn update_detectors(
+    road: Res<Road>,
+    vehicles: Query<(Entity, &Vehicle), Without<FreeDrive>>,
+    mut detectors: ResMut<DetectorStates>,
+) {
+    for (id, detector) in road.detectors.iter_with_ids() {
+        let length = road.segments.get(&detector.segment).length.max(0.01);
+
+        let covering: HashSet<Entity> = vehicles
+            .iter()
+            .filter(|(_, v)| v.segment == detector.segment)
+            .filter(|(_, v)| {
+                let rear = v.progress - v.length / length;
+                rear <= detector.progress && detector.progress <= v.progress
+            })
+            .map(|(entity, _)| entity)
+            .collect();
+
+        let state = detectors.states.entry(id).or_default();
+        state.count += covering.difference(&state.vehicles).count() as u32;
+        state.occupied = !covering.is_empty();
+        state.vehicles = covering;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::move_and_despawn_vehicles;
+    use crate::SimulationStats;
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_time::Time;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    #[test]
+    fn test_crossing_counted_once() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(100.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+        let detector = road.add_detector(segment, 0.5);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<DetectorStates>();
+        world.init_resource::<SimulationStats>();
+        world.insert_resource(road);
+
+        let mut vehicle = Vehicle::new(segment, b, vec![segment]);
+        vehicle.progress = 0.3;
+        vehicle.speed = 10.0;
+        world.spawn(vehicle);
+
+        let mut was_occupied = false;
+        for _ in 0..60 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.1));
+            world.run_system_once(move_and_despawn_vehicles).unwrap();
+            world.run_system_once(update_detectors).unwrap();
+
+            let states = world.resource::<DetectorStates>();
+            was_occupied |= states.get(&detector).unwrap().occupied;
+        }
+
+        let state = world.resource::<DetectorStates>().get(&detector).unwrap();
+        assert!(was_occupied);
+        assert!(!state.occupied);
+        assert_eq!(state.count, 1);
+    }
+}
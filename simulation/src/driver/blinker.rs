@@ -1,5 +1,13 @@
-use crate::{driver::Vehicle, Road};
+use crate::{
+    driver::{idm::QUEUE_GAP, Vehicle, VehicleTelemetry, STOPPED_SPEED},
+    sim_dt, Road,
+};
 use bevy_ecs::prelude::*;
+use bevy_time::Time;
+
+/// Seconds a vehicle stands still with nothing to wait for before it switches on its
+/// hazard lights
+pub const HAZARD_DELAY: f32 = 10.0;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Blinker {
@@ -7,11 +15,36 @@ pub enum Blinker {
     None,
     Left,
     Right,
+    /// All four indicators, for a vehicle stopped where traffic doesn't expect it
+    Hazard,
 }
 
-/// Update blinker state based on upcoming turn direction
-pub fn update_blinkers(mut vehicles: Query<&mut Vehicle>, road: Res<Road>) {
-    for mut vehicle in &mut vehicles {
+/// Update blinker state based on upcoming turn direction. Hazard lights override the turn
+/// signal while switched on by hand, or once a vehicle has stood still for
+/// [`HAZARD_DELAY`] seconds without a leader close ahead or a junction to wait for.
+pub fn update_blinkers(
+    time: Res<Time>,
+    mut vehicles: Query<(&mut Vehicle, Option<&VehicleTelemetry>)>,
+    road: Res<Road>,
+) {
+    let dt = sim_dt(&time);
+
+    for (mut vehicle, telemetry) in &mut vehicles {
+        vehicle.stopped_time = if vehicle.speed < STOPPED_SPEED {
+            vehicle.stopped_time + dt
+        } else {
+            0.0
+        };
+
+        // Queued close behind another vehicle or waiting at the stop line is expected
+        let stranded = vehicle.stopped_time > HAZARD_DELAY
+            && vehicle.gap.waiting_time.is_none()
+            && telemetry.is_none_or(|telemetry| telemetry.gap >= QUEUE_GAP);
+        if vehicle.hazards || stranded {
+            vehicle.blinker = Blinker::Hazard;
+            continue;
+        }
+
         // Only check blinkers when approaching end of segment
         if vehicle.progress < 0.5 {
             vehicle.blinker = Blinker::None;
@@ -48,3 +81,86 @@ pub fn update_blinkers(mut vehicles: Query<&mut Vehicle>, road: Res<Road>) {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    #[test]
+    fn test_long_stopped_vehicle_switches_on_hazards() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(100.0, 100.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(road);
+
+        // Broken down mid-segment, with a left turn coming up
+        let stranded = world
+            .spawn(Vehicle::new(ab, c, vec![ab, bc]).with_progress(0.6))
+            .id();
+        // Stopped right behind it
+        let queued = world
+            .spawn((
+                Vehicle::new(ab, c, vec![ab, bc]).with_progress(0.5),
+                VehicleTelemetry {
+                    leader: Some(stranded),
+                    gap: 2.0,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        // Stopped with its leader far up the road, so not queued behind it
+        let distant = world
+            .spawn((
+                Vehicle::new(ab, c, vec![ab, bc]).with_progress(0.1),
+                VehicleTelemetry {
+                    leader: Some(stranded),
+                    gap: 40.0,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        // Stopped at the line, waiting for a gap
+        let mut waiting = Vehicle::new(ab, c, vec![ab, bc]).with_progress(0.99);
+        waiting.gap.waiting_time = Some(0.0);
+        let waiting = world.spawn(waiting).id();
+
+        let blinker = |world: &World, entity| world.get::<Vehicle>(entity).unwrap().blinker;
+        let step = |world: &mut World, seconds: f32| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            world.run_system_once(update_blinkers).unwrap();
+        };
+
+        step(&mut world, HAZARD_DELAY / 2.0);
+        assert_eq!(blinker(&world, stranded), Blinker::Left);
+
+        step(&mut world, HAZARD_DELAY);
+        assert_eq!(blinker(&world, stranded), Blinker::Hazard);
+        assert_eq!(blinker(&world, queued), Blinker::Left);
+        assert_eq!(blinker(&world, distant), Blinker::Hazard);
+        assert_eq!(blinker(&world, waiting), Blinker::Left);
+
+        // Moving off switches them off again
+        world.get_mut::<Vehicle>(stranded).unwrap().speed = 5.0;
+        step(&mut world, 0.05);
+        assert_eq!(blinker(&world, stranded), Blinker::Left);
+
+        // Switched on by hand they stay on, even while driving
+        world
+            .get_mut::<Vehicle>(stranded)
+            .unwrap()
+            .set_hazards(true);
+        step(&mut world, 0.05);
+        assert_eq!(blinker(&world, stranded), Blinker::Hazard);
+    }
+}
@@ -0,0 +1,201 @@
+use bevy_ecs::prelude::*;
+
+use crate::{
+    driver::{DEFAULT_CAR_LENGTH, DEFAULT_CAR_WIDTH},
+    SimRng,
+};
+
+/// Kind of vehicle, determining its size, dynamics and routing restrictions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum VehicleClass {
+    #[default]
+    Car,
+    Truck,
+    Bus,
+    Motorcycle,
+}
+
+/// Physical defaults of a vehicle class. Acceleration, deceleration and spacing are
+/// those of a driver of average aggression; individual drivers spread around them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClassProfile {
+    /// Meters, front to back
+    pub length: f32,
+    /// Meters, side to side
+    pub width: f32,
+    /// m/s²
+    pub max_acceleration: f32,
+    /// m/s²
+    pub comfortable_deceleration: f32,
+    /// Bumper-to-bumper distance at standstill in meters
+    pub min_spacing: f32,
+}
+
+impl VehicleClass {
+    pub const ALL: [VehicleClass; 4] = [
+        VehicleClass::Car,
+        VehicleClass::Truck,
+        VehicleClass::Bus,
+        VehicleClass::Motorcycle,
+    ];
+
+    pub const fn profile(self) -> ClassProfile {
+        match self {
+            VehicleClass::Car => ClassProfile {
+                length: DEFAULT_CAR_LENGTH,
+                width: DEFAULT_CAR_WIDTH,
+                max_acceleration: 2.0,
+                comfortable_deceleration: 2.25,
+                min_spacing: 1.5,
+            },
+            VehicleClass::Truck => ClassProfile {
+                length: 12.0,
+                width: 2.5,
+                max_acceleration: 0.8,
+                comfortable_deceleration: 1.5,
+                min_spacing: 3.0,
+            },
+            VehicleClass::Bus => ClassProfile {
+                length: 12.0,
+                width: 2.55,
+                max_acceleration: 1.0,
+                comfortable_deceleration: 1.5,
+                min_spacing: 2.5,
+            },
+            VehicleClass::Motorcycle => ClassProfile {
+                length: 2.2,
+                width: 0.8,
+                max_acceleration: 3.0,
+                comfortable_deceleration: 2.5,
+                min_spacing: 1.0,
+            },
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Relative share of each class among spawned vehicles
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct VehicleMix {
+    pub car: f32,
+    pub truck: f32,
+    pub bus: f32,
+    pub motorcycle: f32,
+}
+
+impl Default for VehicleMix {
+    fn default() -> Self {
+        Self {
+            car: 0.85,
+            truck: 0.08,
+            bus: 0.02,
+            motorcycle: 0.05,
+        }
+    }
+}
+
+impl VehicleMix {
+    /// Only ever spawn `class`
+    pub fn only(class: VehicleClass) -> Self {
+        let mut mix = Self {
+            car: 0.0,
+            truck: 0.0,
+            bus: 0.0,
+            motorcycle: 0.0,
+        };
+        *mix.weight_mut(class) = 1.0;
+        mix
+    }
+
+    pub fn weight(&self, class: VehicleClass) -> f32 {
+        match class {
+            VehicleClass::Car => self.car,
+            VehicleClass::Truck => self.truck,
+            VehicleClass::Bus => self.bus,
+            VehicleClass::Motorcycle => self.motorcycle,
+        }
+    }
+
+    fn weight_mut(&mut self, class: VehicleClass) -> &mut f32 {
+        match class {
+            VehicleClass::Car => &mut self.car,
+            VehicleClass::Truck => &mut self.truck,
+            VehicleClass::Bus => &mut self.bus,
+            VehicleClass::Motorcycle => &mut self.motorcycle,
+        }
+    }
+
+    /// Draw a class in proportion to the weights, falling back to cars if all are zero
+    pub fn sample(&self, rng: &mut SimRng) -> VehicleClass {
+        let total: f32 = VehicleClass::ALL
+            .iter()
+            .map(|&class| self.weight(class).max(0.0))
+            .sum();
+        if total <= 0.0 {
+            return VehicleClass::Car;
+        }
+
+        let mut pick = rng.uniform() * total;
+        for class in VehicleClass::ALL {
+            pick -= self.weight(class).max(0.0);
+            if pick < 0.0 {
+                return class;
+            }
+        }
+        VehicleClass::Car
+    }
+}
+
+/// Set of vehicle classes, e.g. those allowed on a segment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VehicleClasses(u8);
+
+impl VehicleClasses {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u8::MAX);
+
+    pub const fn only(class: VehicleClass) -> Self {
+        Self(class.bit())
+    }
+
+    pub const fn with(self, class: VehicleClass) -> Self {
+        Self(self.0 | class.bit())
+    }
+
+    pub const fn without(self, class: VehicleClass) -> Self {
+        Self(self.0 & !class.bit())
+    }
+
+    pub const fn contains(self, class: VehicleClass) -> bool {
+        self.0 & class.bit() != 0
+    }
+}
+
+impl Default for VehicleClasses {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_mix_is_mostly_cars() {
+        let mix = VehicleMix::default();
+        let mut rng = SimRng::seeded(3);
+        let cars = (0..1000)
+            .filter(|_| mix.sample(&mut rng) == VehicleClass::Car)
+            .count();
+        assert!(cars > 750, "{cars} cars out of 1000");
+        assert!(cars < 1000);
+
+        let trucks = VehicleMix::only(VehicleClass::Truck);
+        assert_eq!(trucks.sample(&mut rng), VehicleClass::Truck);
+    }
+}
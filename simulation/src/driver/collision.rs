@@ -0,0 +1,241 @@
+//! Overlap checks between vehicle bodies, as a diagnostic for the driver models.
+//!
+//! IDM spacing and gap acceptance should keep vehicles apart, so any overlap points at a bug
+//! such as tunneling through a leader on a long step or a misjudged gap. Candidates come from
+//! the [`SpatialHash`], and are only compared with vehicles on the same segment, the segments
+//! right after it, and the turns it conflicts with at an intersection; two-way streets share
+//! a centerline, so oncoming traffic would overlap otherwise. Checks only run while the
+//! [`CollisionDetection`] resource exists.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::prelude::*;
+use glam::{Vec2, Vec3};
+
+use crate::{
+    driver::{FreeDrive, Vehicle},
+    Id, Road, Segment, SpatialHash,
+};
+
+/// Two vehicle bodies started to overlap
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct VehicleCollision {
+    pub a: Entity,
+    pub b: Entity,
+    /// Midpoint between the two vehicles
+    pub location: Vec3,
+}
+
+/// Enables [`detect_collisions`]; insert it to start checking
+#[derive(Resource, Clone, Debug, Default)]
+pub struct CollisionDetection {
+    /// Pairs overlapping as of the last check, so a lasting overlap is only reported once
+    overlapping: HashSet<(Entity, Entity)>,
+}
+
+/// A vehicle's footprint on the ground plane
+#[derive(Clone, Copy, Debug)]
+struct Footprint {
+    center: Vec2,
+    /// Unit vector along the vehicle's heading
+    forward: Vec2,
+    half_length: f32,
+    half_width: f32,
+    height: f32,
+}
+
+impl Footprint {
+    fn of(vehicle: &Vehicle, road: &Road) -> Self {
+        let segment = road.segments.get(&vehicle.segment);
+        let from = road.nodes.get(&segment.from).position;
+        let to = road.nodes.get(&segment.to).position;
+        let center = road.lane_position_on(vehicle.segment, vehicle.progress, vehicle.lane);
+        let forward = segment
+            .geometry
+            .direction_at(from, to, vehicle.progress)
+            .truncate()
+            .try_normalize()
+            .unwrap_or(Vec2::X);
+
+        Self {
+            center: center.truncate(),
+            forward,
+            half_length: vehicle.length / 2.0,
+            half_width: vehicle.width / 2.0,
+            height: center.z,
+        }
+    }
+
+    /// Distance from the center to the farthest corner
+    fn reach(&self) -> f32 {
+        self.half_length.hypot(self.half_width)
+    }
+
+    /// Half the footprint's extent along `axis`
+    fn radius_along(&self, axis: Vec2) -> f32 {
+        self.half_length * self.forward.dot(axis).abs()
+            + self.half_width * self.forward.perp().dot(axis).abs()
+    }
+
+    /// Separating axis test between two oriented rectangles
+    fn overlaps(&self, other: &Footprint) -> bool {
+        let offset = other.center - self.center;
+        [
+            self.forward,
+            self.forward.perp(),
+            other.forward,
+            other.forward.perp(),
+        ]
+        .into_iter()
+        .all(|axis| offset.dot(axis).abs() < self.radius_along(axis) + other.radius_along(axis))
+    }
+}
+
+/// Segments whose vehicles could touch one on `segment`: itself, those continuing from its
+/// end, and the turns it crosses at an intersection
+fn neighbouring_segments<'a>(
+    road: &'a Road,
+    conflicts: &HashMap<Id<Segment>, &'a Vec<Id<Segment>>>,
+    segment: Id<Segment>,
+) -> impl Iterator<Item = Id<Segment>> + 'a {
+    let onward = &road.nodes.get(&road.segments.get(&segment).to).outgoing;
+    let crossing = conflicts.get(&segment).copied().into_iter().flatten();
+    std::iter::once(segment)
+        .chain(onward.iter().copied())
+        .chain(crossing.copied())
+}
+
+pub fn detect_collisions(
+    detection: Option<ResMut<CollisionDetection>>,
+    spatial: Option<Res<SpatialHash>>,
+    road: Res<Road>,
+    vehicles: Query<(Entity, &Vehicle), Without<FreeDrive>>,
+    mut collisions: Option<MessageWriter<VehicleCollision>>,
+) {
+    let Some(mut detection) = detection else {
+        return;
+    };
+
+    let mut footprints = HashMap::<Entity, (Id<Segment>, Footprint)>::new();
+    for (entity, vehicle) in &vehicles {
+        if road.segments.get_checked(&vehicle.segment).is_none() {
+            continue;
+        }
+        footprints.insert(entity, (vehicle.segment, Footprint::of(vehicle, &road)));
+    }
+
+    // Without the resource (e.g. in a bare world), bucket the footprints here
+    let local;
+    let spatial = match spatial.as_deref() {
+        Some(spatial) => spatial,
+        None => {
+            let mut hash = SpatialHash::default();
+            for (&entity, (_, footprint)) in &footprints {
+                hash.insert(entity, footprint.center.extend(footprint.height));
+            }
+            local = hash;
+            &local
+        }
+    };
+
+    let conflicts: HashMap<_, _> = road
+        .intersections
+        .iter()
+        .flat_map(|intersection| &intersection.conflicts)
+        .map(|(segment, conflicts)| (*segment, conflicts))
+        .collect();
+
+    // No two bodies can touch from further apart than twice the largest reach
+    let max_reach = footprints
+        .values()
+        .map(|(_, footprint)| footprint.reach())
+        .fold(0.0, f32::max);
+
+    let mut overlapping = HashSet::new();
+    for (&a, (segment, footprint_a)) in &footprints {
+        let neighbours: HashSet<_> = neighbouring_segments(&road, &conflicts, *segment).collect();
+        let center = footprint_a.center.extend(footprint_a.height);
+        for b in spatial.neighbors_within(center, footprint_a.reach() + max_reach) {
+            let Some((segment_b, footprint_b)) = footprints.get(&b) else {
+                continue;
+            };
+            // Vehicles on different levels, e.g. a bridge over a road, never touch
+            if a == b
+                || !neighbours.contains(segment_b)
+                || (footprint_a.height - footprint_b.height).abs() > 2.0
+            {
+                continue;
+            }
+            if footprint_a.overlaps(footprint_b) {
+                overlapping.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+
+    for &(a, b) in overlapping.difference(&detection.overlapping) {
+        let location = |entity| {
+            let vehicle = vehicles.get(entity).unwrap().1;
+            road.lane_position_on(vehicle.segment, vehicle.progress, vehicle.lane)
+        };
+        let location = (location(a) + location(b)) / 2.0;
+        crate::log!("COLLISION: {a} and {b} overlap at {location:.1}");
+        if let Some(collisions) = collisions.as_mut() {
+            collisions.write(VehicleCollision { a, b, location });
+        }
+    }
+    detection.overlapping = overlapping;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+
+    fn collisions(world: &mut World) -> Vec<VehicleCollision> {
+        world.run_system_once(detect_collisions).unwrap();
+        world
+            .resource_mut::<Messages<VehicleCollision>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn test_overlapping_vehicles_are_reported_once() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.insert_resource(road);
+        world.init_resource::<Messages<VehicleCollision>>();
+        let first = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.5))
+            .id();
+        let second = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.51))
+            .id();
+        world.spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.8));
+
+        // Switched off until the resource exists
+        assert!(collisions(&mut world).is_empty());
+
+        world.init_resource::<CollisionDetection>();
+        let reported = collisions(&mut world);
+        assert_eq!(reported.len(), 1);
+        assert_eq!(
+            (reported[0].a, reported[0].b),
+            (first.min(second), first.max(second))
+        );
+        assert!((reported[0].location - Vec3::new(50.5, 0.0, 0.0)).length() < 1e-3);
+
+        // Still overlapping, but already reported
+        assert!(collisions(&mut world).is_empty());
+
+        // Separated, then forced together again
+        world.get_mut::<Vehicle>(second).unwrap().progress = 0.6;
+        assert!(collisions(&mut world).is_empty());
+        world.get_mut::<Vehicle>(second).unwrap().progress = 0.5;
+        assert_eq!(collisions(&mut world).len(), 1);
+    }
+}
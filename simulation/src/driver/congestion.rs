@@ -0,0 +1,218 @@
+//! Dynamic rerouting around jams.
+//!
+//! Every [`CongestionRerouter::rerouting_interval`] seconds, vehicles with a jammed segment
+//! a few hops ahead replan from the end of their current segment. Segments cost their
+//! free-flow time plus a delay that grows with occupancy density, and a vehicle only
+//! switches when the detour is meaningfully faster than staying in the queue.
+
+use bevy_ecs::prelude::*;
+use bevy_time::Time;
+
+use crate::{
+    driver::{
+        fastest_route_with_delays, CostWeights, FreeDrive, PlayerControlled, RoutingConfig,
+        RoutingMode, SegmentOccupancy, Vehicle,
+    },
+    sim_dt, Id, Road, Segment,
+};
+
+/// Extra free-flow travel times a completely jammed segment costs
+const JAM_DELAY_FACTOR: f32 = 10.0;
+
+#[derive(Resource, Clone, Debug)]
+pub struct CongestionRerouter {
+    /// Seconds of simulated time between rerouting passes
+    pub rerouting_interval: f32,
+    /// Segments of the route, after the current one, checked for jams
+    pub lookahead: usize,
+    /// Density (0.0 to 1.0) from which a segment counts as jammed
+    pub jam_density: f32,
+    /// Fraction of the expected remaining travel time a detour has to save
+    pub min_saving: f32,
+    since_last_pass: f32,
+}
+
+impl Default for CongestionRerouter {
+    fn default() -> Self {
+        Self {
+            rerouting_interval: 5.0,
+            lookahead: 3,
+            jam_density: 0.5,
+            min_saving: 0.15,
+            since_last_pass: 0.0,
+        }
+    }
+}
+
+impl CongestionRerouter {
+    /// Expected seconds to drive `segment` at its current density
+    pub fn expected_time(segment: Id<Segment>, road: &Road, occupancy: &SegmentOccupancy) -> f32 {
+        let free_flow = CostWeights::FASTEST.segment_cost(road.segments.get(&segment));
+        free_flow + Self::delay(segment, road, occupancy)
+    }
+
+    /// Seconds lost on `segment` to congestion, on top of its free-flow time
+    fn delay(segment: Id<Segment>, road: &Road, occupancy: &SegmentOccupancy) -> f32 {
+        let free_flow = CostWeights::FASTEST.segment_cost(road.segments.get(&segment));
+        free_flow * JAM_DELAY_FACTOR * occupancy.density(segment, road)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn reroute_congested_vehicles(
+    time: Res<Time>,
+    rerouter: Option<ResMut<CongestionRerouter>>,
+    road: Res<Road>,
+    occupancy: Res<SegmentOccupancy>,
+    routing: Option<Res<RoutingMode>>,
+    routing_config: Option<Res<RoutingConfig>>,
+    mut vehicles: Query<&mut Vehicle, (Without<FreeDrive>, Without<PlayerControlled>)>,
+) {
+    let Some(mut rerouter) = rerouter else {
+        return;
+    };
+    rerouter.since_last_pass += sim_dt(&time);
+    if rerouter.since_last_pass < rerouter.rerouting_interval {
+        return;
+    }
+    rerouter.since_last_pass = 0.0;
+
+    let routing = routing.as_deref().copied().unwrap_or_default();
+    let routing_config = routing_config.as_deref().copied().unwrap_or_default();
+    // Detours are timed, so the turn penalty counts in seconds whatever the routing mode
+    let delay = |segment| {
+        CongestionRerouter::delay(segment, &road, &occupancy)
+            + routing_config.turn_cost(road.segments.get(&segment))
+    };
+
+    for mut vehicle in &mut vehicles {
+        // Committed to the junction ahead; changing the turn now would undo its clearance
+        if vehicle.gap.waiting_time.is_some() || vehicle.gap.cleared_to_go {
+            continue;
+        }
+
+        let jammed = vehicle
+            .route
+            .iter()
+            .skip(1)
+            .take(rerouter.lookahead)
+            .any(|&segment| occupancy.density(segment, &road) >= rerouter.jam_density);
+        if !jammed {
+            continue;
+        }
+
+        let from = road.segments.get(&vehicle.segment).to;
+        let options = routing.route_options(vehicle.class);
+        let Some((_, onward)) =
+            fastest_route_with_delays(&road, from, vehicle.destination, options, delay)
+        else {
+            continue;
+        };
+
+        let remaining = |route: &[Id<Segment>]| -> f32 {
+            route
+                .iter()
+                .map(|&segment| CongestionRerouter::expected_time(segment, &road, &occupancy))
+                .sum()
+        };
+        let current = remaining(&vehicle.route[1..]);
+        if remaining(&onward) > current * (1.0 - rerouter.min_saving) {
+            continue;
+        }
+
+        let route: Vec<_> = std::iter::once(vehicle.segment).chain(onward).collect();
+        // Stay in the lane we're in; a detour needing a different lane waits for the next pass
+        if !road.lane_serves_route(&route, vehicle.lane) {
+            continue;
+        }
+        vehicle.route = route;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{update_occupancy, Frozen};
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    /// Approach into a fork: a direct 200 m road and a longer detour both end at the
+    /// destination. Returns the road, the approach, the direct pair, the detour pair and
+    /// the destination.
+    #[allow(clippy::type_complexity)]
+    fn fork() -> (
+        Road,
+        Id<Segment>,
+        [Id<Segment>; 2],
+        [Id<Segment>; 2],
+        Id<crate::Node>,
+    ) {
+        let mut road = Road::default();
+        let start = road.add_spawn_node(Vec3::ZERO);
+        let a = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(200.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(200.0, 80.0, 0.0));
+        let d = road.add_despawn_node(Vec3::new(300.0, 0.0, 0.0));
+        let approach = road.add_segment(start, a, 13.9);
+        let direct = [road.add_segment(a, b, 13.9), road.add_segment(b, d, 13.9)];
+        let detour = [road.add_segment(a, c, 13.9), road.add_segment(c, d, 13.9)];
+        (road, approach, direct, detour, d)
+    }
+
+    /// World with the vehicle on the approach planning the direct route; the rerouting
+    /// interval has already elapsed
+    fn world_with(road: Road, vehicle: Vehicle) -> (World, Entity) {
+        let mut world = World::new();
+        world.insert_resource(road);
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<CongestionRerouter>();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs(5));
+        world.insert_resource(time);
+        let entity = world.spawn(vehicle).id();
+        (world, entity)
+    }
+
+    #[test]
+    fn test_blocked_segment_triggers_detour() {
+        let (road, approach, direct, detour, end) = fork();
+        let route = vec![approach, direct[0], direct[1]];
+        let (mut world, vehicle) =
+            world_with(road, Vehicle::new(approach, end, route).with_progress(0.5));
+
+        // Stalled queue filling the direct road's first segment
+        for i in 0..16 {
+            let stalled = Vehicle::new(direct[0], end, vec![direct[0], direct[1]])
+                .with_progress(0.05 + i as f32 * 0.06);
+            world.spawn((stalled, Frozen));
+        }
+        world.run_system_once(update_occupancy).unwrap();
+        world.run_system_once(reroute_congested_vehicles).unwrap();
+
+        let vehicle = world.get::<Vehicle>(vehicle).unwrap();
+        assert_eq!(vehicle.route, vec![approach, detour[0], detour[1]]);
+        assert_eq!(vehicle.destination, end);
+    }
+
+    #[test]
+    fn test_light_traffic_keeps_route() {
+        let (road, approach, direct, _, end) = fork();
+        let route = vec![approach, direct[0], direct[1]];
+        let (mut world, vehicle) = world_with(
+            road,
+            Vehicle::new(approach, end, route.clone()).with_progress(0.5),
+        );
+
+        // A couple of cars aren't a jam; the detour would be slower anyway
+        for progress in [0.3, 0.6] {
+            world.spawn(
+                Vehicle::new(direct[0], end, vec![direct[0], direct[1]]).with_progress(progress),
+            );
+        }
+        world.run_system_once(update_occupancy).unwrap();
+        world.run_system_once(reroute_congested_vehicles).unwrap();
+
+        assert_eq!(world.get::<Vehicle>(vehicle).unwrap().route, route);
+    }
+}
@@ -0,0 +1,108 @@
+//! Unconstrained driving for inspecting the network, detached from the segment/progress model.
+
+use bevy_ecs::prelude::*;
+use glam::Vec3;
+
+use crate::{
+    driver::{route_from, RoutingMode, Vehicle},
+    Road,
+};
+
+/// Speed (m/s) while free-driving
+pub const FREE_DRIVE_SPEED: f32 = 15.0;
+
+/// World-space pose of a vehicle that ignores the road.
+/// Movement, occupancy and gap acceptance skip vehicles carrying this component.
+#[derive(Component, Debug)]
+pub struct FreeDrive {
+    pub position: Vec3,
+    /// Unit direction the vehicle faces
+    pub heading: Vec3,
+}
+
+impl FreeDrive {
+    /// Take over a vehicle at its current position and heading on the road
+    pub fn detach(road: &Road, vehicle: &Vehicle) -> Self {
+        let behind = road.position_on(vehicle.segment, (vehicle.progress - 0.01).max(0.0));
+        let ahead = road.position_on(vehicle.segment, (vehicle.progress + 0.01).min(1.0));
+
+        Self {
+            position: road.lane_position_on(vehicle.segment, vehicle.progress, vehicle.lane),
+            heading: (ahead - behind).try_normalize().unwrap_or(Vec3::X),
+        }
+    }
+
+    /// Move along a world-space `direction` for `dt` seconds; a zero direction stands still
+    pub fn drive(&mut self, direction: Vec3, dt: f32) {
+        if let Some(direction) = direction.try_normalize() {
+            self.position += direction * FREE_DRIVE_SPEED * dt;
+            self.heading = direction;
+        }
+    }
+}
+
+/// Snap a vehicle onto the segment nearest `position` and replan its route from there.
+/// Returns false, leaving the vehicle untouched, if the road has no segments.
+pub fn reattach(road: &Road, vehicle: &mut Vehicle, position: Vec3) -> bool {
+    let Some((segment, progress, _)) = road.nearest_segment(position) else {
+        return false;
+    };
+
+    let options = RoutingMode::default().route_options(vehicle.class);
+    let route =
+        route_from(road, segment, vehicle.destination, options).unwrap_or_else(|| vec![segment]);
+
+    vehicle.segment = segment;
+    vehicle.progress = progress;
+    vehicle.speed = 0.0;
+    vehicle.lane = road.lane_for_route(&route);
+    vehicle.route = route;
+    vehicle.gap.waiting_time = None;
+    vehicle.gap.cleared_to_go = false;
+    vehicle.gap.arrival_order = None;
+    vehicle.gap.arrived_at_line = None;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggling_back_reattaches_to_nearest_segment() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(100.0, 100.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+
+        let mut vehicle = Vehicle::new(ab, c, vec![ab, bc]).with_progress(0.2);
+        let mut free = FreeDrive::detach(&road, &vehicle);
+        assert!((free.position - Vec3::new(20.0, 0.0, 0.0)).length() < 1e-3);
+        assert!((free.heading - Vec3::X).length() < 1e-3);
+
+        // Drive off-road toward the northbound leg
+        free.drive(Vec3::X, 5.0);
+        free.drive(Vec3::Y, 2.0);
+        assert!((free.position - Vec3::new(95.0, 30.0, 0.0)).length() < 1e-3);
+
+        assert!(reattach(&road, &mut vehicle, free.position));
+        assert_eq!(vehicle.segment, bc);
+        assert_eq!(vehicle.route, vec![bc]);
+        assert!((vehicle.progress - 0.3).abs() < 1e-3);
+        assert_eq!(vehicle.speed, 0.0);
+    }
+
+    #[test]
+    fn test_reattach_fails_on_empty_road() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::X);
+        let segment = road.add_segment(a, b, 13.9);
+        let mut vehicle = Vehicle::new(segment, b, vec![segment]);
+
+        assert!(!reattach(&Road::default(), &mut vehicle, Vec3::ZERO));
+        assert_eq!(vehicle.segment, segment);
+    }
+}
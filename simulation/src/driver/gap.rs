@@ -9,55 +9,116 @@ use bevy_ecs::prelude::*;
 use bevy_time::Time;
 
 use crate::{
-    driver::{TurnType, Vehicle},
-    Road,
+    driver::{
+        Approach, EmergencyVehicle, FreeDrive, TrafficControl, TurnType, Vehicle, YieldResolver,
+        YieldingConfig,
+    },
+    sim_dt, sim_time, Id, Road, Segment, SimRng,
 };
 
-/// Minimum physical distance (meters) to approaching vehicle before yielding
-const MIN_SAFE_DISTANCE: f32 = 3.0;
+/// Default rate (per second of waiting) at which the accepted gap shrinks
+pub const DEFAULT_IMPATIENCE: f32 = 0.1;
+
+/// The accepted gap never shrinks below this fraction of `min_gap`
+const MIN_GAP_FLOOR: f32 = 0.5;
+
+/// Front bumper within this distance (m) of the segment end counts as at the stop line
+const STOP_LINE_ZONE: f32 = 2.0;
+
+/// Below this speed (m/s) a vehicle at the line has made its stop at an all-way stop
+pub(crate) const STOPPED_SPEED: f32 = 0.1;
+
+/// A polite driver lets a conflicting vehicle go once it has waited this long (s),
+/// scaled by `1 / politeness`
+const COURTESY_MIN_WAIT: f32 = 5.0;
+
+/// ...and only if it has waited this many times longer than the polite driver itself
+const COURTESY_WAIT_RATIO: f32 = 4.0;
 
 pub struct GapAcceptance {
     pub min_gap: f32,
+    /// How quickly the accepted gap shrinks while waiting (per second)
+    pub impatience: f32,
     pub waiting_time: Option<f32>,
     /// Set when deadlock detection grants priority - skip gap checks until segment transition
     pub cleared_to_go: bool,
     /// Arrival order at intersection for FIFO deadlock resolution
     pub arrival_order: Option<u32>,
+    /// Simulation time the front bumper reached the stop line of the current approach
+    pub arrived_at_line: Option<f32>,
+    /// Willingness (0.0 to 1.0) to let a long-waiting vehicle go despite having priority.
+    /// 0.0 never yields voluntarily.
+    pub politeness: f32,
 }
 
 impl GapAcceptance {
-    pub fn new(aggression: f32) -> Self {
+    /// Driver of the given aggression with a randomly spread accepted gap
+    pub fn new(aggression: f32, rng: &mut SimRng) -> Self {
+        let offset = 0.2 * (rng.uniform() * 2.0 - 1.0);
+        Self::from_params(blend(1.5, 1.0, aggression, offset), DEFAULT_IMPATIENCE, 0.0)
+    }
+
+    /// Driver of the given aggression without random spread
+    pub fn typical(aggression: f32) -> Self {
+        Self::from_params(blend(1.5, 1.0, aggression, 0.0), DEFAULT_IMPATIENCE, 0.0)
+    }
+
+    /// Driver with exactly the given parameters and no intersection state, no randomization
+    pub fn from_params(min_gap: f32, impatience: f32, politeness: f32) -> Self {
         Self {
-            min_gap: blend(1.5, 1.0, aggression, 0.2),
+            min_gap,
+            impatience,
             waiting_time: None,
             cleared_to_go: false,
             arrival_order: None,
+            arrived_at_line: None,
+            politeness,
         }
     }
-}
 
-fn blend(safe_value: f32, aggressive_value: f32, aggression: f32, max_random_range: f32) -> f32 {
-    let random = rand::random::<f32>() * 2.0 - 1.0;
-    let random = max_random_range * random;
+    /// Whether to voluntarily give way to a vehicle that has waited `their_waiting_time`
+    pub fn courtesy_yield(&self, their_waiting_time: f32) -> bool {
+        if self.politeness <= 0.0 {
+            return false;
+        }
 
-    lerp(safe_value, aggressive_value, aggression) + random
+        let my_waiting_time = self.waiting_time.unwrap_or(0.0);
+        let required = COURTESY_MIN_WAIT / self.politeness.min(1.0);
+        their_waiting_time >= required.max(my_waiting_time * COURTESY_WAIT_RATIO)
+    }
+
+    /// Gap (seconds) currently accepted, shrinking the longer the vehicle has been waiting.
+    /// Resets with `waiting_time` when the vehicle moves onto the next segment.
+    pub fn accepted_gap(&self) -> f32 {
+        let waited = self.waiting_time.unwrap_or(0.0);
+        let decay = (-self.impatience * waited).exp().max(MIN_GAP_FLOOR);
+        self.min_gap * decay
+    }
+}
+
+fn blend(safe_value: f32, aggressive_value: f32, aggression: f32, offset: f32) -> f32 {
+    lerp(safe_value, aggressive_value, aggression) + offset
 }
 
 fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + (b - a) * t
 }
 
-// TODO: Store conflict_progress in conflicts HashMap to calculate precise time-to-conflict
-// Currently uses time-to-end-of-segment as approximation
 pub fn apply_gap_acceptance(
     time: Res<Time>,
-    mut vehicles: Query<(Entity, &mut Vehicle)>,
+    mut vehicles: Query<(Entity, &mut Vehicle, Has<EmergencyVehicle>), Without<FreeDrive>>,
     mut road: ResMut<Road>,
+    config: Option<Res<YieldingConfig>>,
 ) {
+    let now = sim_time(&time);
+    let dt = sim_dt(&time);
+    let config = config.as_deref().copied().unwrap_or_default();
+
     // Phase 1: Assign arrival orders to vehicles entering waiting zone (FIFO ordering)
-    for (_entity, mut vehicle) in vehicles.iter_mut().filter(|(_, v)| v.progress > 0.5) {
-        if vehicle.gap.arrival_order.is_some() {
-            continue; // Already has an arrival order
+    for (entity, mut vehicle, _) in vehicles.iter_mut().filter(|(_, v, _)| v.progress > 0.5) {
+        let distance_to_line = distance_to_line(&road, &vehicle);
+        if vehicle.gap.arrived_at_line.is_none() && distance_to_line <= STOP_LINE_ZONE {
+            vehicle.gap.arrived_at_line = Some(now);
         }
 
         let next_segment = match vehicle.route.get(1) {
@@ -65,6 +126,22 @@ pub fn apply_gap_acceptance(
             None => continue,
         };
 
+        // All-way stop: remember when the front vehicle of the approach came to a halt.
+        // IDM holds a waiting vehicle min_spacing short of the line.
+        if vehicle.speed < STOPPED_SPEED
+            && distance_to_line <= STOP_LINE_ZONE + vehicle.idm.min_spacing
+        {
+            if let Some(intersection) = road.intersections.iter_mut().find(|i| {
+                i.yield_resolver == YieldResolver::AllWayStop && i.incoming.contains(&next_segment)
+            }) {
+                intersection.stop_times.entry(entity).or_insert(now);
+            }
+        }
+
+        if vehicle.gap.arrival_order.is_some() {
+            continue; // Already has an arrival order
+        }
+
         // Find the intersection this vehicle is approaching and assign arrival order
         for intersection in road.intersections.iter_mut() {
             if intersection.incoming.contains(&next_segment) {
@@ -76,10 +153,11 @@ pub fn apply_gap_acceptance(
     }
 
     // Phase 2: Collect info about all vehicles approaching intersections
-    // Tuple: (entity, segment, next_segment, progress, speed, length, waiting_time, arrival_order)
+    // Tuple: (entity, segment, next_segment, progress, speed, length, waiting_time, arrival_order,
+    //         arrived_at_line, emergency)
     let vehicle_info: Vec<_> = vehicles
         .iter()
-        .map(|(entity, v)| {
+        .map(|(entity, v, emergency)| {
             (
                 entity,
                 v.segment,
@@ -89,21 +167,100 @@ pub fn apply_gap_acceptance(
                 v.length,
                 v.gap.waiting_time.unwrap_or(0.0),
                 v.gap.arrival_order.unwrap_or(u32::MAX),
+                v.gap.arrived_at_line,
+                emergency,
             )
         })
         .collect();
 
+    // Release reservations of vehicles that left the turn segment or despawned
+    for intersection in road.intersections.iter_mut() {
+        intersection.reservations.retain(|segment, holder| {
+            vehicle_info.iter().any(|(entity, on, next, ..)| {
+                entity == holder && (on == segment || *next == Some(*segment))
+            })
+        });
+
+        // Forget stops of vehicles that have entered the junction or despawned
+        let incoming = &intersection.incoming;
+        intersection.stop_times.retain(|stopped, _| {
+            vehicle_info.iter().any(|(entity, _, next, ..)| {
+                entity == stopped && next.is_some_and(|next| incoming.contains(&next))
+            })
+        });
+    }
+
+    // Vehicles let in by a courteous driver with priority
+    let mut courtesy_grants = Vec::new();
+
     // Phase 3: Gap acceptance checks
-    for (entity, mut vehicle) in vehicles.iter_mut().filter(|(_, v)| v.progress > 0.5) {
+    for (entity, mut vehicle, emergency) in vehicles.iter_mut().filter(|(_, v, _)| v.progress > 0.5)
+    {
         let next_segment = match vehicle.route.get(1) {
-            Some(seg) => seg,
+            Some(seg) => *seg,
             None => continue,
         };
+        let next_segment = &next_segment;
+
+        // Emergency vehicles ignore signs, signals and regular traffic. Between two of them
+        // in conflict, the one in the junction or with the lower entity id goes first.
+        if emergency {
+            let conflicts = road
+                .intersections
+                .iter()
+                .filter_map(|i| i.conflicts.get(next_segment))
+                .next();
+            let blocked = conflicts.is_some_and(|conflicts| {
+                vehicle_info.iter().any(|other| {
+                    let (other_entity, other_seg, other_next, .., other_emergency) = *other;
+                    other_emergency
+                        && other_entity != entity
+                        && (conflicts.contains(&other_seg)
+                            || (other_entity.index() < entity.index()
+                                && other_next.is_some_and(|next| conflicts.contains(&next))))
+                })
+            });
+            vehicle.gap.cleared_to_go = !blocked;
+            if blocked {
+                let current = vehicle.gap.waiting_time.unwrap_or(0.0);
+                vehicle.gap.waiting_time = Some(current + dt);
+            }
+            reserve(&mut road, entity, *next_segment, !blocked);
+            continue;
+        }
+
+        // Stop sign: hold until the vehicle has stood still at the line, however hard it
+        // has to brake for it
+        let stop_sign = road.segments.get(&vehicle.segment).control == TrafficControl::Stop
+            && !made_stop(&road, &vehicle);
+
+        // Red light, or an all-way stop not made yet: stop at the line unless too close
+        // to stop comfortably
+        let hold = stop_sign
+            || road
+                .intersections
+                .iter()
+                .find(|i| i.incoming.contains(next_segment))
+                .is_some_and(|intersection| {
+                    let red = !intersection
+                        .yield_resolver
+                        .current_green(now, intersection.entry_directions[next_segment]);
+                    let unstopped = intersection.yield_resolver == YieldResolver::AllWayStop
+                        && !intersection.stop_times.contains_key(&entity);
+                    red || unstopped
+                })
+                && can_stop(&road, &vehicle);
+
+        // Nothing can conflict with this movement
+        if !hold && !road.segments.get(next_segment).yield_required {
+            vehicle.gap.cleared_to_go = true;
+            continue;
+        }
 
-        let critical_time = vehicle.gap.min_gap;
+        let critical_time = vehicle.gap.accepted_gap();
         let my_arrival_order = vehicle.gap.arrival_order.unwrap_or(u32::MAX);
 
-        let mut actual_gap = f32::MAX;
+        let mut actual_gap = if hold { 0.0 } else { f32::MAX };
 
         // find intersection containing next_segment
         for intersection in road
@@ -112,6 +269,15 @@ pub fn apply_gap_acceptance(
             .filter(|i| i.incoming.contains(next_segment))
         {
             if let Some(conflicts) = intersection.conflicts.get(next_segment) {
+                // Safety check 0: another vehicle already committed to a conflicting turn
+                if intersection
+                    .reservations
+                    .iter()
+                    .any(|(segment, holder)| *holder != entity && conflicts.contains(segment))
+                {
+                    actual_gap = 0.0;
+                }
+
                 for &(
                     other_entity,
                     other_seg,
@@ -121,6 +287,8 @@ pub fn apply_gap_acceptance(
                     other_length,
                     other_waiting_time,
                     other_arrival_order,
+                    other_arrived_at_line,
+                    other_emergency,
                 ) in &vehicle_info
                 {
                     if other_entity == entity {
@@ -135,7 +303,7 @@ pub fn apply_gap_acceptance(
 
                         // For roundabouts: circle traffic doesn't yield to entry traffic
                         let dominated = match intersection.yield_resolver {
-                            crate::driver::YieldResolver::Roundabout => {
+                            YieldResolver::Roundabout => {
                                 // Only yield if I'm entering and they're in the circle
                                 my_turn == TurnType::RoundaboutEntry
                                     && other_turn == TurnType::RoundaboutCircle
@@ -160,16 +328,45 @@ pub fn apply_gap_acceptance(
                             let their_dir =
                                 *intersection.entry_directions.get(&other_next_seg).unwrap();
 
-                            if intersection.yield_resolver.has_priority(
-                                my_turn,
-                                my_dir,
-                                my_arrival_order,
-                                vehicle.gap.waiting_time.unwrap_or(0.0),
-                                their_turn,
-                                their_dir,
-                                other_arrival_order,
-                                other_waiting_time,
-                            ) {
+                            let me = Approach {
+                                arrived_at_line: vehicle.gap.arrived_at_line,
+                                stopped_at: intersection.stop_times.get(&entity).copied(),
+                                green: intersection.yield_resolver.current_green(now, my_dir),
+                                priority_road: road.segments.get(&vehicle.segment).is_priority_road,
+                                ..Approach::new(
+                                    my_turn,
+                                    my_dir,
+                                    my_arrival_order,
+                                    vehicle.gap.waiting_time.unwrap_or(0.0),
+                                )
+                            };
+                            let them = Approach {
+                                arrived_at_line: other_arrived_at_line,
+                                stopped_at: intersection.stop_times.get(&other_entity).copied(),
+                                green: intersection.yield_resolver.current_green(now, their_dir),
+                                emergency: other_emergency,
+                                priority_road: road.segments.get(&other_seg).is_priority_road,
+                                ..Approach::new(
+                                    their_turn,
+                                    their_dir,
+                                    other_arrival_order,
+                                    other_waiting_time,
+                                )
+                            };
+
+                            if intersection
+                                .yield_resolver
+                                .has_priority_over_with(&me, &them, &config)
+                            {
+                                // Courtesy: let a long-waiting vehicle go if I can still stop comfortably
+                                if vehicle.gap.courtesy_yield(other_waiting_time)
+                                    && can_stop(&road, &vehicle)
+                                {
+                                    courtesy_grants.push(other_entity);
+                                    actual_gap = 0.0;
+                                    break;
+                                }
+
                                 continue; // I have priority, don't yield to this vehicle
                             }
 
@@ -181,28 +378,736 @@ pub fn apply_gap_acceptance(
                                 (remaining * seg.length - other_length / 2.0).max(0.0);
 
                             // Safety check 2: Minimum physical distance
-                            if distance_to_enter < MIN_SAFE_DISTANCE {
+                            if distance_to_enter < config.min_safe_distance {
                                 actual_gap = 0.0;
                                 break;
                             }
 
-                            let time_to_enter = distance_to_enter / other_speed.max(0.1);
-                            actual_gap = actual_gap.min(time_to_enter);
+                            // Gap at the point where the two paths actually cross: their
+                            // arrival there after mine, pulling away at full acceleration
+                            let their_conflict = intersection
+                                .conflict_progress(other_next_seg, *next_segment)
+                                * road.segments.get(&other_next_seg).length;
+                            let their_time =
+                                (distance_to_enter + their_conflict) / other_speed.max(0.1);
+
+                            let my_conflict = intersection
+                                .conflict_progress(*next_segment, other_next_seg)
+                                * road.segments.get(next_segment).length;
+                            let my_time = time_to_cover(
+                                distance_to_line(&road, &vehicle) + my_conflict,
+                                vehicle.speed,
+                                vehicle.idm.max_acceleration,
+                            );
+
+                            actual_gap = actual_gap.min(their_time - my_time);
                         }
                     }
                 }
             }
         }
 
-        if actual_gap < critical_time {
+        let cleared = actual_gap >= critical_time;
+        if cleared {
+            // Gap is acceptable - tell IDM we can go
+            // Keep waiting_time for deadlock detection (cleared on segment transition)
+            vehicle.gap.cleared_to_go = true;
+        } else {
             // Must wait - accumulate waiting time for deadlock detection
             let current = vehicle.gap.waiting_time.unwrap_or(0.0);
-            vehicle.gap.waiting_time = Some(current + time.delta_secs());
+            vehicle.gap.waiting_time = Some(current + dt);
             vehicle.gap.cleared_to_go = false;
-        } else {
-            // Gap is acceptable - tell IDM we can go
-            // Keep waiting_time for deadlock detection (cleared on segment transition)
+        }
+        reserve(&mut road, entity, *next_segment, cleared);
+    }
+
+    // Phase 4: Vehicles granted courtesy go regardless of their own gap check
+    for entity in courtesy_grants {
+        if let Ok((_, mut vehicle, _)) = vehicles.get_mut(entity) {
             vehicle.gap.cleared_to_go = true;
+            if let Some(&next_segment) = vehicle.route.get(1) {
+                reserve(&mut road, entity, next_segment, true);
+            }
         }
     }
 }
+
+/// Whether a vehicle held at a stop sign has come to a standstill at the line (not further
+/// back in the queue), or has since been cleared to pull away
+fn made_stop(road: &Road, vehicle: &Vehicle) -> bool {
+    if vehicle.gap.waiting_time.is_none() {
+        return false;
+    }
+    if vehicle.gap.cleared_to_go {
+        return true;
+    }
+
+    vehicle.speed < STOPPED_SPEED
+        && distance_to_line(road, vehicle) <= STOP_LINE_ZONE + vehicle.idm.min_spacing
+}
+
+/// Whether the vehicle can still come to a stop at its stop line at comfortable deceleration
+fn can_stop(road: &Road, vehicle: &Vehicle) -> bool {
+    let stopping_distance = vehicle.speed.powi(2) / (2.0 * vehicle.idm.comfortable_deceleration);
+    distance_to_line(road, vehicle) >= stopping_distance
+}
+
+/// Distance (m) from the front bumper to the end of the current segment
+fn distance_to_line(road: &Road, vehicle: &Vehicle) -> f32 {
+    let segment = road.segments.get(&vehicle.segment);
+    ((1.0 - vehicle.progress) * segment.length - vehicle.length / 2.0).max(0.0)
+}
+
+/// Seconds to cover `distance` starting at `speed` and accelerating at `acceleration`
+fn time_to_cover(distance: f32, speed: f32, acceleration: f32) -> f32 {
+    let acceleration = acceleration.max(0.1);
+    ((speed.powi(2) + 2.0 * acceleration * distance).sqrt() - speed) / acceleration
+}
+
+/// Claim (or, for a vehicle that has to wait again, release) a turn segment at its intersection
+fn reserve(road: &mut Road, entity: Entity, turn: Id<Segment>, cleared: bool) {
+    let Some(intersection) = road
+        .intersections
+        .iter_mut()
+        .find(|i| i.incoming.contains(&turn))
+    else {
+        return;
+    };
+
+    if cleared {
+        intersection.reservations.insert(turn, entity);
+    } else if intersection.reservations.get(&turn) == Some(&entity) {
+        intersection.reservations.remove(&turn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{
+        apply_idm, move_and_despawn_vehicles, next_segment_toward, update_occupancy, Idm,
+        SegmentOccupancy, SidePriority, DEFAULT_DEADLOCK_THRESHOLD, DEFAULT_MAX_JERK,
+        DEFAULT_MIN_SAFE_DISTANCE,
+    };
+    use crate::{Node, SimRng, SimulationStats};
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    /// Plus junction with 60 m arms; returns the road and the (north, east, south, west) edges
+    fn junction(resolver: YieldResolver) -> (Road, [Vec3; 4]) {
+        let road = Road::cross_intersection(Vec3::ZERO, 60.0, 13.9, resolver);
+        let edges = [
+            Vec3::new(0.0, 60.0, 0.0),
+            Vec3::new(60.0, 0.0, 0.0),
+            Vec3::new(0.0, -60.0, 0.0),
+            Vec3::new(-60.0, 0.0, 0.0),
+        ];
+        (road, edges)
+    }
+
+    /// Vehicle on the approach from edge `from`, routed to edge `to`.
+    /// Finalize splits edge nodes into one node per lane, so look them up by position.
+    fn approaching(road: &Road, from: Vec3, to: Vec3, progress: f32) -> Vehicle {
+        let lane_node = |edge: Vec3, outbound: bool| -> Id<Node> {
+            road.nodes
+                .iter_with_ids()
+                .find(|(_, node)| {
+                    node.position.distance(edge) < 3.0
+                        && if outbound {
+                            !node.outgoing.is_empty()
+                        } else {
+                            !node.incoming.is_empty()
+                        }
+                })
+                .map(|(id, _)| id)
+                .unwrap()
+        };
+        let destination = lane_node(to, false);
+        let (approach, route) =
+            next_segment_toward(road, lane_node(from, true), destination).unwrap();
+        let mut vehicle = Vehicle::new(approach, destination, route);
+        vehicle.progress = progress;
+        vehicle.speed = 10.0;
+        vehicle
+    }
+
+    /// World for running the driving systems on `road`
+    fn driving_world(road: Road) -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.insert_resource(SimRng::seeded(1));
+        world.insert_resource(road);
+        world
+    }
+
+    /// Advance 50 ms through occupancy, gap acceptance, IDM and movement
+    fn drive(world: &mut World) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.05));
+        world.run_system_once(update_occupancy).unwrap();
+        world.run_system_once(apply_gap_acceptance).unwrap();
+        world.run_system_once(apply_idm).unwrap();
+        world.run_system_once(move_and_despawn_vehicles).unwrap();
+    }
+
+    /// Run gap acceptance once with a north-to-south minor vehicle and an optional
+    /// east-to-west vehicle close to the junction; returns whether the first was cleared
+    fn minor_cleared(resolver: YieldResolver, with_major_traffic: bool) -> bool {
+        let (road, [north, east, south, west]) = junction(resolver);
+        let minor = approaching(&road, north, south, 0.8);
+        let major = approaching(&road, east, west, 0.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.05));
+        world.insert_resource(road);
+        let minor = world.spawn(minor).id();
+        if with_major_traffic {
+            world.spawn(major);
+        }
+
+        world.run_system_once(apply_gap_acceptance).unwrap();
+        let vehicle = world.get::<Vehicle>(minor).unwrap();
+        assert_eq!(vehicle.speed, 10.0);
+        vehicle.gap.cleared_to_go
+    }
+
+    #[test]
+    fn test_red_light_holds_vehicle_at_stop_line() {
+        let signal = YieldResolver::TrafficLight {
+            phase_duration: 10.0,
+            offset: 0.0,
+            east_west_duration: None,
+        };
+        let cleared_at = |time: f32, speed: f32| {
+            let (road, [north, _, south, _]) = junction(signal);
+            let mut vehicle = approaching(&road, north, south, 0.6);
+            vehicle.speed = speed;
+
+            let mut world = World::new();
+            world.init_resource::<Time>();
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(time));
+            world.insert_resource(road);
+            let entity = world.spawn(vehicle).id();
+
+            world.run_system_once(apply_gap_acceptance).unwrap();
+            world.get::<Vehicle>(entity).unwrap().gap.cleared_to_go
+        };
+
+        // North-south runs first: green at 5 s, red at 15 s, green again at 25 s
+        assert!(cleared_at(5.0, 0.0));
+        assert!(!cleared_at(15.0, 0.0));
+        assert!(cleared_at(25.0, 0.0));
+
+        // Too fast to stop before the line: carries on through the red
+        assert!(cleared_at(15.0, 30.0));
+    }
+
+    #[test]
+    fn test_all_way_stop_clears_vehicles_in_stopping_order() {
+        let (road, [north, east, south, west]) = junction(YieldResolver::AllWayStop);
+        // Mutually conflicting movements, staggered so they reach their lines one by one
+        let mut vehicles = [
+            approaching(&road, north, south, 0.3),
+            approaching(&road, east, west, 0.15),
+            approaching(&road, south, west, 0.0),
+        ];
+        for vehicle in &mut vehicles {
+            vehicle.speed = 6.0;
+            vehicle.idm =
+                Idm::from_params(0.5, 1.2, 2.0, 2.0, 2.5, 4.0, 0.5, 0.0, DEFAULT_MAX_JERK);
+        }
+
+        let mut world = driving_world(road);
+        let entities = vehicles.map(|vehicle| world.spawn(vehicle).id());
+        let approaches = entities.map(|entity| world.get::<Vehicle>(entity).unwrap().segment);
+
+        // Time each vehicle left its approach and whether it ever stood still first
+        let mut entered = [None; 3];
+        let mut stopped = [false; 3];
+        for step in 0..1200 {
+            drive(&mut world);
+
+            for i in 0..3 {
+                match world.get::<Vehicle>(entities[i]) {
+                    Some(vehicle) if vehicle.segment == approaches[i] => {
+                        stopped[i] |= vehicle.progress > 0.5 && vehicle.speed < STOPPED_SPEED;
+                    }
+                    _ => {
+                        entered[i].get_or_insert(step);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(stopped, [true; 3], "every vehicle stops before entering");
+        let entered = entered.map(Option::unwrap);
+        assert!(
+            entered[0] < entered[1] && entered[1] < entered[2],
+            "{entered:?}"
+        );
+    }
+
+    #[test]
+    fn test_stop_sign_forces_full_stop_on_empty_junction() {
+        // Drive up to an empty junction; returns whether the vehicle waited at a standstill
+        // before entering it
+        let stopped_before_entering = |control: TrafficControl| {
+            let (mut road, [north, _, south, _]) = junction(YieldResolver::default());
+            let mut vehicle = approaching(&road, north, south, 0.3);
+            vehicle.speed = 6.0;
+            road.set_control(vehicle.segment, control);
+            let approach = vehicle.segment;
+
+            let mut world = driving_world(road);
+            let entity = world.spawn(vehicle).id();
+
+            let mut stood_waiting = false;
+            for _ in 0..400 {
+                drive(&mut world);
+
+                let vehicle = world.get::<Vehicle>(entity).unwrap();
+                if vehicle.segment != approach {
+                    return stood_waiting;
+                }
+                stood_waiting |= vehicle.speed < STOPPED_SPEED
+                    && vehicle.gap.waiting_time.is_some_and(|waited| waited > 0.0);
+            }
+            panic!("vehicle never entered the junction");
+        };
+
+        assert!(stopped_before_entering(TrafficControl::Stop));
+        assert!(!stopped_before_entering(TrafficControl::Yield));
+        assert!(!stopped_before_entering(TrafficControl::None));
+    }
+
+    #[test]
+    fn test_emergency_vehicle_cuts_ahead_of_priority_traffic() {
+        // Returns the steps at which the (westbound, northbound) vehicles left their approaches
+        let run = |northbound_emergency: bool| {
+            let (road, [north, east, south, west]) = junction(YieldResolver::default());
+            // Westbound comes from the right of the northbound vehicle and normally goes first
+            let westbound = approaching(&road, east, west, 0.6);
+            let northbound = approaching(&road, south, north, 0.6);
+            let approaches = [westbound.segment, northbound.segment];
+
+            let mut world = driving_world(road);
+            let westbound = world.spawn(westbound).id();
+            let northbound = world.spawn(northbound).id();
+            if northbound_emergency {
+                world.entity_mut(northbound).insert(EmergencyVehicle);
+            }
+
+            let mut entered = [None; 2];
+            for step in 0..600 {
+                drive(&mut world);
+                for (i, entity) in [westbound, northbound].into_iter().enumerate() {
+                    let on_approach = world
+                        .get::<Vehicle>(entity)
+                        .is_some_and(|vehicle| vehicle.segment == approaches[i]);
+                    if !on_approach {
+                        entered[i].get_or_insert(step);
+                    }
+                }
+            }
+            entered.map(Option::unwrap)
+        };
+
+        let [westbound, northbound] = run(false);
+        assert!(
+            westbound < northbound,
+            "regular priority: {westbound} vs {northbound}"
+        );
+        let [westbound, northbound] = run(true);
+        assert!(
+            northbound < westbound,
+            "emergency: {westbound} vs {northbound}"
+        );
+    }
+
+    #[test]
+    fn test_conflicting_emergency_vehicles_go_by_entity_id() {
+        let (road, [north, east, south, west]) = junction(YieldResolver::default());
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let first = world
+            .spawn((approaching(&road, south, north, 0.9), EmergencyVehicle))
+            .id();
+        let second = world
+            .spawn((approaching(&road, east, west, 0.9), EmergencyVehicle))
+            .id();
+        world.insert_resource(road);
+
+        world.run_system_once(apply_gap_acceptance).unwrap();
+        assert!(world.get::<Vehicle>(first).unwrap().gap.cleared_to_go);
+        assert!(!world.get::<Vehicle>(second).unwrap().gap.cleared_to_go);
+    }
+
+    #[test]
+    fn test_gap_is_measured_at_the_conflict_point() {
+        let (road, [north, east, south, west]) = junction(YieldResolver::YieldSign {
+            major_axis: Vec3::X,
+        });
+        // Front bumper `distance` meters short of the stop line at 10 m/s
+        let at_distance = |from, to, distance: f32| {
+            let mut vehicle = approaching(&road, from, to, 0.0);
+            let length = road.segments.get(&vehicle.segment).length;
+            vehicle.progress = 1.0 - (distance + vehicle.length / 2.0) / length;
+            vehicle.speed = 10.0;
+            vehicle
+        };
+        let mut minor = at_distance(north, south, 0.5);
+        minor.gap = GapAcceptance::from_params(1.5, 0.0, 0.0);
+        minor.idm.max_acceleration = 2.0;
+        let major = at_distance(east, west, 13.5);
+
+        // The westbound lane is crossed early on the southbound path but late on the
+        // westbound one
+        let intersection = road.intersections.iter().next().unwrap();
+        let (mine, theirs) = (minor.route[1], major.route[1]);
+        assert!(intersection.conflict_progress(mine, theirs) < 0.5);
+        assert!(intersection.conflict_progress(theirs, mine) > 0.5);
+
+        // Reaching the junction in 1.35 s is under the accepted gap, but the major vehicle
+        // only gets to the crossing point well after the minor one has passed it
+        assert!(13.5 / major.speed < minor.gap.accepted_gap());
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(road);
+        let minor = world.spawn(minor).id();
+        world.spawn(major);
+
+        world.run_system_once(apply_gap_acceptance).unwrap();
+        assert!(world.get::<Vehicle>(minor).unwrap().gap.cleared_to_go);
+    }
+
+    #[test]
+    fn test_polite_major_vehicle_lets_long_waiting_minor_in() {
+        let run = |politeness: f32| {
+            let (road, [north, east, south, west]) = junction(YieldResolver::YieldSign {
+                major_axis: Vec3::X,
+            });
+            let mut minor = approaching(&road, north, south, 0.9);
+            minor.speed = 0.0;
+            minor.gap.waiting_time = Some(30.0);
+            // Crawling up 2.5 m from the junction: too close for the minor vehicle to go
+            // on its own, but still able to stop
+            let mut major = approaching(&road, east, west, 0.0);
+            let approach_length = road.segments.get(&major.segment).length;
+            major.progress = 1.0 - (2.5 + major.length / 2.0) / approach_length;
+            major.speed = 2.0;
+            major.idm.comfortable_deceleration = 2.0;
+            major.gap.politeness = politeness;
+
+            let mut world = World::new();
+            world.init_resource::<Time>();
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.05));
+            world.insert_resource(road);
+            let minor = world.spawn(minor).id();
+            let major = world.spawn(major).id();
+
+            world.run_system_once(apply_gap_acceptance).unwrap();
+            (
+                world.get::<Vehicle>(minor).unwrap().gap.cleared_to_go,
+                world.get::<Vehicle>(major).unwrap().gap.cleared_to_go,
+            )
+        };
+
+        // Impolite: the major vehicle keeps its priority and the minor keeps waiting
+        assert_eq!(run(0.0), (false, true));
+        // Polite: the major vehicle holds back and lets the minor go
+        assert_eq!(run(1.0), (true, false));
+    }
+
+    #[test]
+    fn test_courtesy_requires_long_relative_wait() {
+        let mut gap = GapAcceptance::typical(0.5);
+        gap.politeness = 0.5;
+        assert!(!gap.courtesy_yield(9.0));
+        assert!(gap.courtesy_yield(10.0));
+
+        // Already waiting a while myself: the other must have waited far longer
+        gap.waiting_time = Some(5.0);
+        assert!(!gap.courtesy_yield(10.0));
+        assert!(gap.courtesy_yield(20.0));
+    }
+
+    #[test]
+    fn test_yield_sign_minor_proceeds_on_empty_major_road() {
+        let yield_sign = YieldResolver::YieldSign {
+            major_axis: Vec3::X,
+        };
+        assert!(minor_cleared(yield_sign, false));
+    }
+
+    #[test]
+    fn test_yield_sign_minor_gives_way_to_major_traffic() {
+        let yield_sign = YieldResolver::YieldSign {
+            major_axis: Vec3::X,
+        };
+        assert!(!minor_cleared(yield_sign, true));
+
+        // Under plain right-of-way the same vehicle has the east approach on its left
+        assert!(minor_cleared(
+            YieldResolver::RightOfWay(SidePriority::RIGHT_HAND),
+            true
+        ));
+    }
+
+    #[test]
+    fn test_side_street_gives_way_to_priority_road_on_its_left() {
+        let cleared = |priority: bool| {
+            let (mut road, [north, east, south, west]) = junction(YieldResolver::default());
+            let minor = approaching(&road, north, south, 0.8);
+            let major = approaching(&road, east, west, 0.9);
+            road.set_priority(major.segment, priority);
+
+            let mut world = World::new();
+            world.init_resource::<Time>();
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.05));
+            world.insert_resource(road);
+            let minor = world.spawn(minor).id();
+            world.spawn(major);
+
+            world.run_system_once(apply_gap_acceptance).unwrap();
+            world.get::<Vehicle>(minor).unwrap().gap.cleared_to_go
+        };
+
+        // The eastern approach is on the minor car's left, so it only yields on a priority road
+        assert!(cleared(false));
+        assert!(!cleared(true));
+    }
+
+    #[test]
+    fn test_accepted_gap_shrinks_while_waiting() {
+        let mut gap = GapAcceptance::typical(0.5);
+        let fresh = gap.accepted_gap();
+        assert_eq!(fresh, gap.min_gap);
+
+        gap.waiting_time = Some(2.0);
+        let waited = gap.accepted_gap();
+        gap.waiting_time = Some(5.0);
+        let waited_longer = gap.accepted_gap();
+
+        assert!(waited < fresh);
+        assert!(waited_longer < waited);
+
+        // Never drops below the floor
+        gap.waiting_time = Some(1000.0);
+        assert!((gap.accepted_gap() - gap.min_gap * MIN_GAP_FLOOR).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_zero_impatience_keeps_gap() {
+        let mut gap = GapAcceptance::typical(0.5);
+        gap.impatience = 0.0;
+        gap.waiting_time = Some(10.0);
+        assert_eq!(gap.accepted_gap(), gap.min_gap);
+    }
+
+    #[test]
+    fn test_explicit_params_give_exact_accepted_gap() {
+        let mut gap = GapAcceptance::from_params(1.5, 0.1, 0.0);
+        assert_eq!(gap.accepted_gap(), 1.5);
+
+        // 1.5 * e^(-0.1 * 5)
+        gap.waiting_time = Some(5.0);
+        assert!((gap.accepted_gap() - 0.909796).abs() < 1e-5);
+
+        gap.waiting_time = Some(100.0);
+        assert!((gap.accepted_gap() - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_simultaneous_conflicting_entries_are_serialized() {
+        let (road, [north, east, south, west]) = junction(YieldResolver::default());
+
+        // Both stopped just short of the line: each sees the other as a distant gap
+        let stopped = |from, to| {
+            let mut vehicle = approaching(&road, from, to, 0.0);
+            let length = road.segments.get(&vehicle.segment).length;
+            vehicle.progress =
+                1.0 - (DEFAULT_MIN_SAFE_DISTANCE + 1.0 + vehicle.length / 2.0) / length;
+            vehicle.speed = 0.0;
+            vehicle
+        };
+        let southbound = stopped(north, south);
+        let westbound = stopped(east, west);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(road);
+        let southbound = world.spawn(southbound).id();
+        let westbound = world.spawn(westbound).id();
+
+        world.run_system_once(apply_gap_acceptance).unwrap();
+        let cleared =
+            |world: &World, entity| world.get::<Vehicle>(entity).unwrap().gap.cleared_to_go;
+        assert_ne!(
+            cleared(&world, southbound),
+            cleared(&world, westbound),
+            "exactly one of the conflicting movements may enter"
+        );
+
+        // Once the first vehicle is gone its reservation lapses and the other may go
+        let (first, second) = if cleared(&world, southbound) {
+            (southbound, westbound)
+        } else {
+            (westbound, southbound)
+        };
+        world.despawn(first);
+        world.run_system_once(apply_gap_acceptance).unwrap();
+        assert!(cleared(&world, second));
+    }
+
+    #[test]
+    fn test_lower_deadlock_threshold_breaks_gridlock_sooner() {
+        // Steps until one of four vehicles at the lines, each with another on its right, is
+        // let through
+        let steps_to_break = |deadlock_threshold: f32| {
+            let (road, [north, east, south, west]) = junction(YieldResolver::default());
+            let stopped = |from, to| {
+                let mut vehicle = approaching(&road, from, to, 0.0);
+                let length = road.segments.get(&vehicle.segment).length;
+                vehicle.progress = 1.0 - (1.0 + vehicle.length / 2.0) / length;
+                vehicle.speed = 0.0;
+                vehicle
+            };
+            let vehicles = [
+                stopped(north, south),
+                stopped(east, west),
+                stopped(south, north),
+                stopped(west, east),
+            ];
+
+            let mut world = World::new();
+            world.init_resource::<Time>();
+            world.insert_resource(road);
+            world.insert_resource(YieldingConfig {
+                deadlock_threshold,
+                ..Default::default()
+            });
+            let entities = vehicles.map(|vehicle| world.spawn(vehicle).id());
+
+            for step in 0..200 {
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_secs_f32(0.05));
+                world.run_system_once(apply_gap_acceptance).unwrap();
+                if entities
+                    .iter()
+                    .any(|&entity| world.get::<Vehicle>(entity).unwrap().gap.cleared_to_go)
+                {
+                    return step;
+                }
+            }
+            panic!("gridlock never broke");
+        };
+
+        let default = steps_to_break(DEFAULT_DEADLOCK_THRESHOLD);
+        let lowered = steps_to_break(0.1);
+        assert!(lowered < default, "{lowered} vs {default}");
+        assert!(default < steps_to_break(2.0));
+    }
+
+    #[test]
+    fn test_conflict_free_movement_never_waits() {
+        // Leaving a roundabout diverges from the circle and conflicts with nothing
+        let (road, [north, _, south, _]) = junction(YieldResolver::Roundabout);
+        let mut vehicle = approaching(&road, south, north, 0.9);
+        let exit = vehicle
+            .route
+            .iter()
+            .position(|id| road.segments.get(id).turn_type == TurnType::RoundaboutExit)
+            .unwrap();
+        assert!(!road.segments.get(&vehicle.route[exit]).yield_required);
+
+        // Put the vehicle on the circle segment just before the exit
+        vehicle.route.drain(..exit - 1);
+        vehicle.segment = vehicle.route[0];
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(road);
+        let entity = world.spawn(vehicle).id();
+
+        for _ in 0..20 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.05));
+            world.run_system_once(apply_gap_acceptance).unwrap();
+            let vehicle = world.get::<Vehicle>(entity).unwrap();
+            assert_eq!(vehicle.gap.waiting_time, None);
+            assert!(vehicle.gap.cleared_to_go);
+        }
+    }
+
+    #[test]
+    fn test_vehicle_circulates_roundabout_and_leaves_at_its_arm() {
+        let mut road = Road::default();
+        let [north, east, south, west] =
+            [Vec3::Y, Vec3::X, Vec3::NEG_Y, Vec3::NEG_X].map(|direction| direction * 60.0);
+        let arms = [north, east, south, west]
+            .map(|position| road.add_edge_node(position))
+            .to_vec();
+        road.add_roundabout(Vec3::ZERO, 15.0, arms, YieldResolver::Roundabout);
+        road.finalize();
+
+        // Three quarters of the way round, past the east and north arms
+        let vehicle = approaching(&road, south, west, 0.5);
+        let destination = vehicle.destination;
+        let mut world = driving_world(road);
+        let entity = world.spawn(vehicle).id();
+
+        let mut driven = vec![];
+        for _ in 0..2000 {
+            drive(&mut world);
+            let Some(vehicle) = world.get::<Vehicle>(entity) else {
+                break;
+            };
+            if driven.last() != Some(&vehicle.segment) {
+                driven.push(vehicle.segment);
+            }
+        }
+        assert!(world.get_entity(entity).is_err(), "vehicle never arrived");
+
+        let road = world.resource::<Road>();
+        let turns: Vec<_> = driven
+            .iter()
+            .map(|id| road.segments.get(id).turn_type)
+            .collect();
+        assert_eq!(
+            turns
+                .iter()
+                .filter(|turn| **turn == TurnType::RoundaboutCircle)
+                .count(),
+            2,
+            "{turns:?}"
+        );
+        assert_eq!(road.segments.get(driven.last().unwrap()).to, destination);
+    }
+
+    #[test]
+    fn test_crossing_movements_require_yield() {
+        let (road, _) = junction(YieldResolver::default());
+        let intersection = road.intersections.iter().next().unwrap();
+        assert!(intersection
+            .incoming
+            .iter()
+            .filter(|id| road.segments.get(id).turn_type == TurnType::Straight)
+            .all(|id| road.segments.get(id).yield_required));
+    }
+}
@@ -9,13 +9,13 @@
 use bevy_ecs::{
     entity::Entity,
     query::Without,
-    system::{Query, Res},
+    system::{Query, Res, ResMut},
 };
 use bevy_time::Time;
 
 use crate::{
-    driver::{PlayerControlled, SegmentOccupancy, Vehicle},
-    Road,
+    driver::{Frozen, PlayerControlled, SegmentOccupancy, Vehicle, VehicleClass, VehicleTelemetry},
+    sim_dt, Road, Segment, SimRng,
 };
 
 /// Intelligent Driver Model parameters.
@@ -25,27 +25,107 @@ use crate::{
 /// - Min spacing: 2.0-5.0 m (bumper-to-bumper distance at standstill)
 /// - Max acceleration: 1.0-3.0 m/s² (comfortable acceleration)
 /// - Comfortable deceleration: 1.5-3.0 m/s² (comfortable braking)
+/// - Acceleration exponent: 4 (lower = softer approach to desired speed)
+/// - Startup delay: 0.5-1.5 s (reaction time before following a leader that pulls away)
+/// - Noise sigma: 0.0-0.3 m/s² (standard deviation of random acceleration jitter at full speed)
+/// - Max jerk: 2.0-10.0 m/s³ (how quickly acceleration may change from one step to the next)
 pub struct Idm {
     pub aggression: f32,
     pub desired_time_headway: f32,
     pub min_spacing: f32,
     pub max_acceleration: f32,
     pub comfortable_deceleration: f32,
+    pub acceleration_exponent: f32,
+    pub startup_delay: f32,
+    pub noise_sigma: f32,
+    pub max_jerk: f32,
 }
 
+/// Below this speed (m/s) a vehicle counts as standing still
+const STANDSTILL_SPEED: f32 = 0.1;
+
+/// A stopped vehicle this close (m) behind its leader is part of a standing queue
+pub(crate) const QUEUE_GAP: f32 = 8.0;
+
+/// Sideways acceleration (m/s²) drivers accept when cornering
+pub const COMFORTABLE_LATERAL_ACCELERATION: f32 = 2.5;
+
+/// Standard IDM free-road exponent (delta)
+pub const DEFAULT_ACCELERATION_EXPONENT: f32 = 4.0;
+
+/// Rate (m/s³) at which drivers change between throttle and brake
+pub const DEFAULT_MAX_JERK: f32 = 5.0;
+
 impl Idm {
-    pub fn new(aggression: f32) -> Self {
+    /// Driver of the given aggression with parameters randomly spread around the class profile
+    pub fn new(aggression: f32, class: VehicleClass, rng: &mut SimRng) -> Self {
+        Self::sample(aggression, class, || rng.uniform() * 2.0 - 1.0)
+    }
+
+    /// Driver of the given aggression without random spread
+    pub fn typical(aggression: f32, class: VehicleClass) -> Self {
+        Self::sample(aggression, class, || 0.0)
+    }
+
+    /// `jitter` yields values in [-1, 1] scaling each parameter's random range.
+    /// Class-dependent parameters are blended as factors of the profile value.
+    fn sample(aggression: f32, class: VehicleClass, mut jitter: impl FnMut() -> f32) -> Self {
+        let profile = class.profile();
+        let mut blend = |safe_value, aggressive_value, max_random_range| {
+            blend(
+                safe_value,
+                aggressive_value,
+                aggression,
+                max_random_range * jitter(),
+            )
+        };
+
+        Self::from_params(
+            aggression,
+            blend(1.5, 0.8, 0.2).max(0.5),
+            (profile.min_spacing * blend(4.0 / 3.0, 2.0 / 3.0, 1.0 / 3.0)).max(0.5),
+            (profile.max_acceleration * blend(0.5, 1.5, 0.25)).max(0.3),
+            (profile.comfortable_deceleration * blend(2.0 / 3.0, 4.0 / 3.0, 0.2)).max(0.5),
+            DEFAULT_ACCELERATION_EXPONENT,
+            blend(1.2, 0.6, 0.2).max(0.3),
+            0.0,
+            (DEFAULT_MAX_JERK * blend(0.6, 1.4, 0.2)).clamp(2.0, 10.0),
+        )
+    }
+
+    /// Driver with exactly the given parameters, no randomization
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_params(
+        aggression: f32,
+        desired_time_headway: f32,
+        min_spacing: f32,
+        max_acceleration: f32,
+        comfortable_deceleration: f32,
+        acceleration_exponent: f32,
+        startup_delay: f32,
+        noise_sigma: f32,
+        max_jerk: f32,
+    ) -> Self {
         Self {
             aggression,
-            desired_time_headway: blend(1.5, 0.8, aggression, 0.2).max(0.5),
-            min_spacing: blend(2.0, 1.0, aggression, 0.5).max(0.5),
-            max_acceleration: blend(1.0, 3.0, aggression, 0.5).max(0.5),
-            comfortable_deceleration: blend(1.5, 3.0, aggression, 0.5).max(0.5),
+            desired_time_headway,
+            min_spacing,
+            max_acceleration,
+            comfortable_deceleration,
+            acceleration_exponent,
+            startup_delay,
+            noise_sigma,
+            max_jerk,
         }
     }
 
+    /// Speed this driver settles at on a free road with the given limit
+    pub fn desired_speed(&self, speed_limit: f32) -> f32 {
+        lerp(speed_limit * 0.8, speed_limit * 1.2, self.aggression)
+    }
+
     pub fn acceleration(&self, speed_limit: f32, speed: f32, gap: f32, delta_speed: f32) -> f32 {
-        let desired_speed = lerp(speed_limit * 0.8, speed_limit * 1.2, self.aggression);
+        let desired_speed = self.desired_speed(speed_limit);
 
         let gap = gap.max(0.01);
 
@@ -55,69 +135,717 @@ impl Idm {
                 / (2.0 * (self.max_acceleration * self.comfortable_deceleration).sqrt());
 
         let raw = self.max_acceleration
-            * (1.0 - (speed / desired_speed).powi(4) - (s_star / gap).powi(2));
+            * (1.0
+                - (speed / desired_speed).powf(self.acceleration_exponent)
+                - (s_star / gap).powi(2));
 
         // Clamp to realistic limits:
         // - Can't accelerate faster than max_acceleration
         // - Can't brake harder than 2x comfortable_deceleration (emergency brake)
         raw.clamp(-self.comfortable_deceleration * 2.0, self.max_acceleration)
     }
+
+    /// Acceleration after moving from `previous` toward `target` for `dt` seconds, changing by
+    /// at most `max_jerk * dt`. Braking harder than comfortable applies at once, so an
+    /// emergency stop is never softened.
+    pub fn limit_jerk(&self, previous: f32, target: f32, dt: f32) -> f32 {
+        if target < -self.comfortable_deceleration && target < previous {
+            return target;
+        }
+        // NaN (no time passed with unlimited jerk) means no change
+        let max_change = (self.max_jerk * dt).max(0.0);
+        previous + (target - previous).clamp(-max_change, max_change)
+    }
+
+    /// Speed after one explicit Euler step of `dt` seconds, outside of any ECS system
+    pub fn step(&self, speed: f32, speed_limit: f32, gap: f32, delta_speed: f32, dt: f32) -> f32 {
+        let (_, speed) = self.step_with(speed, speed_limit, gap, delta_speed, dt, |a| a);
+        speed
+    }
+
+    /// [`Idm::step`] where `respond` turns the model's acceleration into the one the driver
+    /// actually applies (noise, reaction delay, jerk). Returns the applied acceleration and
+    /// the new speed.
+    fn step_with(
+        &self,
+        speed: f32,
+        speed_limit: f32,
+        gap: f32,
+        delta_speed: f32,
+        dt: f32,
+        respond: impl FnOnce(f32) -> f32,
+    ) -> (f32, f32) {
+        let acceleration = respond(self.acceleration(speed_limit, speed, gap, delta_speed));
+        (acceleration, integrate_speed(speed, acceleration, dt))
+    }
 }
 
-fn blend(safe_value: f32, aggressive_value: f32, aggression: f32, max_random_range: f32) -> f32 {
-    let random = rand::random::<f32>() * 2.0 - 1.0;
-    let random = max_random_range * random;
+/// Apply `acceleration` for `dt` seconds; vehicles never reverse
+fn integrate_speed(speed: f32, acceleration: f32, dt: f32) -> f32 {
+    (speed + acceleration * dt).max(0.0)
+}
 
-    lerp(safe_value, aggressive_value, aggression) + random
+/// Posted limit of a segment, lowered on curves to what can be cornered within
+/// [`COMFORTABLE_LATERAL_ACCELERATION`]; tighter curves get lower limits
+pub fn cornering_speed_limit(road: &Road, segment: &Segment) -> f32 {
+    let from = road.nodes.get(&segment.from).position;
+    let to = road.nodes.get(&segment.to).position;
+    segment
+        .geometry
+        .max_comfortable_speed(from, to, COMFORTABLE_LATERAL_ACCELERATION)
+        .map_or(segment.speed_limit, |limit| limit.min(segment.speed_limit))
+}
+
+/// Effective speed limit while approaching a slower downstream segment: the highest speed
+/// from which the vehicle can still slow to `next_limit` by the end of the segment at
+/// `deceleration`, capped at the current segment's limit
+pub fn approach_speed_limit(
+    segment_limit: f32,
+    next_limit: f32,
+    distance_to_end: f32,
+    deceleration: f32,
+) -> f32 {
+    if next_limit >= segment_limit {
+        return segment_limit;
+    }
+
+    (next_limit.powi(2) + 2.0 * deceleration * distance_to_end.max(0.0))
+        .sqrt()
+        .min(segment_limit)
+}
+
+fn blend(safe_value: f32, aggressive_value: f32, aggression: f32, offset: f32) -> f32 {
+    lerp(safe_value, aggressive_value, aggression) + offset
 }
 
 fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + (b - a) * t
 }
 
+/// Updates every vehicle's speed from the IDM. Vehicles only read the road and the
+/// occupancy snapshot and write their own state, so they run in parallel with the
+/// `parallel` feature. Noise comes from a per-vehicle stream of one seed drawn per step,
+/// which keeps runs reproducible however the work is split.
+#[allow(clippy::type_complexity)]
 pub fn apply_idm(
     time: Res<Time>,
-    mut vehicles: Query<(Entity, &mut Vehicle), Without<PlayerControlled>>,
+    mut vehicles: Query<
+        (Entity, &mut Vehicle, Option<&mut VehicleTelemetry>),
+        (Without<PlayerControlled>, Without<Frozen>),
+    >,
     occupancy: Res<SegmentOccupancy>,
     road: Res<Road>,
+    mut rng: ResMut<SimRng>,
 ) {
-    for (entity, mut vehicle) in &mut vehicles {
-        let segment = road.segments.get(&vehicle.segment);
+    let dt = sim_dt(&time);
+    let noise_seed = rng.next_seed();
+
+    #[cfg(feature = "parallel")]
+    bevy_tasks::ComputeTaskPool::get_or_init(bevy_tasks::TaskPool::default);
+
+    vehicles
+        .par_iter_mut()
+        .for_each(|(entity, mut vehicle, telemetry)| {
+            let update = follow(entity, &mut vehicle, &occupancy, &road, dt, noise_seed);
+            if let Some(mut telemetry) = telemetry {
+                *telemetry = update;
+            }
+        });
+}
+
+/// One IDM step for `vehicle`, returning what it responded to
+fn follow(
+    entity: Entity,
+    vehicle: &mut Vehicle,
+    occupancy: &SegmentOccupancy,
+    road: &Road,
+    dt: f32,
+    noise_seed: u64,
+) -> VehicleTelemetry {
+    let segment = road.segments.get(&vehicle.segment);
 
-        let next_driver = occupancy.find_next(entity, &vehicle, &road);
-        // Distance from front bumper to end of segment (stop line)
-        let distance_to_end =
-            ((1.0 - vehicle.progress) * segment.length - vehicle.length / 2.0).max(0.0);
+    let next_driver = occupancy.find_next(entity, vehicle, road);
+    // Distance from front bumper to end of segment (stop line)
+    let distance_to_end =
+        ((1.0 - vehicle.progress) * segment.length - vehicle.length / 2.0).max(0.0);
 
-        let (gap, delta_speed) = if vehicle.gap.waiting_time.is_some() && !vehicle.gap.cleared_to_go
-        {
+    let (gap, delta_speed, leader) =
+        if vehicle.gap.waiting_time.is_some() && !vehicle.gap.cleared_to_go {
             // Waiting - stop at end of segment (front bumper at stop line)
             // Also consider vehicle ahead (take smaller gap)
-            if let Some((next_occupant, distance)) = next_driver {
-                let min_gap = distance.min(distance_to_end);
-                let delta = if min_gap == distance {
-                    vehicle.speed - next_occupant.speed
-                } else {
-                    vehicle.speed
-                };
-                (min_gap, delta)
-            } else {
-                (distance_to_end, vehicle.speed)
+            match next_driver {
+                Some((next_occupant, distance)) if distance <= distance_to_end => (
+                    distance,
+                    vehicle.speed - next_occupant.speed,
+                    Some(next_occupant.vehicle),
+                ),
+                _ => (distance_to_end, vehicle.speed, None),
             }
         } else if let Some((next_occupant, distance)) = next_driver {
-            (distance, vehicle.speed - next_occupant.speed)
+            (
+                distance,
+                vehicle.speed - next_occupant.speed,
+                Some(next_occupant.vehicle),
+            )
         } else {
-            (f32::MAX, 0.0)
+            (f32::MAX, 0.0, None)
+        };
+
+    // Start slowing for a slower turn segment before reaching it
+    let segment_limit = cornering_speed_limit(road, segment);
+    let speed_limit = match vehicle.route.get(1) {
+        Some(next) => approach_speed_limit(
+            segment_limit,
+            cornering_speed_limit(road, road.segments.get(next)),
+            distance_to_end,
+            vehicle.idm.comfortable_deceleration,
+        ),
+        None => segment_limit,
+    };
+
+    // Speed-scaled jitter: none at standstill, full sigma at the speed limit
+    let noise = if vehicle.idm.noise_sigma > 0.0 {
+        let scale = (vehicle.speed / speed_limit.max(0.1)).min(1.0);
+        let mut rng = SimRng::stream(noise_seed, entity.to_bits());
+        vehicle.idm.noise_sigma * scale * rng.gaussian()
+    } else {
+        0.0
+    };
+
+    // Standing queue discharge: only start moving a reaction delay after the leader does,
+    // so queues unzip front-to-back instead of accelerating in unison
+    let queued_behind = next_driver
+        .filter(|(_, distance)| *distance < QUEUE_GAP)
+        .map(|(leader, _)| leader.speed);
+    let held = match queued_behind {
+        Some(leader_speed) if vehicle.speed < STANDSTILL_SPEED => {
+            if leader_speed > STANDSTILL_SPEED {
+                vehicle.queue_release_timer += dt;
+            } else {
+                vehicle.queue_release_timer = 0.0;
+            }
+            vehicle.queue_release_timer < vehicle.idm.startup_delay
+        }
+        _ => {
+            vehicle.queue_release_timer = 0.0;
+            false
+        }
+    };
+
+    let idm = &vehicle.idm;
+    let previous = vehicle.acceleration;
+    let (acceleration, speed) = idm.step_with(
+        vehicle.speed,
+        speed_limit,
+        gap,
+        delta_speed,
+        dt,
+        |acceleration| {
+            let acceleration = acceleration + noise;
+            let acceleration = if held {
+                acceleration.min(0.0)
+            } else {
+                acceleration
+            };
+            idm.limit_jerk(previous, acceleration, dt)
+        },
+    );
+    vehicle.acceleration = acceleration;
+    vehicle.speed = speed;
+
+    // Brake lights on when decelerating significantly
+    vehicle.braking = acceleration < -0.5;
+
+    VehicleTelemetry {
+        acceleration,
+        gap,
+        delta_speed,
+        leader,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{move_and_despawn_vehicles, update_occupancy};
+    use crate::{SegmentGeometry, SimRng, SimulationStats};
+    use bevy_ecs::{system::RunSystemOnce, world::World};
+    use glam::Vec3;
+    use std::time::Duration;
+
+    fn step(world: &mut World, dt: f32) {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(dt));
+        world.run_system_once(update_occupancy).unwrap();
+        world.run_system_once(apply_idm).unwrap();
+        world.run_system_once(move_and_despawn_vehicles).unwrap();
+    }
+
+    /// [`apply_idm`] one vehicle at a time, as the reference for the parallel version
+    #[cfg(feature = "parallel")]
+    #[allow(clippy::type_complexity)]
+    fn apply_idm_serially(
+        time: Res<Time>,
+        mut vehicles: Query<
+            (Entity, &mut Vehicle, &mut VehicleTelemetry),
+            (Without<PlayerControlled>, Without<Frozen>),
+        >,
+        occupancy: Res<SegmentOccupancy>,
+        road: Res<Road>,
+        mut rng: ResMut<SimRng>,
+    ) {
+        let dt = sim_dt(&time);
+        let noise_seed = rng.next_seed();
+        for (entity, mut vehicle, mut telemetry) in &mut vehicles {
+            *telemetry = follow(entity, &mut vehicle, &occupancy, &road, dt, noise_seed);
+        }
+    }
+
+    // Without the feature both runs are serial, so only compare them when it's on
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_idm_matches_serial() {
+        // Hundreds of noisy drivers of every temperament on one long road
+        let run = |parallel: bool| {
+            let mut road = Road::default();
+            let a = road.add_spawn_node(Vec3::ZERO);
+            let b = road.add_despawn_node(Vec3::new(20_000.0, 0.0, 0.0));
+            let segment = road.add_segment(a, b, 22.2);
+
+            let mut world = World::new();
+            world.init_resource::<Time>();
+            world.init_resource::<SegmentOccupancy>();
+            world.init_resource::<SimulationStats>();
+            world.insert_resource(SimRng::seeded(7));
+            world.insert_resource(road);
+
+            let mut rng = SimRng::seeded(3);
+            for i in 0..500 {
+                let speed = rng.uniform() * 20.0;
+                let mut vehicle =
+                    Vehicle::random(segment, b, vec![segment], VehicleClass::Car, &mut rng)
+                        .with_progress(i as f32 / 1000.0)
+                        .with_speed(speed);
+                vehicle.idm.noise_sigma = 0.2;
+                world.spawn(vehicle);
+            }
+
+            for _ in 0..200 {
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_secs_f32(0.05));
+                world.run_system_once(update_occupancy).unwrap();
+                if parallel {
+                    world.run_system_once(apply_idm).unwrap();
+                } else {
+                    world.run_system_once(apply_idm_serially).unwrap();
+                }
+                world.run_system_once(move_and_despawn_vehicles).unwrap();
+            }
+
+            let mut vehicles: Vec<_> = world
+                .query::<(Entity, &Vehicle, &VehicleTelemetry)>()
+                .iter(&world)
+                .map(|(entity, vehicle, telemetry)| {
+                    (entity, vehicle.progress, vehicle.speed, *telemetry)
+                })
+                .collect();
+            vehicles.sort_by_key(|(entity, ..)| *entity);
+            vehicles
         };
 
-        let acceleration =
-            vehicle
-                .idm
-                .acceleration(segment.speed_limit, vehicle.speed, gap, delta_speed);
+        let parallel = run(true);
+        assert_eq!(parallel.len(), 500);
+        assert!(parallel
+            .iter()
+            .any(|(.., telemetry)| telemetry.leader.is_some()));
+        assert_eq!(parallel, run(false));
+    }
+
+    #[test]
+    fn test_braking_follows_acceleration_sign() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.05));
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        // Closing fast on a stopped car 10 m ahead
+        let leader = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.35))
+            .id();
+        let follower = world
+            .spawn(
+                Vehicle::new(segment, b, vec![segment])
+                    .with_progress(0.3)
+                    .with_speed(10.0),
+            )
+            .id();
 
-        // Brake lights on when decelerating significantly
-        vehicle.braking = acceleration < -0.5;
+        world.run_system_once(update_occupancy).unwrap();
+        world.run_system_once(apply_idm).unwrap();
+        assert!(world.get::<Vehicle>(follower).unwrap().braking);
+
+        // Road clears: easing off the brake switches the lights off within a second
+        world.despawn(leader);
+        world.run_system_once(update_occupancy).unwrap();
+        for _ in 0..20 {
+            world.run_system_once(apply_idm).unwrap();
+        }
+        assert!(!world.get::<Vehicle>(follower).unwrap().braking);
+    }
+
+    #[test]
+    fn test_telemetry_reports_leader_being_braked_for() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        let leader = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.35))
+            .id();
+        let follower = world
+            .spawn(
+                Vehicle::new(segment, b, vec![segment])
+                    .with_progress(0.3)
+                    .with_speed(10.0),
+            )
+            .id();
+        assert_eq!(
+            *world.get::<VehicleTelemetry>(follower).unwrap(),
+            VehicleTelemetry::default()
+        );
+
+        step(&mut world, 0.05);
+        let telemetry = *world.get::<VehicleTelemetry>(follower).unwrap();
+        assert!(telemetry.acceleration < 0.0);
+        assert_eq!(telemetry.leader, Some(leader));
+        assert!(telemetry.gap < 10.0);
+        assert_eq!(telemetry.delta_speed, 10.0);
+
+        // The leader itself has a free road ahead
+        let telemetry = world.get::<VehicleTelemetry>(leader).unwrap();
+        assert_eq!(telemetry.leader, None);
+        assert_eq!(telemetry.gap, f32::MAX);
+    }
+
+    #[test]
+    fn test_standing_queue_discharges_front_to_back() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        // Three stopped cars bumper to bumper with 2m gaps, front car has a clear road
+        let queue: Vec<Entity> = [0.3, 0.265, 0.23]
+            .into_iter()
+            .map(|progress| {
+                let mut vehicle = Vehicle::new(segment, b, vec![segment]);
+                vehicle.progress = progress;
+                vehicle.idm.startup_delay = 1.0;
+                world.spawn(vehicle).id()
+            })
+            .collect();
+
+        let mut started_at = vec![None; queue.len()];
+        for tick in 0..200 {
+            step(&mut world, 0.05);
+            for (i, entity) in queue.iter().enumerate() {
+                let speed = world.get::<Vehicle>(*entity).unwrap().speed;
+                if started_at[i].is_none() && speed > STANDSTILL_SPEED {
+                    started_at[i] = Some(tick);
+                }
+            }
+        }
+
+        let started_at: Vec<u32> = started_at.into_iter().map(Option::unwrap).collect();
+        assert!(started_at[0] < started_at[1]);
+        assert!(started_at[1] < started_at[2]);
+        // Each follower waits at least its reaction delay (20 ticks) after its leader
+        assert!(started_at[1] - started_at[0] >= 20);
+        assert!(started_at[2] - started_at[1] >= 20);
+    }
+
+    #[test]
+    fn test_acceleration_changes_no_faster_than_max_jerk() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(2000.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        // Leader surging between 6 and 10 m/s, 40 m ahead of a follower that starts from a
+        // standstill
+        let leader = world
+            .spawn((
+                Vehicle::new(segment, b, vec![segment])
+                    .with_progress(0.03)
+                    .with_speed(10.0),
+                PlayerControlled,
+            ))
+            .id();
+        let follower = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.01))
+            .id();
+
+        let dt = 0.05;
+        let mut previous = 0.0;
+        let mut largest_change: f32 = 0.0;
+        for tick in 0..1200 {
+            world.get_mut::<Vehicle>(leader).unwrap().speed =
+                8.0 + 2.0 * (tick as f32 * dt * 0.5).sin();
+            step(&mut world, dt);
+
+            let vehicle = world.get::<Vehicle>(follower).unwrap();
+            assert!(
+                vehicle.acceleration >= -vehicle.idm.comfortable_deceleration,
+                "normal following never needs emergency braking"
+            );
+            let change = (vehicle.acceleration - previous).abs();
+            assert!(
+                change <= vehicle.idm.max_jerk * dt + 1e-4,
+                "tick {tick}: {change}"
+            );
+            largest_change = largest_change.max(change);
+            previous = vehicle.acceleration;
+        }
+        // The limit was actually in play
+        let max_jerk = world.get::<Vehicle>(follower).unwrap().idm.max_jerk;
+        assert!(largest_change > max_jerk * dt * 0.9);
+    }
+
+    #[test]
+    fn test_aggressive_drivers_change_acceleration_faster() {
+        let jerk = |aggression| Idm::typical(aggression, VehicleClass::Car).max_jerk;
+        assert_eq!(jerk(0.5), DEFAULT_MAX_JERK);
+        assert!(jerk(0.0) < jerk(0.5) && jerk(0.5) < jerk(1.0));
+
+        let mut rng = SimRng::seeded(5);
+        let jerks: Vec<_> = (0..100)
+            .map(|_| Idm::new(0.5, VehicleClass::Car, &mut rng).max_jerk)
+            .collect();
+        assert!(jerks.iter().any(|&jerk| jerk != DEFAULT_MAX_JERK));
+        assert!(jerks.iter().all(|jerk| (2.0..=10.0).contains(jerk)));
+    }
+
+    /// Drives at exactly the speed limit: aggression 0.5, 1.5 s headway, 2 m spacing,
+    /// 1 m/s² acceleration, 2 m/s² deceleration
+    fn known_driver() -> Idm {
+        Idm::from_params(0.5, 1.5, 2.0, 1.0, 2.0, 4.0, 1.0, 0.0, DEFAULT_MAX_JERK)
+    }
+
+    #[test]
+    fn test_acceleration_matches_hand_computation() {
+        let idm = known_driver();
+
+        // Standing start on an open road: full max_acceleration
+        assert_eq!(idm.acceleration(10.0, 0.0, f32::MAX, 0.0), 1.0);
+
+        // s* = 2 + 5 * 1.5 = 9.5; a = 1 - (5/10)^4 - (9.5/20)^2
+        let following = idm.acceleration(10.0, 5.0, 20.0, 0.0);
+        assert!((following - 0.711875).abs() < 1e-5);
+
+        // Closing at 2 m/s adds 5 * 2 / (2 * sqrt(1 * 2)) to s*
+        let closing = idm.acceleration(10.0, 5.0, 20.0, 2.0);
+        assert!((closing - 0.512687).abs() < 1e-5);
+
+        // Far too close: clamped to twice the comfortable deceleration
+        assert_eq!(idm.acceleration(10.0, 10.0, 1.0, 0.0), -4.0);
+    }
+
+    #[test]
+    fn test_step_converges_to_desired_speed_on_empty_road() {
+        let idm = known_driver();
+        let mut speed = 0.0;
+        for _ in 0..1200 {
+            speed = idm.step(speed, 10.0, f32::MAX, 0.0, 0.05);
+        }
+        assert!((speed - 10.0).abs() < 0.1, "speed {speed}");
+    }
+
+    #[test]
+    fn test_step_keeps_safe_distance_behind_constant_leader() {
+        let idm = known_driver();
+        let leader_speed = 8.0;
+        let (mut speed, mut gap) = (10.0, 60.0);
+        let mut closest = gap;
+        for _ in 0..2400 {
+            speed = idm.step(speed, 10.0, gap, speed - leader_speed, 0.05);
+            gap += (leader_speed - speed) * 0.05;
+            closest = f32::min(closest, gap);
+        }
+
+        assert!((speed - leader_speed).abs() < 0.05, "speed {speed}");
+        // Equilibrium gap is at least the desired gap: min_spacing + speed * headway
+        assert!(gap >= 2.0 + leader_speed * 1.5 - 0.1, "gap {gap}");
+        assert!(closest > 2.0);
+    }
+
+    #[test]
+    fn test_lower_exponent_approaches_desired_speed_gently() {
+        let mut idm = Idm::typical(0.5, VehicleClass::Car);
+        let speed_limit = 10.0;
+        // Aggression 0.5 drives at exactly the speed limit
+        let half_speed = speed_limit / 2.0;
+
+        idm.acceleration_exponent = 4.0;
+        let sharp = idm.acceleration(speed_limit, half_speed, f32::MAX, 0.0);
+        idm.acceleration_exponent = 1.0;
+        let gentle = idm.acceleration(speed_limit, half_speed, f32::MAX, 0.0);
+
+        assert!(gentle < sharp);
+        assert!((sharp - idm.max_acceleration * (1.0 - 0.5f32.powi(4))).abs() < 1e-4);
+        assert!((gentle - idm.max_acceleration * 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_slows_before_reaching_slow_turn() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(150.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(170.0, 0.0, 0.0));
+        let approach = road.add_segment(a, b, 13.9);
+        let turn = road.add_segment(b, c, 5.0);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        let mut vehicle = Vehicle::new(approach, c, vec![approach, turn]);
+        vehicle.idm = Idm::typical(0.5, VehicleClass::Car);
+        vehicle.speed = 13.0;
+        let entity = world.spawn(vehicle).id();
+
+        let mut speed_at_junction = None;
+        for _ in 0..400 {
+            step(&mut world, 0.05);
+            let vehicle = world.get::<Vehicle>(entity).unwrap();
+            if vehicle.segment != approach {
+                break;
+            }
+            speed_at_junction = Some(vehicle.speed);
+        }
+
+        // Without the taper the vehicle would reach the turn near 13 m/s
+        let speed = speed_at_junction.unwrap();
+        assert!(speed < 8.0, "still at {speed} m/s entering the turn");
+    }
+
+    /// Speeds of a lone car accelerating from 5 m/s on an open road
+    fn noisy_trajectory(noise_sigma: f32, seed: u64) -> Vec<f32> {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(1000.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.insert_resource(SimRng::seeded(seed));
+        world.insert_resource(road);
+
+        let mut vehicle = Vehicle::new(segment, b, vec![segment]).with_speed(5.0);
+        vehicle.idm.aggression = 0.5;
+        vehicle.idm.max_acceleration = 1.5;
+        vehicle.idm.noise_sigma = noise_sigma;
+        let entity = world.spawn(vehicle).id();
+
+        (0..100)
+            .map(|_| {
+                step(&mut world, 0.1);
+                world.get::<Vehicle>(entity).unwrap().speed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_acceleration_noise_is_opt_in_and_deterministic() {
+        let smooth = noisy_trajectory(0.0, 1);
+        assert_eq!(
+            smooth,
+            noisy_trajectory(0.0, 2),
+            "seed must not matter without noise"
+        );
+
+        let noisy = noisy_trajectory(0.3, 1);
+        assert_eq!(noisy, noisy_trajectory(0.3, 1));
+        assert_ne!(noisy, smooth);
+        assert_ne!(noisy, noisy_trajectory(0.3, 2));
+    }
+
+    #[test]
+    fn test_approach_limit_tapers_to_next_limit() {
+        assert_eq!(approach_speed_limit(13.9, 20.0, 10.0, 2.0), 13.9);
+        assert_eq!(approach_speed_limit(13.9, 5.0, 500.0, 2.0), 13.9);
+        assert_eq!(approach_speed_limit(13.9, 5.0, 0.0, 2.0), 5.0);
+
+        let mid = approach_speed_limit(13.9, 5.0, 20.0, 2.0);
+        assert!(mid > 5.0 && mid < 13.9);
+    }
+
+    #[test]
+    fn test_tighter_curves_lower_the_speed_limit() {
+        let mut road = Road::default();
+        let center = Vec3::ZERO;
+        let mut quarter_turn = |radius: f32| {
+            let from = road.add_node(Vec3::new(radius, 0.0, 0.0));
+            let to = road.add_node(Vec3::new(0.0, radius, 0.0));
+            let id = road.add_segment(from, to, 13.9);
+            let segment = road.segments.get_mut(&id);
+            segment.geometry = SegmentGeometry::Curved {
+                center,
+                radius,
+                clockwise: false,
+            };
+            id
+        };
+        let tight = quarter_turn(6.0);
+        let wide = quarter_turn(30.0);
+        let straight = {
+            let a = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+            let b = road.add_node(Vec3::new(200.0, 0.0, 0.0));
+            road.add_segment(a, b, 13.9)
+        };
 
-        vehicle.speed = (vehicle.speed + acceleration * time.delta_secs()).max(0.0);
+        let limit = |id| cornering_speed_limit(&road, road.segments.get(&id));
+        let expected = (COMFORTABLE_LATERAL_ACCELERATION * 6.0).sqrt();
+        assert!((limit(tight) - expected).abs() < 1e-4);
+        assert!(limit(tight) < limit(wide));
+        assert!(limit(wide) < 13.9);
+        assert_eq!(limit(straight), 13.9);
     }
 }
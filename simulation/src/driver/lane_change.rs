@@ -0,0 +1,232 @@
+//! Overtaking on multi-lane segments.
+//!
+//! A driver held up by a slow leader moves to an adjacent lane when the IDM says it would
+//! accelerate harder there and the vehicle it cuts in front of would not have to brake hard.
+//! Lane changes are instantaneous and only happen on straight segments; curved lanes still
+//! share the centerline.
+
+use bevy_ecs::prelude::*;
+
+use crate::{
+    driver::{FreeDrive, Frozen, Occupant, PlayerControlled, SegmentOccupancy, Vehicle},
+    Road, SegmentGeometry,
+};
+
+/// Leaders further ahead than this bumper gap (m) aren't worth overtaking yet
+const OVERTAKE_LOOKAHEAD: f32 = 60.0;
+
+/// A leader slower than this fraction of our desired speed is holding us up
+const SLOW_LEADER_RATIO: f32 = 0.9;
+
+/// Acceleration (m/s²) a lane change has to gain, so drivers don't weave for nothing
+const LANE_CHANGE_THRESHOLD: f32 = 0.2;
+
+/// Hardest braking (m/s²) a lane change may force on the vehicle behind in the new lane
+const SAFE_DECELERATION: f32 = 3.0;
+
+type LaneChangers<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static mut Vehicle),
+    (
+        Without<PlayerControlled>,
+        Without<Frozen>,
+        Without<FreeDrive>,
+    ),
+>;
+
+pub fn change_lanes(
+    mut vehicles: LaneChangers,
+    mut occupancy: ResMut<SegmentOccupancy>,
+    road: Res<Road>,
+) {
+    let mut changes = vec![];
+
+    for (entity, vehicle) in &vehicles {
+        let Some(lane) = choose_lane(entity, vehicle, &vehicles, &occupancy, &road) else {
+            continue;
+        };
+
+        // Move the occupant right away so later drivers don't pick the same gap
+        let from = (vehicle.segment, vehicle.lane);
+        if !occupancy.move_to_lane(entity, from, lane) {
+            continue;
+        }
+
+        changes.push((entity, lane));
+    }
+
+    for (entity, lane) in changes {
+        if let Ok((_, mut vehicle)) = vehicles.get_mut(entity) {
+            vehicle.lane = lane;
+        }
+    }
+}
+
+/// Adjacent lane worth moving to, if any
+fn choose_lane(
+    entity: Entity,
+    vehicle: &Vehicle,
+    vehicles: &LaneChangers,
+    occupancy: &SegmentOccupancy,
+    road: &Road,
+) -> Option<u8> {
+    let segment = road.segments.get(&vehicle.segment);
+    if segment.lanes <= 1
+        || !matches!(segment.geometry, SegmentGeometry::Straight)
+        || vehicle.gap.waiting_time.is_some()
+    {
+        return None;
+    }
+
+    let limit = segment.speed_limit;
+    let (leader, gap) = occupancy.find_next(entity, vehicle, road)?;
+    if gap > OVERTAKE_LOOKAHEAD
+        || leader.speed >= vehicle.idm.desired_speed(limit) * SLOW_LEADER_RATIO
+    {
+        return None;
+    }
+    let current = acceleration_behind(vehicle, Some((leader, gap)), limit);
+
+    let left = vehicle
+        .lane
+        .checked_add(1)
+        .filter(|lane| *lane < segment.lanes);
+    let right = vehicle.lane.checked_sub(1);
+    [left, right]
+        .into_iter()
+        .flatten()
+        .filter(|lane| road.lane_serves_route(&vehicle.route, *lane))
+        .filter_map(|lane| {
+            let ahead = occupancy.find_next_in_lane(entity, vehicle, lane, road);
+            let gain = acceleration_behind(vehicle, ahead, limit) - current;
+            if gain < LANE_CHANGE_THRESHOLD {
+                return None;
+            }
+
+            if let Some((follower, gap)) =
+                occupancy.find_previous_in_lane(entity, vehicle, lane, road)
+            {
+                // Drivers outside this system (player, frozen) are judged by our own manners
+                let idm = vehicles
+                    .get(follower.vehicle)
+                    .map_or(&vehicle.idm, |(_, follower)| &follower.idm);
+                let braking =
+                    idm.acceleration(limit, follower.speed, gap, follower.speed - vehicle.speed);
+                if gap < idm.min_spacing || braking < -SAFE_DECELERATION {
+                    return None;
+                }
+            }
+
+            Some((lane, gain))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(lane, _)| lane)
+}
+
+/// IDM acceleration of `vehicle` following `ahead`, or on a free road without one
+fn acceleration_behind(vehicle: &Vehicle, ahead: Option<(&Occupant, f32)>, limit: f32) -> f32 {
+    match ahead {
+        Some((leader, gap)) => {
+            vehicle
+                .idm
+                .acceleration(limit, vehicle.speed, gap, vehicle.speed - leader.speed)
+        }
+        None => vehicle
+            .idm
+            .acceleration(limit, vehicle.speed, f32::MAX, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{apply_idm, move_and_despawn_vehicles, update_occupancy, VehicleClass};
+    use crate::{Id, Node, Segment, SimRng};
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_time::Time;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    /// Straight 400 m segment with `lanes` lanes ending at a despawn node
+    fn straight(lanes: u8) -> (Road, Id<Segment>, Id<Node>) {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(400.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+        road.segments.get_mut(&segment).lanes = lanes;
+        (road, segment, b)
+    }
+
+    /// Fast car 20 m behind a player-driven car crawling at 4 m/s; returns the fast car's
+    /// lead over the slow one after 20 s, and the lanes the fast car used
+    fn overtake(lanes: u8) -> (f32, Vec<u8>) {
+        let (road, segment, end) = straight(lanes);
+        let mut world = World::new();
+        world.insert_resource(road);
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<Time>();
+        world.init_resource::<crate::SimulationStats>();
+        world.insert_resource(SimRng::seeded(1));
+
+        let mut fast = Vehicle::new(segment, end, vec![segment])
+            .with_progress(0.1)
+            .with_speed(12.0);
+        fast.idm = crate::driver::Idm::typical(1.0, VehicleClass::Car);
+        let fast = world.spawn(fast).id();
+        let slow = Vehicle::new(segment, end, vec![segment])
+            .with_progress(0.15)
+            .with_speed(4.0);
+        let slow = world.spawn((slow, PlayerControlled)).id();
+
+        let mut lanes_used = vec![0];
+        for _ in 0..400 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(50));
+            world.run_system_once(update_occupancy).unwrap();
+            world.run_system_once(change_lanes).unwrap();
+            world.run_system_once(apply_idm).unwrap();
+            world.run_system_once(move_and_despawn_vehicles).unwrap();
+            world.get_mut::<Vehicle>(slow).unwrap().speed = 4.0;
+
+            let lane = world.get::<Vehicle>(fast).unwrap().lane;
+            if lanes_used.last() != Some(&lane) {
+                lanes_used.push(lane);
+            }
+        }
+
+        let progress = |entity| world.get::<Vehicle>(entity).unwrap().progress;
+        ((progress(fast) - progress(slow)) * 400.0, lanes_used)
+    }
+
+    #[test]
+    fn test_fast_car_overtakes_slow_car_on_two_lanes() {
+        let (lead, lanes_used) = overtake(2);
+        assert!(lead > 0.0, "still {:.1} m behind", -lead);
+        assert_eq!(lanes_used, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_single_lane_keeps_fast_car_behind() {
+        let (lead, lanes_used) = overtake(1);
+        assert!(lead < 0.0);
+        assert_eq!(lanes_used, vec![0]);
+    }
+
+    #[test]
+    fn test_lanes_are_offset_from_the_centerline() {
+        let (mut road, segment, _) = straight(2);
+        // Eastbound, so the rightmost lane lies south of the centerline
+        let right = road.lane_position_on(segment, 0.5, 0);
+        let left = road.lane_position_on(segment, 0.5, 1);
+        assert!((right - Vec3::new(200.0, -crate::LANE_WIDTH / 2.0, 0.0)).length() < 1e-3);
+        assert!((left - Vec3::new(200.0, crate::LANE_WIDTH / 2.0, 0.0)).length() < 1e-3);
+
+        road.segments.get_mut(&segment).lanes = 1;
+        assert_eq!(
+            road.lane_position_on(segment, 0.5, 0),
+            road.position_on(segment, 0.5)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Messages marking the start and end of a vehicle's life, for UI, metrics and rendering
+//! to react to without polling the world.
+
+use bevy_ecs::prelude::*;
+
+use crate::{Id, Intersection, Node};
+
+/// A vehicle entered the road at `origin`
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct VehicleSpawned {
+    pub entity: Entity,
+    pub origin: Id<Node>,
+}
+
+/// A vehicle reached its destination; followed by a [`VehicleDespawned`] with
+/// [`DespawnReason::Arrived`]
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct VehicleArrived {
+    pub entity: Entity,
+    pub destination: Id<Node>,
+    /// Seconds since spawning
+    pub travel_time: f32,
+}
+
+/// A vehicle left the simulation
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct VehicleDespawned {
+    pub entity: Entity,
+    pub reason: DespawnReason,
+}
+
+/// A vehicle moved from its approach onto a turn through `intersection`
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct IntersectionCleared {
+    pub entity: Entity,
+    pub intersection: Id<Intersection>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DespawnReason {
+    /// Reached its destination
+    Arrived,
+    /// Ran into a node with no way out that isn't its destination
+    DeadEnd,
+    /// No route left to its destination
+    NoRoute,
+    /// The segment it was on or the node it was heading to was removed
+    RoadRemoved,
+}
@@ -18,3 +18,24 @@ pub use yielding::*;
 
 mod blinker;
 pub use blinker::*;
+
+mod class;
+pub use class::*;
+
+mod free_drive;
+pub use free_drive::*;
+
+mod lifecycle;
+pub use lifecycle::*;
+
+mod lane_change;
+pub use lane_change::*;
+
+mod congestion;
+pub use congestion::*;
+
+mod route_cache;
+pub use route_cache::*;
+
+mod collision;
+pub use collision::*;
@@ -1,6 +1,18 @@
-use crate::{driver::Vehicle, Id, Road, Segment};
+use crate::{
+    driver::{FreeDrive, Vehicle},
+    Id, Road, Segment,
+};
 use bevy_ecs::prelude::*;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// Default number of segments `find_next` looks through before giving up
+pub const DEFAULT_MAX_LOOKAHEAD_HOPS: usize = 10;
+
+/// Bumper gap (m) counted per vehicle when measuring how full a segment is
+const JAM_SPACING: f32 = 2.0;
 
 #[derive(Debug)]
 pub struct Occupant {
@@ -8,112 +20,606 @@ pub struct Occupant {
     pub vehicle: Entity,
     pub speed: f32,
     pub segment: Id<Segment>,
+    pub lane: u8,
     /// Vehicle length in meters (for gap calculations)
     pub length: f32,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct SegmentOccupancy {
-    pub vehicles: HashMap<Id<Segment>, Vec<Occupant>>,
+    /// Occupants of each lane of each segment, sorted by progress
+    pub vehicles: HashMap<(Id<Segment>, u8), Vec<Occupant>>,
+    /// Segments `find_next` searches (including the vehicle's own) before giving up
+    pub max_lookahead_hops: usize,
+    /// Times `find_next` gave up at the hop cap while the road still continued.
+    /// Non-zero means leaders beyond the cap may have been missed.
+    pub lookahead_cap_hits: AtomicU32,
+    /// Lane each tracked vehicle is filed under, so [`update_occupancy`] only moves
+    /// vehicles that changed segment or lane
+    slots: HashMap<Entity, (Id<Segment>, u8)>,
+}
+
+impl Default for SegmentOccupancy {
+    fn default() -> Self {
+        Self {
+            vehicles: HashMap::new(),
+            max_lookahead_hops: DEFAULT_MAX_LOOKAHEAD_HOPS,
+            lookahead_cap_hits: AtomicU32::new(0),
+            slots: HashMap::new(),
+        }
+    }
 }
 
 impl SegmentOccupancy {
-    pub fn is_occupied(&self, segment: Id<Segment>) -> bool {
-        if let Some(occupants) = self.vehicles.get(&segment) {
-            !occupants.is_empty()
-        } else {
-            false
+    /// Whether any lane of the segment holds a vehicle
+    pub fn is_occupied(&self, segment: Id<Segment>, road: &Road) -> bool {
+        let lanes = road.segments.get(&segment).lanes.max(1);
+        (0..lanes).any(|lane| !self.lane(segment, lane).is_empty())
+    }
+
+    /// How full a segment is, from 0.0 (empty) to 1.0 (bumper to bumper in every lane)
+    pub fn density(&self, segment: Id<Segment>, road: &Road) -> f32 {
+        let data = road.segments.get(&segment);
+        let capacity = data.length * data.lanes.max(1) as f32;
+        if capacity <= f32::EPSILON {
+            return 0.0;
         }
+
+        let occupied: f32 = (0..data.lanes.max(1))
+            .flat_map(|lane| self.lane(segment, lane))
+            .map(|occupant| occupant.length + JAM_SPACING)
+            .sum();
+        (occupied / capacity).min(1.0)
+    }
+
+    /// Moves a vehicle's occupant from `from` to `lane` of the same segment, keeping the
+    /// target lane sorted. Returns false if the vehicle isn't filed under `from`.
+    pub fn move_to_lane(&mut self, entity: Entity, from: (Id<Segment>, u8), lane: u8) -> bool {
+        let Some(occupants) = self.vehicles.get_mut(&from) else {
+            return false;
+        };
+        let Some(index) = occupants.iter().position(|occ| occ.vehicle == entity) else {
+            return false;
+        };
+        let mut occupant = occupants.remove(index);
+        occupant.lane = lane;
+        self.insert((from.0, lane), occupant);
+        true
+    }
+
+    /// Files an occupant under `key` at its place in the progress order
+    fn insert(&mut self, key: (Id<Segment>, u8), occupant: Occupant) {
+        self.slots.insert(occupant.vehicle, key);
+        let target = self.vehicles.entry(key).or_default();
+        let index = target.partition_point(|occ| occ.progress <= occupant.progress);
+        target.insert(index, occupant);
+    }
+
+    /// Occupants of one lane of a segment, sorted by progress
+    pub fn lane(&self, segment: Id<Segment>, lane: u8) -> &[Occupant] {
+        self.vehicles
+            .get(&(segment, lane))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
     }
 
-    /// Returns the next occupant ahead and the bumper-to-bumper distance in meters
+    /// Returns the next occupant ahead and the bumper-to-bumper distance in meters,
+    /// looking along the vehicle's route past the end of its segment but not past the
+    /// end of the route
     pub fn find_next(
         &self,
         entity: Entity,
         vehicle: &Vehicle,
         road: &Road,
+    ) -> Option<(&Occupant, f32)> {
+        self.find_next_in_lane(entity, vehicle, vehicle.lane, road)
+    }
+
+    /// [`SegmentOccupancy::find_next`] as if the vehicle drove in `lane` of its segment.
+    /// Further along the route it looks in the lane the vehicle will pick there.
+    pub fn find_next_in_lane(
+        &self,
+        entity: Entity,
+        vehicle: &Vehicle,
+        lane: u8,
+        road: &Road,
     ) -> Option<(&Occupant, f32)> {
         let mut segment = vehicle.segment;
-        let mut max_iteration = 10;
+        let mut lane = lane;
         let mut progress = vehicle.progress;
-        let mut accumulated_distance: f32 = 0.0;
-        let mut first_segment = true;
+        // Distance from our center to the start of `segment` (negative on our own segment)
+        let mut segment_start = -vehicle.progress * road.segments.get(&segment).length;
 
-        loop {
+        for hop in 1..=self.max_lookahead_hops {
             let seg_data = road.segments.get(&segment);
-            let segment_length = seg_data.length;
 
-            let occupants = self.vehicles.get(&segment);
-
-            if let Some(occupants) = occupants {
-                // Find next car ahead, excluding self
-                let next = occupants
-                    .iter()
-                    .find(|occ| occ.progress > progress && occ.vehicle != entity);
-
-                if let Some(occ) = next {
-                    // Calculate center-to-center distance
-                    let center_distance = if first_segment {
-                        // Same segment: simple progress difference
-                        (occ.progress - vehicle.progress) * segment_length
-                    } else {
-                        // Different segment: accumulated + their progress
-                        accumulated_distance + occ.progress * segment_length
-                    };
-                    // Convert to bumper-to-bumper distance (front of us to rear of them)
-                    let bumper_distance = center_distance - vehicle.length / 2.0 - occ.length / 2.0;
-                    return Some((occ, bumper_distance.max(0.0)));
-                }
-            }
+            // Find next car ahead, excluding self
+            let next = self
+                .lane(segment, lane)
+                .iter()
+                .find(|occ| occ.progress > progress && occ.vehicle != entity);
 
-            // Add remaining distance on this segment before moving to next
-            if first_segment {
-                accumulated_distance += (1.0 - vehicle.progress) * segment_length;
-                first_segment = false;
-            } else {
-                accumulated_distance += segment_length;
+            if let Some(occ) = next {
+                let center_distance = segment_start + occ.progress * seg_data.length;
+                // Convert to bumper-to-bumper distance (front of us to rear of them)
+                let bumper_distance = center_distance - vehicle.length / 2.0 - occ.length / 2.0;
+                return Some((occ, bumper_distance.max(0.0)));
             }
 
-            // Look at next segment
+            segment_start += seg_data.length;
             progress = f32::MIN;
-            max_iteration -= 1;
-            if max_iteration == 0 {
-                return None;
-            }
 
+            // Only look along the planned route; nothing past its end is in our way
             let to_node = road.nodes.get(&seg_data.to);
-            if to_node.outgoing.is_empty() {
-                return None;
-            } else {
-                segment = *to_node.outgoing.first().unwrap();
+            segment = match vehicle.route.get(hop) {
+                Some(next) if to_node.outgoing.contains(next) => *next,
+                _ => return None,
+            };
+            lane = road.lane_for_route(&vehicle.route[hop..]);
+        }
+
+        if self.lookahead_cap_hits.fetch_add(1, Ordering::Relaxed) == 0 {
+            crate::log!(
+                "find_next hit the {}-hop lookahead cap at {:.0}m; leaders further ahead are ignored",
+                self.max_lookahead_hops,
+                segment_start
+            );
+        }
+        None
+    }
+
+    /// Returns the leader (next occupant ahead) and follower (previous occupant behind),
+    /// each with the bumper-to-bumper distance in meters
+    #[allow(clippy::type_complexity)]
+    pub fn neighbors(
+        &self,
+        entity: Entity,
+        vehicle: &Vehicle,
+        road: &Road,
+    ) -> (Option<(&Occupant, f32)>, Option<(&Occupant, f32)>) {
+        (
+            self.find_next(entity, vehicle, road),
+            self.find_previous(entity, vehicle, road),
+        )
+    }
+
+    /// Returns the closest occupant behind and the bumper-to-bumper distance in meters,
+    /// looking back across every incoming segment
+    pub fn find_previous(
+        &self,
+        entity: Entity,
+        vehicle: &Vehicle,
+        road: &Road,
+    ) -> Option<(&Occupant, f32)> {
+        self.find_previous_in_lane(entity, vehicle, vehicle.lane, road)
+    }
+
+    /// [`SegmentOccupancy::find_previous`] as if the vehicle drove in `lane` of its segment.
+    /// Every lane of the incoming segments counts, since their vehicles may end up in it.
+    pub fn find_previous_in_lane(
+        &self,
+        entity: Entity,
+        vehicle: &Vehicle,
+        lane: u8,
+        road: &Road,
+    ) -> Option<(&Occupant, f32)> {
+        let segment_length = road.segments.get(&vehicle.segment).length;
+
+        // Same segment: the closest occupant with lower progress
+        let previous = self
+            .lane(vehicle.segment, lane)
+            .iter()
+            .rev()
+            .find(|occ| occ.progress < vehicle.progress && occ.vehicle != entity);
+
+        if let Some(occ) = previous {
+            let center_distance = (vehicle.progress - occ.progress) * segment_length;
+            let bumper_distance = center_distance - vehicle.length / 2.0 - occ.length / 2.0;
+            return Some((occ, bumper_distance.max(0.0)));
+        }
+
+        // Walk backwards over incoming segments, keeping the nearest follower found
+        let mut frontier = vec![(vehicle.segment, vehicle.progress * segment_length)];
+        let mut best: Option<(&Occupant, f32)> = None;
+
+        for _ in 0..10 {
+            let mut next_frontier = vec![];
+
+            for (segment, distance_behind) in frontier {
+                let from_node = road.nodes.get(&road.segments.get(&segment).from);
+
+                for &incoming in &from_node.incoming {
+                    let incoming_segment = road.segments.get(&incoming);
+                    let length = incoming_segment.length;
+                    let last = (0..incoming_segment.lanes.max(1))
+                        .filter_map(|lane| {
+                            self.lane(incoming, lane)
+                                .iter()
+                                .rev()
+                                .find(|occ| occ.vehicle != entity)
+                        })
+                        .max_by(|a, b| a.progress.total_cmp(&b.progress));
+
+                    match last {
+                        Some(occ) => {
+                            let center_distance = distance_behind + (1.0 - occ.progress) * length;
+                            let bumper_distance =
+                                (center_distance - vehicle.length / 2.0 - occ.length / 2.0)
+                                    .max(0.0);
+                            if best.is_none_or(|(_, d)| bumper_distance < d) {
+                                best = Some((occ, bumper_distance));
+                            }
+                        }
+                        None => next_frontier.push((incoming, distance_behind + length)),
+                    }
+                }
+            }
+
+            // Anything further back than the best follower can't be closer
+            if let Some((_, best_distance)) = best {
+                next_frontier.retain(|(_, d)| *d < best_distance);
+            }
+            if next_frontier.is_empty() {
+                break;
             }
+            frontier = next_frontier;
         }
+
+        best
     }
 }
 
+/// Keeps [`SegmentOccupancy`] in step with the vehicles. Occupants that stay in their lane
+/// are updated in place; only vehicles that changed segment or lane, appeared or left are
+/// moved between lists.
 pub fn update_occupancy(
     mut occupancy: ResMut<SegmentOccupancy>,
-    vehicles: Query<(Entity, &Vehicle)>,
+    vehicles: Query<(Entity, &Vehicle), Without<FreeDrive>>,
 ) {
-    occupancy.vehicles.clear();
+    let occupancy = &mut *occupancy;
 
-    for (entity, vehicle) in &vehicles {
-        let entry = occupancy.vehicles.entry(vehicle.segment).or_default();
-        entry.push(Occupant {
-            progress: vehicle.progress,
-            vehicle: entity,
-            speed: vehicle.speed,
-            segment: vehicle.segment,
-            length: vehicle.length,
+    // Refresh occupants still in their lane and drop the ones that left it
+    occupancy.vehicles.retain(|&key, occupants| {
+        occupants.retain_mut(|occ| match vehicles.get(occ.vehicle) {
+            Ok((_, vehicle)) if (vehicle.segment, vehicle.lane) == key => {
+                occ.progress = vehicle.progress;
+                occ.speed = vehicle.speed;
+                occ.length = vehicle.length;
+                true
+            }
+            _ => false,
         });
+        // Progress only moves a little per tick, so the lane is nearly sorted already
+        sort_by_progress(occupants);
+        !occupants.is_empty()
+    });
+    occupancy
+        .slots
+        .retain(|&entity, _| vehicles.contains(entity));
+
+    for (entity, vehicle) in &vehicles {
+        let key = (vehicle.segment, vehicle.lane);
+        if occupancy.slots.get(&entity) == Some(&key) {
+            continue;
+        }
+        occupancy.insert(
+            key,
+            Occupant {
+                progress: vehicle.progress,
+                vehicle: entity,
+                speed: vehicle.speed,
+                segment: vehicle.segment,
+                lane: vehicle.lane,
+                length: vehicle.length,
+            },
+        );
     }
+}
 
-    // Sort vehicles on each segment by progress
-    for occupants in occupancy.vehicles.values_mut() {
-        occupants.sort_by(|a, b| {
-            a.progress
-                .partial_cmp(&b.progress)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+/// Insertion sort by progress, linear on a list that is already almost in order
+fn sort_by_progress(occupants: &mut [Occupant]) {
+    for i in 1..occupants.len() {
+        let mut j = i;
+        while j > 0 && occupants[j - 1].progress > occupants[j].progress {
+            occupants.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::DEFAULT_CAR_LENGTH;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+
+    #[test]
+    fn test_neighbors_in_three_car_line() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let first = road.add_segment(a, b, 13.9);
+        let second = road.add_segment(b, c, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<SegmentOccupancy>();
+
+        let mut spawn = |segment, progress| {
+            let mut vehicle = Vehicle::new(segment, c, vec![segment]);
+            vehicle.progress = progress;
+            world.spawn(vehicle).id()
+        };
+        let rear = spawn(first, 0.8); // 80m
+        let middle = spawn(second, 0.2); // 120m
+        let front = spawn(second, 0.5); // 150m
+
+        world.run_system_once(update_occupancy).unwrap();
+
+        let occupancy = world.resource::<SegmentOccupancy>();
+        let vehicle = world.get::<Vehicle>(middle).unwrap();
+        let (leader, follower) = occupancy.neighbors(middle, vehicle, &road);
+
+        let (leader, leader_gap) = leader.unwrap();
+        assert_eq!(leader.vehicle, front);
+        assert!((leader_gap - (30.0 - vehicle.length)).abs() < 1e-3);
+
+        let (follower, follower_gap) = follower.unwrap();
+        assert_eq!(follower.vehicle, rear);
+        assert!((follower_gap - (40.0 - vehicle.length)).abs() < 1e-3);
+
+        // Rear car has no follower, front car has no leader
+        let rear_vehicle = world.get::<Vehicle>(rear).unwrap();
+        assert!(occupancy.neighbors(rear, rear_vehicle, &road).1.is_none());
+        let front_vehicle = world.get::<Vehicle>(front).unwrap();
+        assert!(occupancy.neighbors(front, front_vehicle, &road).0.is_none());
+    }
+
+    /// Straight chain 0 -> 100 -> 130 -> 200 m along X; returns the road, its three
+    /// segments and the final node
+    fn chain() -> (Road, [Id<Segment>; 3], Id<crate::Node>) {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(130.0, 0.0, 0.0));
+        let d = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let segments = [
+            road.add_segment(a, b, 13.9),
+            road.add_segment(b, c, 13.9),
+            road.add_segment(c, d, 13.9),
+        ];
+        (road, segments, d)
+    }
+
+    /// Bumper gap from a car 60 m along the chain to a single leader
+    fn gap_to_leader(leader_segment: usize, leader_progress: f32) -> f32 {
+        let (road, segments, d) = chain();
+        let mut world = World::new();
+        world.init_resource::<SegmentOccupancy>();
+
+        let rear = Vehicle::new(segments[0], d, segments.to_vec()).with_progress(0.6);
+        let rear = world.spawn(rear).id();
+        let leader =
+            Vehicle::new(segments[leader_segment], d, vec![]).with_progress(leader_progress);
+        let leader = world.spawn(leader).id();
+        world.run_system_once(update_occupancy).unwrap();
+
+        let occupancy = world.resource::<SegmentOccupancy>();
+        for (index, segment) in segments.into_iter().enumerate() {
+            let occupied = index == 0 || index == leader_segment;
+            assert_eq!(occupancy.is_occupied(segment, &road), occupied);
+        }
+        let vehicle = world.get::<Vehicle>(rear).unwrap();
+        let (occupant, gap) = occupancy.find_next(rear, vehicle, &road).unwrap();
+        assert_eq!(occupant.vehicle, leader);
+        gap
+    }
+
+    #[test]
+    fn test_find_next_distance_on_same_segment() {
+        // 60 m -> 90 m
+        let gap = gap_to_leader(0, 0.9);
+        assert!((gap - (30.0 - DEFAULT_CAR_LENGTH)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_find_next_distance_on_next_segment() {
+        // 60 m -> 100 + 0.5 * 30 = 115 m
+        let gap = gap_to_leader(1, 0.5);
+        assert!((gap - (55.0 - DEFAULT_CAR_LENGTH)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_find_next_distance_two_segments_ahead() {
+        // 60 m -> 130 + 0.25 * 70 = 147.5 m
+        let gap = gap_to_leader(2, 0.25);
+        assert!((gap - (87.5 - DEFAULT_CAR_LENGTH)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_find_next_stops_at_end_of_route() {
+        let (road, segments, d) = chain();
+        let mut world = World::new();
+        world.init_resource::<SegmentOccupancy>();
+
+        // Route ends after the second segment; a car further on is not a leader
+        let rear = Vehicle::new(segments[0], d, segments[..2].to_vec()).with_progress(0.6);
+        let rear = world.spawn(rear).id();
+        world.spawn(Vehicle::new(segments[2], d, vec![]).with_progress(0.1));
+        world.run_system_once(update_occupancy).unwrap();
+
+        let occupancy = world.resource::<SegmentOccupancy>();
+        let vehicle = world.get::<Vehicle>(rear).unwrap();
+        assert!(occupancy.find_next(rear, vehicle, &road).is_none());
+        assert_eq!(occupancy.lookahead_cap_hits.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_find_next_follows_route_at_diverging_node() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let straight_end = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let branch_end = road.add_despawn_node(Vec3::new(100.0, 100.0, 0.0));
+        let approach = road.add_segment(a, b, 13.9);
+        let straight = road.add_segment(b, straight_end, 13.9);
+        let branch = road.add_segment(b, branch_end, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<SegmentOccupancy>();
+        let rear = Vehicle::new(approach, branch_end, vec![approach, branch]).with_progress(0.5);
+        let rear = world.spawn(rear).id();
+        // Closer car on the segment we are not taking
+        world.spawn(Vehicle::new(straight, straight_end, vec![]).with_progress(0.1));
+        let leader = world
+            .spawn(Vehicle::new(branch, branch_end, vec![]).with_progress(0.5))
+            .id();
+        world.run_system_once(update_occupancy).unwrap();
+
+        let occupancy = world.resource::<SegmentOccupancy>();
+        let vehicle = world.get::<Vehicle>(rear).unwrap();
+        let (occupant, _) = occupancy.find_next(rear, vehicle, &road).unwrap();
+        assert_eq!(occupant.vehicle, leader);
+    }
+
+    #[test]
+    fn test_lookahead_cap_on_short_segments_is_configurable_and_counted() {
+        // Fifteen 5 m segments; the leader sits 62.5 m ahead, twelve hops away
+        let mut road = Road::default();
+        let nodes: Vec<_> = (0..=15)
+            .map(|i| road.add_node(Vec3::new(i as f32 * 5.0, 0.0, 0.0)))
+            .collect();
+        let segments: Vec<_> = nodes
+            .windows(2)
+            .map(|pair| road.add_segment(pair[0], pair[1], 13.9))
+            .collect();
+        let end = nodes[15];
+
+        let mut world = World::new();
+        world.init_resource::<SegmentOccupancy>();
+        let rear = world
+            .spawn(Vehicle::new(segments[0], end, segments.clone()).with_progress(0.5))
+            .id();
+        let leader = world
+            .spawn(Vehicle::new(segments[12], end, vec![]).with_progress(0.5))
+            .id();
+        world.run_system_once(update_occupancy).unwrap();
+
+        let vehicle = world.entity_mut(rear).take::<Vehicle>().unwrap();
+        let occupancy = world.resource::<SegmentOccupancy>();
+        assert!(occupancy.find_next(rear, &vehicle, &road).is_none());
+        assert_eq!(occupancy.lookahead_cap_hits.load(Ordering::Relaxed), 1);
+
+        world.resource_mut::<SegmentOccupancy>().max_lookahead_hops = 20;
+        let occupancy = world.resource::<SegmentOccupancy>();
+        let (occupant, gap) = occupancy.find_next(rear, &vehicle, &road).unwrap();
+        assert_eq!(occupant.vehicle, leader);
+        assert!((gap - (60.0 - DEFAULT_CAR_LENGTH)).abs() < 1e-3);
+        assert_eq!(occupancy.lookahead_cap_hits.load(Ordering::Relaxed), 1);
+    }
+
+    /// Every lane's occupants as comparable tuples, from a from-scratch rebuild of the
+    /// vehicles in `world`
+    #[allow(clippy::type_complexity)]
+    fn rebuilt(world: &mut World) -> HashMap<(Id<Segment>, u8), Vec<(Entity, f32, f32)>> {
+        let mut lanes = HashMap::<_, Vec<_>>::new();
+        let mut query = world.query_filtered::<(Entity, &Vehicle), Without<FreeDrive>>();
+        for (entity, vehicle) in query.iter(world) {
+            lanes
+                .entry((vehicle.segment, vehicle.lane))
+                .or_default()
+                .push((entity, vehicle.progress, vehicle.speed));
+        }
+        for occupants in lanes.values_mut() {
+            occupants.sort_by(|a, b| a.1.total_cmp(&b.1));
+        }
+        lanes
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn tracked(
+        occupancy: &SegmentOccupancy,
+    ) -> HashMap<(Id<Segment>, u8), Vec<(Entity, f32, f32)>> {
+        occupancy
+            .vehicles
+            .iter()
+            .map(|(&key, occupants)| {
+                for occ in occupants {
+                    assert_eq!((occ.segment, occ.lane), key);
+                }
+                let occupants = occupants
+                    .iter()
+                    .map(|occ| (occ.vehicle, occ.progress, occ.speed))
+                    .collect();
+                (key, occupants)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_incremental_update_matches_full_rebuild() {
+        let (mut road, segments, d) = chain();
+        road.segments.get_mut(&segments[0]).lanes = 2;
+        let mut world = World::new();
+        world.init_resource::<SegmentOccupancy>();
+
+        // Speeds differ, so vehicles overtake each other and lanes need reordering
+        let entities: Vec<_> = (0..12)
+            .map(|i| {
+                let vehicle = Vehicle::new(segments[0], d, segments.to_vec())
+                    .with_progress(0.03 + i as f32 * 0.07)
+                    .with_speed(1.0 + (i * 7 % 5) as f32);
+                world.spawn(vehicle).id()
+            })
+            .collect();
+
+        for tick in 0..40 {
+            for (i, &entity) in entities.iter().enumerate() {
+                let Ok(mut entity) = world.get_entity_mut(entity) else {
+                    continue;
+                };
+                let Some(mut vehicle) = entity.get_mut::<Vehicle>() else {
+                    continue;
+                };
+                vehicle.progress += vehicle.speed * 0.01 + i as f32 * 1e-4;
+                if vehicle.progress >= 1.0 {
+                    let hop = segments.iter().position(|&s| s == vehicle.segment).unwrap() + 1;
+                    if hop == segments.len() {
+                        vehicle.progress = 0.999;
+                        continue;
+                    }
+                    vehicle.progress -= 1.0;
+                    vehicle.segment = segments[hop];
+                    vehicle.lane = 0;
+                } else if vehicle.segment == segments[0] && (tick + i) % 9 == 0 {
+                    vehicle.lane = 1 - vehicle.lane;
+                }
+            }
+            match tick {
+                10 => {
+                    world.despawn(entities[3]);
+                }
+                15 => {
+                    world.entity_mut(entities[5]).insert(FreeDrive {
+                        position: Vec3::ZERO,
+                        heading: Vec3::X,
+                    });
+                }
+                20 => {
+                    world.entity_mut(entities[5]).remove::<FreeDrive>();
+                    world.spawn(Vehicle::new(segments[0], d, segments.to_vec()).with_progress(0.5));
+                }
+                _ => {}
+            }
+
+            world.run_system_once(update_occupancy).unwrap();
+            let expected = rebuilt(&mut world);
+            assert_eq!(
+                tracked(world.resource::<SegmentOccupancy>()),
+                expected,
+                "tick {tick}"
+            );
+        }
     }
 }
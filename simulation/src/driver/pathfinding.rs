@@ -1,13 +1,261 @@
-use crate::{Id, Node, Road, Segment};
-use std::collections::{HashMap, VecDeque};
+use crate::{
+    driver::{TurnType, VehicleClass},
+    Id, Node, Road, Segment,
+};
+use bevy_ecs::prelude::*;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+/// Upper bound on nodes explored when picking routes for new vehicles
+pub const SPAWN_ROUTE_BUDGET: usize = 10_000;
+
+/// Restrictions applied while searching for a route
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RouteOptions {
+    /// Only use segments that allow this vehicle class
+    pub class: Option<VehicleClass>,
+    /// Give up after exploring this many nodes
+    pub budget: Option<usize>,
+    /// Find the cheapest route under these weights instead of the one with fewest segments
+    pub weights: Option<CostWeights>,
+}
+
+/// Trade-off between route criteria; a segment costs the weighted sum of its length (m),
+/// free-flow travel time (s), whether it is a turn, its toll, and a flat per-segment cost
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostWeights {
+    pub distance: f32,
+    pub time: f32,
+    pub turn: f32,
+    pub toll: f32,
+    pub hop: f32,
+}
+
+impl CostWeights {
+    pub const SHORTEST: Self = Self {
+        distance: 1.0,
+        time: 0.0,
+        turn: 0.0,
+        toll: 0.0,
+        hop: 0.0,
+    };
+
+    pub const FASTEST: Self = Self {
+        distance: 0.0,
+        time: 1.0,
+        turn: 0.0,
+        toll: 0.0,
+        hop: 0.0,
+    };
+
+    /// Each turn counts as a 100 m detour
+    pub const FEWEST_TURNS: Self = Self {
+        distance: 1.0,
+        time: 0.0,
+        turn: 100.0,
+        toll: 0.0,
+        hop: 0.0,
+    };
+
+    /// Fastest route, with each unit of toll worth 60 s of driving
+    pub const AVOID_TOLLS: Self = Self {
+        distance: 0.0,
+        time: 1.0,
+        turn: 0.0,
+        toll: 60.0,
+        hop: 0.0,
+    };
+
+    pub fn segment_cost(&self, segment: &Segment) -> f32 {
+        let is_turn = matches!(segment.turn_type, TurnType::Left(_) | TurnType::Right(_));
+
+        self.distance * segment.length
+            + self.time * segment.length / segment.speed_limit.max(0.1)
+            + if is_turn { self.turn } else { 0.0 }
+            + self.toll * segment.toll
+            + self.hop
+    }
+}
+
+/// How vehicles pick between alternative routes
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RoutingMode {
+    /// Fewest segments
+    #[default]
+    Hops,
+    /// Shortest free-flow travel time
+    Time,
+}
+
+impl RoutingMode {
+    pub fn route_options(self, class: VehicleClass) -> RouteOptions {
+        self.route_options_with(class, RoutingConfig::default())
+    }
+
+    /// [`RoutingMode::route_options`] with the turn penalty from `config`
+    pub fn route_options_with(self, class: VehicleClass, config: RoutingConfig) -> RouteOptions {
+        let turn = config.turn_penalty.max(0.0);
+        RouteOptions {
+            class: Some(class),
+            weights: match self {
+                RoutingMode::Hops if turn == 0.0 => None,
+                RoutingMode::Hops => Some(CostWeights {
+                    distance: 0.0,
+                    time: 0.0,
+                    turn,
+                    toll: 0.0,
+                    hop: 1.0,
+                }),
+                RoutingMode::Time => Some(CostWeights {
+                    turn,
+                    ..CostWeights::FASTEST
+                }),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Tuning shared by every route search vehicles make
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoutingConfig {
+    /// Extra cost of each left or right turn, so drivers prefer straighter routes. Counted in
+    /// seconds under [`RoutingMode::Time`] and in segments under [`RoutingMode::Hops`].
+    pub turn_penalty: f32,
+}
+
+impl RoutingConfig {
+    /// Penalty `segment` adds to a route
+    pub fn turn_cost(&self, segment: &Segment) -> f32 {
+        match segment.turn_type {
+            TurnType::Left(_) | TurnType::Right(_) => self.turn_penalty.max(0.0),
+            _ => 0.0,
+        }
+    }
+}
 
 pub fn next_segment_toward(
     road: &Road,
     current: Id<Node>,
     destination: Id<Node>,
 ) -> Option<(Id<Segment>, Vec<Id<Segment>>)> {
+    search(road, current, destination, RouteOptions::default()).0
+}
+
+/// Like [`next_segment_toward`], with class restrictions and/or a node budget
+pub fn next_segment_toward_with(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    options: RouteOptions,
+) -> Option<(Id<Segment>, Vec<Id<Segment>>)> {
+    search(road, current, destination, options).0
+}
+
+/// Like [`next_segment_toward`], but gives up and returns `None` once more than `budget`
+/// nodes have been explored, bounding the cost of unreachable destinations on large networks
+pub fn next_segment_toward_within(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    budget: usize,
+) -> Option<(Id<Segment>, Vec<Id<Segment>>)> {
+    let options = RouteOptions {
+        budget: Some(budget),
+        ..Default::default()
+    };
+    search(road, current, destination, options).0
+}
+
+/// Route with the shortest free-flow travel time (`length / speed_limit` summed), so slow
+/// turn segments and side streets are avoided when a faster through-road exists
+pub fn shortest_route_by_time(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+) -> Option<(Id<Segment>, Vec<Id<Segment>>)> {
+    let options = RouteOptions {
+        weights: Some(CostWeights::FASTEST),
+        ..Default::default()
+    };
+    search(road, current, destination, options).0
+}
+
+/// Fastest route from `current` to `destination` when each segment also costs `delay(segment)`
+/// seconds on top of its free-flow time, e.g. time lost in a jam. Delays must not be negative.
+pub fn fastest_route_with_delays(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    options: RouteOptions,
+    delay: impl Fn(Id<Segment>) -> f32,
+) -> Option<(Id<Segment>, Vec<Id<Segment>>)> {
+    search_weighted_with(
+        road,
+        current,
+        destination,
+        options,
+        CostWeights::FASTEST,
+        delay,
+    )
+    .0
+}
+
+/// First segment to take and the full route from the start node
+type Found = (Id<Segment>, Vec<Id<Segment>>);
+
+/// Route search returning the route (if any) and the number of nodes explored
+fn search(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    options: RouteOptions,
+) -> (Option<Found>, usize) {
+    match options.weights {
+        Some(weights) => search_weighted(road, current, destination, options, weights),
+        None => search_fewest_segments(road, current, destination, options),
+    }
+}
+
+fn is_allowed(road: &Road, options: &RouteOptions, segment: &Id<Segment>) -> bool {
+    options
+        .class
+        .is_none_or(|class| road.segments.get(segment).allowed_classes.contains(class))
+}
+
+/// Follow `came_from` back from the destination to the start node
+fn backtrack(
+    road: &Road,
+    came_from: &HashMap<Id<Node>, Id<Segment>>,
+    current: Id<Node>,
+    destination: Id<Node>,
+) -> Found {
+    let mut route = vec![];
+    let mut node = destination;
+    loop {
+        let previous_id = came_from[&node];
+        route.push(previous_id);
+
+        let previous = road.segments.get(&previous_id);
+        if previous.from == current {
+            route.reverse();
+            return (previous_id, route);
+        }
+        node = previous.from;
+    }
+}
+
+/// Breadth-first search for the route with the fewest segments
+fn search_fewest_segments(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    options: RouteOptions,
+) -> (Option<Found>, usize) {
     if current == destination {
-        return None; // arrived
+        return (None, 0); // arrived
     }
 
     // Debug: log BFS start
@@ -16,35 +264,32 @@ pub fn next_segment_toward(
     let mut queue = VecDeque::<Id<Node>>::new();
     let mut came_from = HashMap::<Id<Node>, Id<Segment>>::new();
 
+    let allowed = |segment_id: &&Id<Segment>| is_allowed(road, &options, segment_id);
+
     let current_node = road.nodes.get(&current);
-    for segment_id in &current_node.outgoing {
+    for segment_id in current_node.outgoing.iter().filter(allowed) {
         let neighbor = road.segments.get(segment_id).to;
         queue.push_back(neighbor);
         came_from.insert(neighbor, *segment_id);
     }
 
     // bfs
-    let mut route = vec![];
+    let mut explored = 0;
     while let Some(node_id) = queue.pop_front() {
-        if node_id == destination {
-            let mut backtrack = destination;
-            loop {
-                let previous_id = came_from.get(&backtrack).unwrap();
-                route.push(*previous_id);
-
-                let previous = road.segments.get(previous_id);
-
-                if previous.from == current {
-                    route.reverse();
-                    return Some((*previous_id, route));
-                }
+        if options.budget.is_some_and(|budget| explored >= budget) {
+            return (None, explored);
+        }
+        explored += 1;
 
-                backtrack = previous.from;
-            }
+        if node_id == destination {
+            return (
+                Some(backtrack(road, &came_from, current, destination)),
+                explored,
+            );
         }
 
         let node = road.nodes.get(&node_id);
-        for segment_id in &node.outgoing {
+        for segment_id in node.outgoing.iter().filter(allowed) {
             let neighbor = road.segments.get(segment_id).to;
             came_from.entry(neighbor).or_insert_with(|| {
                 queue.push_back(neighbor);
@@ -53,5 +298,388 @@ pub fn next_segment_toward(
         }
     }
 
-    None
+    (None, explored)
+}
+
+/// Open-set entry ordered so the heap pops the lowest estimated total cost first
+struct Candidate {
+    estimate: f32,
+    cost: f32,
+    node: Id<Node>,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// A* search for the cheapest route under `weights`
+fn search_weighted(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    options: RouteOptions,
+    weights: CostWeights,
+) -> (Option<Found>, usize) {
+    search_weighted_with(road, current, destination, options, weights, |_| 0.0)
+}
+
+/// [`search_weighted`] with a non-negative `extra` cost added to every segment, which keeps
+/// the straight-line heuristic admissible
+fn search_weighted_with(
+    road: &Road,
+    current: Id<Node>,
+    destination: Id<Node>,
+    options: RouteOptions,
+    weights: CostWeights,
+    extra: impl Fn(Id<Segment>) -> f32,
+) -> (Option<Found>, usize) {
+    if current == destination {
+        return (None, 0);
+    }
+
+    // Straight-line distance at the top speed never overestimates the remaining cost
+    let top_speed = road
+        .segments
+        .iter()
+        .map(|s| s.speed_limit)
+        .fold(0.1, f32::max);
+    let target = road.nodes.get(&destination).position;
+    let heuristic = |node: Id<Node>| {
+        let distance = road.nodes.get(&node).position.distance(target);
+        weights.distance * distance + weights.time * distance / top_speed
+    };
+
+    let mut open = BinaryHeap::new();
+    let mut best = HashMap::<Id<Node>, f32>::new();
+    let mut came_from = HashMap::<Id<Node>, Id<Segment>>::new();
+    best.insert(current, 0.0);
+    open.push(Candidate {
+        estimate: heuristic(current),
+        cost: 0.0,
+        node: current,
+    });
+
+    let mut explored = 0;
+    while let Some(Candidate { cost, node, .. }) = open.pop() {
+        if best.get(&node).is_some_and(|&known| cost > known) {
+            continue; // stale entry
+        }
+        if options.budget.is_some_and(|budget| explored >= budget) {
+            return (None, explored);
+        }
+        explored += 1;
+
+        if node == destination {
+            return (
+                Some(backtrack(road, &came_from, current, destination)),
+                explored,
+            );
+        }
+
+        for segment_id in &road.nodes.get(&node).outgoing {
+            if !is_allowed(road, &options, segment_id) {
+                continue;
+            }
+
+            let segment = road.segments.get(segment_id);
+            let cost = cost + weights.segment_cost(segment) + extra(*segment_id);
+            if best.get(&segment.to).is_none_or(|&known| cost < known) {
+                best.insert(segment.to, cost);
+                came_from.insert(segment.to, *segment_id);
+                open.push(Candidate {
+                    estimate: cost + heuristic(segment.to),
+                    cost,
+                    node: segment.to,
+                });
+            }
+        }
+    }
+
+    (None, explored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{VehicleClasses, YieldResolver};
+    use glam::Vec3;
+
+    #[test]
+    fn test_unreachable_destination_respects_budget() {
+        // 50x50 one-way grid flowing east and north; the origin corner can't be reached
+        const SIZE: usize = 50;
+        let mut road = Road::default();
+        let ids: Vec<_> = (0..SIZE * SIZE)
+            .map(|i| road.add_node(Vec3::new((i % SIZE) as f32, (i / SIZE) as f32, 0.0) * 10.0))
+            .collect();
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if x + 1 < SIZE {
+                    road.add_segment(ids[y * SIZE + x], ids[y * SIZE + x + 1], 13.9);
+                }
+                if y + 1 < SIZE {
+                    road.add_segment(ids[y * SIZE + x], ids[(y + 1) * SIZE + x], 13.9);
+                }
+            }
+        }
+
+        let start = ids[SIZE + 1];
+        let unreachable = ids[0];
+        let budget = 100;
+
+        let (route, explored) = search(
+            &road,
+            start,
+            unreachable,
+            RouteOptions {
+                budget: Some(budget),
+                ..Default::default()
+            },
+        );
+        assert!(route.is_none());
+        assert!(explored <= budget);
+
+        // Without a budget the whole reachable grid is explored
+        let (route, explored) = search(&road, start, unreachable, RouteOptions::default());
+        assert!(route.is_none());
+        assert!(explored > budget);
+
+        // Reachable destinations within the budget are still found
+        assert!(next_segment_toward_within(&road, start, ids[3 * SIZE + 3], budget).is_some());
+    }
+
+    #[test]
+    fn test_route_is_ordered_from_first_segment_to_destination() {
+        // 3x3 two-way grid with 100 m blocks
+        let mut road = Road::default();
+        let ids: Vec<_> = (0..9)
+            .map(|i| road.add_node(Vec3::new((i % 3) as f32, (i / 3) as f32, 0.0) * 100.0))
+            .collect();
+        for y in 0..3 {
+            for x in 0..3 {
+                let here = ids[y * 3 + x];
+                let mut connect = |there| {
+                    road.add_segment(here, there, 13.9);
+                    road.add_segment(there, here, 13.9);
+                };
+                if x + 1 < 3 {
+                    connect(ids[y * 3 + x + 1]);
+                }
+                if y + 1 < 3 {
+                    connect(ids[(y + 1) * 3 + x]);
+                }
+            }
+        }
+
+        let (start, destination) = (ids[0], ids[7]);
+        let (first, route) = next_segment_toward(&road, start, destination).unwrap();
+
+        assert_eq!(route.len(), 3);
+        assert_eq!(route[0], first);
+        assert_eq!(road.segments.get(&first).from, start);
+        assert_eq!(road.segments.get(&route[2]).to, destination);
+        for pair in route.windows(2) {
+            assert_eq!(
+                road.segments.get(&pair[0]).to,
+                road.segments.get(&pair[1]).from
+            );
+        }
+    }
+
+    #[test]
+    fn test_time_mode_prefers_fast_road_over_fewer_segments() {
+        // Two-segment side street at 5 m/s versus a four-segment arterial at 20 m/s
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let side = road.add_node(Vec3::new(100.0, 50.0, 0.0));
+        let b = road.add_node(Vec3::new(200.0, 0.0, 0.0));
+        let arterial: Vec<_> = [50.0, 100.0, 150.0]
+            .into_iter()
+            .map(|x| road.add_node(Vec3::new(x, -20.0, 0.0)))
+            .collect();
+        road.add_segment(a, side, 5.0);
+        road.add_segment(side, b, 5.0);
+        road.add_segment(a, arterial[0], 20.0);
+        road.add_segment(arterial[0], arterial[1], 20.0);
+        road.add_segment(arterial[1], arterial[2], 20.0);
+        road.add_segment(arterial[2], b, 20.0);
+
+        let (_, hops) = next_segment_toward(&road, a, b).unwrap();
+        assert_eq!(hops.len(), 2);
+
+        let (_, time) = shortest_route_by_time(&road, a, b).unwrap();
+        assert_eq!(time.len(), 4);
+        assert!(road.free_flow_time(&time) < road.free_flow_time(&hops));
+
+        // Same result through the mode a vehicle routes with
+        let options = RoutingMode::Time.route_options(VehicleClass::default());
+        assert_eq!(
+            next_segment_toward_with(&road, a, b, options).unwrap().1,
+            time
+        );
+
+        let unreachable = road.add_node(Vec3::new(0.0, 200.0, 0.0));
+        assert!(shortest_route_by_time(&road, a, unreachable).is_none());
+    }
+
+    #[test]
+    fn test_truck_routes_around_car_only_segment() {
+        // Short residential shortcut a -> b -> d, longer arterial a -> c -> e -> d
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(50.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(0.0, 50.0, 0.0));
+        let e = road.add_node(Vec3::new(100.0, 50.0, 0.0));
+        let d = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let ab = road.add_segment(a, b, 8.3);
+        road.add_segment(b, d, 8.3);
+        let ac = road.add_segment(a, c, 13.9);
+        road.add_segment(c, e, 13.9);
+        road.add_segment(e, d, 13.9);
+        road.segments.get_mut(&ab).allowed_classes = VehicleClasses::only(VehicleClass::Car);
+
+        let route_for = |class| {
+            let options = RouteOptions {
+                class: Some(class),
+                ..Default::default()
+            };
+            next_segment_toward_with(&road, a, d, options).unwrap()
+        };
+
+        let (first, route) = route_for(VehicleClass::Car);
+        assert_eq!(first, ab);
+        assert_eq!(route.len(), 2);
+
+        let (first, route) = route_for(VehicleClass::Truck);
+        assert_eq!(first, ac);
+        assert_eq!(route.len(), 3);
+        assert!(!route.contains(&ab));
+    }
+
+    #[test]
+    fn test_weights_trade_distance_against_turns() {
+        // Short route a -> b -> d ending in a turn (110 m), straight route a -> c -> d (150 m)
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(50.0, 5.0, 0.0));
+        let d = road.add_node(Vec3::new(100.0, 10.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bd = road.add_segment(b, d, 13.9);
+        let ac = road.add_segment(a, c, 13.9);
+        road.segments.get_mut(&bd).turn_type = TurnType::Left(1.0);
+        let cd = road.add_segment(c, d, 13.9);
+        // Stretch the straight route to exactly 150 m
+        road.segments.get_mut(&ac).length = 75.0;
+        road.segments.get_mut(&cd).length = 75.0;
+
+        let route_with = |turn: f32| {
+            let options = RouteOptions {
+                weights: Some(CostWeights {
+                    turn,
+                    ..CostWeights::SHORTEST
+                }),
+                ..Default::default()
+            };
+            next_segment_toward_with(&road, a, d, options).unwrap().1
+        };
+
+        assert_eq!(route_with(0.0), vec![ab, bd]);
+        // A turn worth less than the 40 m detour is still taken...
+        assert_eq!(route_with(30.0), vec![ab, bd]);
+        // ...one worth more is avoided
+        assert_eq!(route_with(50.0), vec![ac, cd]);
+        assert_eq!(
+            next_segment_toward_with(
+                &road,
+                a,
+                d,
+                RouteOptions {
+                    weights: Some(CostWeights::FEWEST_TURNS),
+                    ..Default::default()
+                }
+            )
+            .unwrap()
+            .1,
+            vec![ac, cd]
+        );
+    }
+
+    #[test]
+    fn test_avoid_tolls_prefers_free_route() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(50.0, 50.0, 0.0));
+        let toll_road = road.add_segment(a, b, 13.9);
+        road.segments.get_mut(&toll_road).toll = 2.0;
+        road.add_segment(a, c, 13.9);
+        road.add_segment(c, b, 13.9);
+
+        let route_with = |weights| {
+            let options = RouteOptions {
+                weights: Some(weights),
+                ..Default::default()
+            };
+            next_segment_toward_with(&road, a, b, options).unwrap().1
+        };
+
+        assert_eq!(route_with(CostWeights::FASTEST), vec![toll_road]);
+        assert_eq!(route_with(CostWeights::AVOID_TOLLS).len(), 2);
+    }
+
+    #[test]
+    fn test_turn_penalty_prefers_straighter_grid_routes() {
+        let road = Road::generate_grid(3, 3, 100.0, 13.9, YieldResolver::default());
+        let turns = |route: &[Id<Segment>]| {
+            route
+                .iter()
+                .filter(|segment| {
+                    matches!(
+                        road.segments.get(segment).turn_type,
+                        TurnType::Left(_) | TurnType::Right(_)
+                    )
+                })
+                .count()
+        };
+        let route_with = |origin, destination, turn_penalty| {
+            let options = RoutingMode::Hops
+                .route_options_with(VehicleClass::Car, RoutingConfig { turn_penalty });
+            next_segment_toward_with(&road, origin, destination, options).map(|(_, route)| route)
+        };
+
+        let mut straightened = 0;
+        for (origin, _) in road.nodes.iter_with_ids().filter(|(_, n)| n.is_spawn) {
+            for (destination, _) in road.nodes.iter_with_ids().filter(|(_, n)| n.is_despawn) {
+                let Some(plain) = route_with(origin, destination, 0.0) else {
+                    continue;
+                };
+                let penalized = route_with(origin, destination, 30.0).unwrap();
+
+                // Still a fewest-segments route, but never one with more turns
+                assert_eq!(penalized.len(), plain.len());
+                assert!(turns(&penalized) <= turns(&plain));
+                if turns(&penalized) < turns(&plain) {
+                    straightened += 1;
+                }
+            }
+        }
+        assert!(straightened > 0, "no route changed under the turn penalty");
+    }
 }
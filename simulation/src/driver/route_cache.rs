@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use bevy_ecs::prelude::*;
+
+use crate::{
+    driver::{
+        next_segment_toward_with, RouteOptions, RoutingConfig, RoutingMode, VehicleClass,
+        SPAWN_ROUTE_BUDGET,
+    },
+    Id, Node, Road, Segment,
+};
+
+/// First segment to take and the full route from the origin node
+type CachedRoute = Option<(Id<Segment>, Vec<Id<Segment>>)>;
+
+/// Origin, destination, class, mode and the bits of the turn penalty
+type RouteKey = (Id<Node>, Id<Node>, VehicleClass, RoutingMode, u32);
+
+/// Routes between node pairs, reused until [`Road::version`] changes. Unreachable pairs are
+/// remembered too, so a stranded spawn point doesn't search the network on every attempt.
+#[derive(Resource, Default)]
+pub struct RouteCache {
+    routes: HashMap<RouteKey, CachedRoute>,
+    road_version: u64,
+    /// Route searches run because the cache had no answer yet
+    pub searches: u64,
+}
+
+impl RouteCache {
+    /// Route from `origin` to `destination` for a vehicle of `class`, searching only on a
+    /// miss. Searches give up after [`SPAWN_ROUTE_BUDGET`] nodes.
+    pub fn route(
+        &mut self,
+        road: &Road,
+        origin: Id<Node>,
+        destination: Id<Node>,
+        class: VehicleClass,
+        routing: RoutingMode,
+        config: RoutingConfig,
+    ) -> CachedRoute {
+        if road.version() != self.road_version {
+            self.clear();
+            self.road_version = road.version();
+        }
+
+        let key = (
+            origin,
+            destination,
+            class,
+            routing,
+            config.turn_penalty.to_bits(),
+        );
+        if let Some(route) = self.routes.get(&key) {
+            return route.clone();
+        }
+
+        self.searches += 1;
+        let route = Self::search(road, origin, destination, class, routing, config);
+        self.routes.insert(key, route.clone());
+        route
+    }
+
+    /// [`RouteCache::route`] without a cache
+    pub fn search(
+        road: &Road,
+        origin: Id<Node>,
+        destination: Id<Node>,
+        class: VehicleClass,
+        routing: RoutingMode,
+        config: RoutingConfig,
+    ) -> CachedRoute {
+        let options = RouteOptions {
+            budget: Some(SPAWN_ROUTE_BUDGET),
+            ..routing.route_options_with(class, config)
+        };
+        next_segment_toward_with(road, origin, destination, options)
+    }
+
+    /// Forget every route
+    pub fn clear(&mut self) {
+        self.routes.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+/// Look up a route through `cache` if there is one, otherwise search directly
+pub(crate) fn cached_route(
+    cache: Option<&mut RouteCache>,
+    road: &Road,
+    origin: Id<Node>,
+    destination: Id<Node>,
+    class: VehicleClass,
+    routing: RoutingMode,
+    config: RoutingConfig,
+) -> CachedRoute {
+    match cache {
+        Some(cache) => cache.route(road, origin, destination, class, routing, config),
+        None => RouteCache::search(road, origin, destination, class, routing, config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{spawn_vehicles, SegmentOccupancy, Vehicle, VehicleMix};
+    use crate::{SimRng, SpawnSpacing};
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_time::Time;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    #[test]
+    fn test_spawns_reuse_cached_routes_until_road_changes() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        road.add_segment(a, b, 13.9);
+        road.add_segment(b, c, 13.9);
+
+        let mut world = World::new();
+        world.insert_resource(road);
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SpawnSpacing>();
+        world.init_resource::<RouteCache>();
+        world.insert_resource(SimRng::seeded(3));
+        world.init_resource::<Time>();
+
+        // One class, so every spawn looks up the same route
+        world.insert_resource(VehicleMix::only(VehicleClass::Car));
+
+        let spawn_until = |world: &mut World, count: usize| {
+            for _ in 0..10_000 {
+                if world.query::<&Vehicle>().iter(world).count() >= count {
+                    return;
+                }
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_secs(10));
+                world.run_system_once(spawn_vehicles).unwrap();
+            }
+            panic!("never reached {count} vehicles");
+        };
+
+        spawn_until(&mut world, 1);
+        assert_eq!(world.resource::<RouteCache>().len(), 1);
+        assert_eq!(world.resource::<RouteCache>().searches, 1);
+
+        spawn_until(&mut world, 4);
+        assert_eq!(world.resource::<RouteCache>().searches, 1);
+
+        // Any change to the road invalidates what was cached
+        world.resource_mut::<Road>().mark_changed();
+        spawn_until(&mut world, 5);
+        assert_eq!(world.resource::<RouteCache>().searches, 2);
+
+        // So does a new destination, which both routes are searched again for
+        let mut road = world.resource_mut::<Road>();
+        let version = road.version();
+        let d = road.add_despawn_node(Vec3::new(100.0, 100.0, 0.0));
+        assert_ne!(road.version(), version);
+        road.add_segment(b, d, 13.9);
+        spawn_until(&mut world, 6);
+        assert_eq!(world.resource::<RouteCache>().len(), 2);
+        assert_eq!(world.resource::<RouteCache>().searches, 4);
+    }
+}
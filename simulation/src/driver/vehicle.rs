@@ -1,16 +1,23 @@
 use crate::{
-    driver::{next_segment_toward, Blinker, GapAcceptance, Idm, SegmentOccupancy},
-    Id, Node, Road, Segment,
+    driver::{
+        cached_route, next_segment_toward_with, Blinker, DespawnReason, FreeDrive, GapAcceptance,
+        Idm, IntersectionCleared, RouteCache, RouteOptions, RoutingConfig, RoutingMode,
+        SegmentOccupancy, VehicleArrived, VehicleClass, VehicleDespawned, VehicleMix,
+        VehicleSpawned,
+    },
+    sim_dt, sim_time, DemandProfile, Id, Node, OdMatrix, Road, Segment, SimRng, SimulationStats,
+    SpawnConfig, SpawnSpacing,
 };
 use bevy_ecs::prelude::*;
 use bevy_time::Time;
-use rand::seq::{IndexedRandom, IteratorRandom};
+use glam::Vec3;
 
 /// Typical car dimensions in meters
 pub const DEFAULT_CAR_LENGTH: f32 = 4.5;
 pub const DEFAULT_CAR_WIDTH: f32 = 1.8;
 
 #[derive(Component)]
+#[require(VehicleTelemetry)]
 pub struct Vehicle {
     pub speed: f32,
     pub segment: Id<Segment>,
@@ -25,26 +32,160 @@ pub struct Vehicle {
     pub width: f32,
     /// Turn signal state
     pub blinker: Blinker,
+    /// Hazard lights switched on by hand, see [`Vehicle::set_hazards`]
+    pub hazards: bool,
+    /// Seconds stood still since last moving
+    pub stopped_time: f32,
     /// Brake lights on
     pub braking: bool,
+    /// Acceleration (m/s²) applied on the last step, see [`Idm::limit_jerk`]
+    pub acceleration: f32,
+    /// Seconds since spawning
+    pub travel_time: f32,
+    /// Expected travel time for the planned route with no other traffic
+    pub free_flow_time: f32,
+    /// Seconds the leader has been moving while this vehicle still stands in its queue
+    pub queue_release_timer: f32,
+    /// Determines the vehicle's size and dynamics and which segments it may route over
+    pub class: VehicleClass,
+    /// Lane on the current segment, 0 being the rightmost
+    pub lane: u8,
 }
 
 impl Vehicle {
+    /// Car with a typical, middle-of-the-road driver
     pub fn new(segment: Id<Segment>, destination: Id<Node>, route: Vec<Id<Segment>>) -> Self {
-        let aggression = rand::random();
+        Self::new_with_class(segment, destination, route, VehicleClass::Car)
+    }
+
+    /// Vehicle of `class` with a typical driver, sized from the class profile
+    pub fn new_with_class(
+        segment: Id<Segment>,
+        destination: Id<Node>,
+        route: Vec<Id<Segment>>,
+        class: VehicleClass,
+    ) -> Self {
+        let idm = Idm::typical(0.5, class);
+        let gap = GapAcceptance::typical(0.5);
+        Self::with_driver(segment, destination, route, class, idm, gap)
+    }
 
+    /// Vehicle of `class` with a driver of random aggression drawn from `rng`
+    pub fn random(
+        segment: Id<Segment>,
+        destination: Id<Node>,
+        route: Vec<Id<Segment>>,
+        class: VehicleClass,
+        rng: &mut SimRng,
+    ) -> Self {
+        let aggression = rng.uniform();
+        let idm = Idm::new(aggression, class, rng);
+        let gap = GapAcceptance::new(aggression, rng);
+        Self::with_driver(segment, destination, route, class, idm, gap)
+    }
+
+    fn with_driver(
+        segment: Id<Segment>,
+        destination: Id<Node>,
+        route: Vec<Id<Segment>>,
+        class: VehicleClass,
+        idm: Idm,
+        gap: GapAcceptance,
+    ) -> Self {
+        let profile = class.profile();
         Self {
             speed: 0.0,
             segment,
             progress: 0.0,
             destination,
             route,
-            idm: Idm::new(aggression),
-            gap: GapAcceptance::new(aggression),
-            length: DEFAULT_CAR_LENGTH,
-            width: DEFAULT_CAR_WIDTH,
+            idm,
+            gap,
+            length: profile.length,
+            width: profile.width,
             blinker: Blinker::None,
+            hazards: false,
+            stopped_time: 0.0,
             braking: false,
+            acceleration: 0.0,
+            travel_time: 0.0,
+            free_flow_time: 0.0,
+            queue_release_timer: 0.0,
+            class,
+            lane: 0,
+        }
+    }
+
+    /// Start partway along the first segment, e.g. for traffic entering from off-map
+    pub fn with_progress(mut self, progress: f32) -> Self {
+        self.progress = progress.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Start already moving at `speed` (m/s)
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    /// Switch the hazard lights on or off by hand, e.g. for a broken-down vehicle. They stay
+    /// on until switched off and take the place of the turn signals.
+    pub fn set_hazards(&mut self, on: bool) {
+        self.hazards = on;
+        self.blinker = if on { Blinker::Hazard } else { Blinker::None };
+    }
+
+    /// Meters left to the destination: the rest of the current segment plus every route
+    /// segment after it. A route that doesn't contain the current segment counts as stale
+    /// and only the current segment's remainder is returned.
+    pub fn remaining_distance(&self, road: &Road) -> f32 {
+        let current = road.segments.get(&self.segment).length * (1.0 - self.progress).max(0.0);
+        let ahead = match self.route.iter().position(|&id| id == self.segment) {
+            Some(index) => &self.route[index + 1..],
+            None => &[],
+        };
+
+        current
+            + ahead
+                .iter()
+                .map(|id| road.segments.get(id).length)
+                .sum::<f32>()
+    }
+}
+
+/// World positions of the given vehicles, in iteration order
+pub fn vehicle_world_positions<'a>(
+    road: &Road,
+    vehicles: impl IntoIterator<Item = &'a Vehicle>,
+) -> Vec<Vec3> {
+    vehicles
+        .into_iter()
+        .map(|vehicle| road.lane_position_on(vehicle.segment, vehicle.progress, vehicle.lane))
+        .collect()
+}
+
+/// What the driver model responded to on the last step, kept for overlays and logging so
+/// nothing has to re-derive it. Added with every [`Vehicle`] and updated by
+/// [`apply_idm`](crate::driver::apply_idm).
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct VehicleTelemetry {
+    /// Acceleration (m/s²) applied
+    pub acceleration: f32,
+    /// Distance (m) to the leader or stop line held for; `f32::MAX` on a free road
+    pub gap: f32,
+    /// Own speed minus that of whatever is ahead (m/s); positive while closing in
+    pub delta_speed: f32,
+    /// Vehicle followed, `None` on a free road or when holding at the stop line
+    pub leader: Option<Entity>,
+}
+
+impl Default for VehicleTelemetry {
+    fn default() -> Self {
+        Self {
+            acceleration: 0.0,
+            gap: f32::MAX,
+            delta_speed: 0.0,
+            leader: None,
         }
     }
 }
@@ -53,17 +194,52 @@ impl Vehicle {
 #[derive(Component)]
 pub struct PlayerControlled;
 
+/// Marker for vehicles with blue lights, e.g. an ambulance: they never yield at
+/// intersections and everyone else gives way to them
+#[derive(Component)]
+pub struct EmergencyVehicle;
+
+/// Debug marker that pins a vehicle in place: it keeps occupying the road but never moves
+#[derive(Component)]
+pub struct Frozen;
+
+/// Fraction by which the player may exceed the speed limit
+pub const PLAYER_OVERSPEED: f32 = 0.2;
+
+/// Highest speed (m/s) the player may drive on their current segment
+pub fn player_max_speed(road: &Road, vehicle: &Vehicle) -> f32 {
+    road.segments.get(&vehicle.segment).speed_limit * (1.0 + PLAYER_OVERSPEED)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn move_and_despawn_vehicles(
     mut commands: Commands,
     time: Res<Time>,
-    mut vehicles: Query<(Entity, &mut Vehicle)>,
+    mut vehicles: Query<(Entity, &mut Vehicle, Has<Frozen>), Without<FreeDrive>>,
     roads: Res<Road>,
+    mut stats: ResMut<SimulationStats>,
+    routing: Option<Res<RoutingMode>>,
+    routing_config: Option<Res<RoutingConfig>>,
+    mut arrived: Option<MessageWriter<VehicleArrived>>,
+    mut despawned: Option<MessageWriter<VehicleDespawned>>,
+    mut cleared: Option<MessageWriter<IntersectionCleared>>,
+    mut routes: Option<ResMut<RouteCache>>,
 ) {
-    for (entity, mut vehicle) in &mut vehicles {
+    let routing = routing.as_deref().copied().unwrap_or_default();
+    let routing_config = routing_config.as_deref().copied().unwrap_or_default();
+    let dt = sim_dt(&time);
+
+    for (entity, mut vehicle, frozen) in &mut vehicles {
         let segment = roads.segments.get(&vehicle.segment);
+        vehicle.travel_time += dt;
+
+        if frozen {
+            vehicle.speed = 0.0;
+            continue;
+        }
 
         let segment_length = segment.length;
-        let progress_delta = vehicle.speed * time.delta_secs() / segment_length;
+        let progress_delta = vehicle.speed * dt / segment_length;
 
         vehicle.progress += progress_delta;
 
@@ -72,9 +248,40 @@ pub fn move_and_despawn_vehicles(
             let to_node = roads.nodes.get(&segment.to);
             if to_node.outgoing.is_empty() {
                 crate::log!("DESPAWN: to_node has no outgoing segments");
+                let reason = if segment.to == vehicle.destination {
+                    stats.record_trip(vehicle.travel_time, vehicle.free_flow_time);
+                    if let Some(arrived) = arrived.as_mut() {
+                        arrived.write(VehicleArrived {
+                            entity,
+                            destination: vehicle.destination,
+                            travel_time: vehicle.travel_time,
+                        });
+                    }
+                    DespawnReason::Arrived
+                } else {
+                    DespawnReason::DeadEnd
+                };
+                if let Some(despawned) = despawned.as_mut() {
+                    despawned.write(VehicleDespawned { entity, reason });
+                }
                 commands.entity(entity).despawn();
             } else {
-                let next_segment = next_segment_toward(&roads, segment.to, vehicle.destination);
+                // Follow the planned route; replan only once it is exhausted or no longer
+                // leaves from this node
+                let next_segment = match vehicle.route.get(1) {
+                    Some(&next) if to_node.outgoing.contains(&next) => {
+                        Some((next, vehicle.route[1..].to_vec()))
+                    }
+                    _ => cached_route(
+                        routes.as_deref_mut(),
+                        &roads,
+                        segment.to,
+                        vehicle.destination,
+                        vehicle.class,
+                        routing,
+                        routing_config,
+                    ),
+                };
                 match next_segment {
                     Some((next, route)) => {
                         // Convert excess progress to distance, then to progress on new segment
@@ -82,19 +289,40 @@ pub fn move_and_despawn_vehicles(
                         let next_seg = roads.segments.get(&next);
                         let new_progress = excess_distance / next_seg.length;
 
+                        if let Some(cleared) = cleared.as_mut() {
+                            if let Some((intersection, _)) = roads
+                                .intersections
+                                .iter_with_ids()
+                                .find(|(_, i)| i.incoming.contains(&next))
+                            {
+                                cleared.write(IntersectionCleared {
+                                    entity,
+                                    intersection,
+                                });
+                            }
+                        }
+
+                        vehicle.lane = roads.lane_for_route(&route);
                         vehicle.route = route;
                         vehicle.segment = next;
                         vehicle.progress = new_progress;
                         vehicle.gap.waiting_time = None;
                         vehicle.gap.cleared_to_go = false;
                         vehicle.gap.arrival_order = None;
+                        vehicle.gap.arrived_at_line = None;
                     }
                     None => {
                         crate::log!(
-                            "DESPAWN: pathfinding returned None from {:?} to {:?}",
-                            segment.to,
-                            vehicle.destination
+                            "DESPAWN: pathfinding returned None from {} to {}",
+                            roads.node_label(segment.to),
+                            roads.node_label(vehicle.destination)
                         );
+                        if let Some(despawned) = despawned.as_mut() {
+                            despawned.write(VehicleDespawned {
+                                entity,
+                                reason: DespawnReason::NoRoute,
+                            });
+                        }
                         commands.entity(entity).despawn();
                     }
                 }
@@ -103,32 +331,669 @@ pub fn move_and_despawn_vehicles(
     }
 }
 
-pub fn spawn_vehicles(mut commands: Commands, roads: Res<Road>, occupancy: Res<SegmentOccupancy>) {
+/// Route starting with `segment` and continuing to `destination`, if one exists
+pub fn route_from(
+    road: &Road,
+    segment: Id<Segment>,
+    destination: Id<Node>,
+    options: RouteOptions,
+) -> Option<Vec<Id<Segment>>> {
+    let to = road.segments.get(&segment).to;
+    if to == destination {
+        return Some(vec![segment]);
+    }
+
+    let (_, onward) = next_segment_toward_with(road, to, destination, options)?;
+    Some(std::iter::once(segment).chain(onward).collect())
+}
+
+/// Replan any route that no longer starts with the vehicle's current segment or runs over a
+/// removed segment, which happens after road edits or a routing bug. Vehicles that cannot
+/// reach their destination from where they are get despawned.
+pub fn restore_route_consistency(
+    mut commands: Commands,
+    mut vehicles: Query<(Entity, &mut Vehicle), Without<FreeDrive>>,
+    road: Res<Road>,
+    routing: Option<Res<RoutingMode>>,
+    routing_config: Option<Res<RoutingConfig>>,
+    mut despawned: Option<MessageWriter<VehicleDespawned>>,
+    mut routes: Option<ResMut<RouteCache>>,
+) {
+    let routing = routing.as_deref().copied().unwrap_or_default();
+    let routing_config = routing_config.as_deref().copied().unwrap_or_default();
+
+    for (entity, mut vehicle) in &mut vehicles {
+        // The road was edited away under the vehicle: there is nothing to replan from
+        if road.segments.get_checked(&vehicle.segment).is_none()
+            || road.nodes.get_checked(&vehicle.destination).is_none()
+        {
+            crate::log!(
+                "DESPAWN: {entity} was on removed segment {} or heading to removed node {}",
+                vehicle.segment,
+                vehicle.destination
+            );
+            if let Some(despawned) = despawned.as_mut() {
+                despawned.write(VehicleDespawned {
+                    entity,
+                    reason: DespawnReason::RoadRemoved,
+                });
+            }
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let removed = vehicle
+            .route
+            .iter()
+            .any(|segment| road.segments.get_checked(segment).is_none());
+        if !removed && vehicle.route.first() == Some(&vehicle.segment) {
+            continue;
+        }
+
+        let to = road.segments.get(&vehicle.segment).to;
+        let onward = if to == vehicle.destination {
+            Some(vec![])
+        } else {
+            cached_route(
+                routes.as_deref_mut(),
+                &road,
+                to,
+                vehicle.destination,
+                vehicle.class,
+                routing,
+                routing_config,
+            )
+            .map(|(_, onward)| onward)
+        };
+        let route: Option<Vec<_>> =
+            onward.map(|onward| std::iter::once(vehicle.segment).chain(onward).collect());
+        match route {
+            Some(route) => {
+                crate::log!(
+                    "ROUTE: {entity} was on {} but its route started at {:?}; replanned",
+                    road.segment_label(vehicle.segment),
+                    vehicle.route.first()
+                );
+                vehicle.lane = road.lane_for_route(&route);
+                vehicle.route = route;
+            }
+            None => {
+                crate::log!(
+                    "DESPAWN: {entity} lost its route on {} and cannot reach {}",
+                    road.segment_label(vehicle.segment),
+                    road.node_label(vehicle.destination)
+                );
+                if let Some(despawned) = despawned.as_mut() {
+                    despawned.write(VehicleDespawned {
+                        entity,
+                        reason: DespawnReason::NoRoute,
+                    });
+                }
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_vehicles(
+    mut commands: Commands,
+    time: Res<Time>,
+    roads: Res<Road>,
+    occupancy: Res<SegmentOccupancy>,
+    mut spacing: ResMut<SpawnSpacing>,
+    mut rng: ResMut<SimRng>,
+    routing: Option<Res<RoutingMode>>,
+    routing_config: Option<Res<RoutingConfig>>,
+    mix: Option<Res<VehicleMix>>,
+    mut spawned: Option<MessageWriter<VehicleSpawned>>,
+    mut routes: Option<ResMut<RouteCache>>,
+    config: Option<Res<SpawnConfig>>,
+    demand: Option<Res<DemandProfile>>,
+    od: Option<Res<OdMatrix>>,
+) {
+    let config = config.as_deref().copied().unwrap_or_default();
+    if config.max_vehicles == 0 {
+        return;
+    }
+
+    let routing = routing.as_deref().copied().unwrap_or_default();
+    let routing_config = routing_config.as_deref().copied().unwrap_or_default();
+    let mix = mix.as_deref().copied().unwrap_or_default();
     let mut total_vehicles: usize = occupancy.vehicles.values().map(|v| v.len()).sum();
+    let now = sim_time(&time);
+    let demand = demand.map_or(1.0, |demand| demand.multiplier(now));
 
     for (spawn_id, n) in roads
         .nodes
         .iter_with_ids()
         .filter(|(_, n)| n.is_spawn && !n.outgoing.is_empty())
     {
-        if rand::random::<f32>() >= 0.1 || total_vehicles >= 40 {
+        if rng.uniform() >= config.probability() * demand * n.spawn_weight
+            || total_vehicles >= config.max_vehicles
+        {
             continue;
         }
 
+        if !spacing.allows(spawn_id, now, &roads, &occupancy) {
+            continue;
+        }
+
+        let class = mix.sample(&mut rng);
+
         // Collect valid (destination, first_segment, route) candidates
         let candidates: Vec<_> = roads
             .nodes
             .iter_with_ids()
             .filter(|(_, node)| node.is_despawn && node.position != n.position)
+            .filter(|(dest_id, _)| {
+                od.as_ref()
+                    .is_none_or(|od| od.weight(spawn_id, *dest_id) > 0.0)
+            })
             .filter_map(|(dest_id, _)| {
-                next_segment_toward(&roads, spawn_id, dest_id)
-                    .map(|(first_seg, route)| (dest_id, first_seg, route))
+                cached_route(
+                    routes.as_deref_mut(),
+                    &roads,
+                    spawn_id,
+                    dest_id,
+                    class,
+                    routing,
+                    routing_config,
+                )
+                .map(|(first_seg, route)| (dest_id, first_seg, route))
             })
             .collect();
 
-        if let Some((dest_id, first_seg, route)) = candidates.choose(&mut rand::rng()) {
-            commands.spawn(Vehicle::new(*first_seg, *dest_id, route.clone()));
+        let choice = match od.as_deref() {
+            Some(od) => {
+                rng.choose_weighted(&candidates, |(dest_id, ..)| od.weight(spawn_id, *dest_id))
+            }
+            None => rng.choose(&candidates),
+        };
+        if let Some((dest_id, first_seg, route)) = choice {
+            let mut vehicle = Vehicle::random(*first_seg, *dest_id, route.clone(), class, &mut rng);
+            vehicle.free_flow_time = roads.free_flow_time(route);
+            vehicle.lane = roads.lane_for_route(route);
+            let entity = commands.spawn(vehicle).id();
+            if let Some(spawned) = spawned.as_mut() {
+                spawned.write(VehicleSpawned {
+                    entity,
+                    origin: spawn_id,
+                });
+            }
+            spacing.record(spawn_id, now);
             total_vehicles += 1;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{apply_idm, next_segment_toward, update_occupancy};
+    use crate::RoadEdit;
+    use bevy_ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    #[test]
+    fn test_truck_is_longer_and_slower_than_car() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::X * 100.0);
+        let segment = road.add_segment(a, b, 13.9);
+
+        let car = Vehicle::new_with_class(segment, b, vec![segment], VehicleClass::Car);
+        let truck = Vehicle::new_with_class(segment, b, vec![segment], VehicleClass::Truck);
+        assert!(truck.length > car.length);
+        assert!(truck.width > car.width);
+        assert!(truck.idm.max_acceleration < car.idm.max_acceleration);
+        assert!(truck.idm.min_spacing > car.idm.min_spacing);
+        assert_eq!(car.length, DEFAULT_CAR_LENGTH);
+    }
+
+    #[test]
+    fn test_simulation_speed_scales_distance_per_tick() {
+        // Cruising at its desired speed on an empty road, so it neither speeds up nor slows
+        let distance = |speed: f32| {
+            let mut road = Road::default();
+            let a = road.add_node(Vec3::ZERO);
+            let b = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+            let segment = road.add_segment(a, b, 13.9);
+
+            let mut app = crate::headless::app(road);
+            app.insert_resource(crate::SimulationSpeed(speed));
+            let vehicle = app
+                .world_mut()
+                .spawn(Vehicle::new(segment, b, vec![segment]).with_speed(13.9))
+                .id();
+
+            crate::headless::advance(&mut app, 6, 1.0 / 60.0);
+            let vehicle = app.world().get::<Vehicle>(vehicle).unwrap();
+            (vehicle.progress * 200.0, vehicle.travel_time)
+        };
+
+        let (normal, normal_time) = distance(1.0);
+        assert!((normal - 13.9 * 0.1).abs() < 1e-3, "{normal}");
+        let (fast, fast_time) = distance(2.0);
+        assert!((fast - 2.0 * normal).abs() < 1e-3, "{fast} vs {normal}");
+        assert!((fast_time - 2.0 * normal_time).abs() < 1e-4);
+        assert_eq!(distance(0.0), (0.0, 0.0));
+        assert_eq!(distance(-1.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_mid_segment_spawn_joins_traffic_immediately() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.1));
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        // Stopped car 10 m ahead of the injected one, and a follower behind it
+        world.spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.55));
+        let injected = world
+            .spawn(
+                Vehicle::new(segment, b, vec![segment])
+                    .with_progress(0.5)
+                    .with_speed(12.0),
+            )
+            .id();
+        let follower = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.2))
+            .id();
+
+        world.run_system_once(update_occupancy).unwrap();
+
+        let occupancy = world.resource::<SegmentOccupancy>();
+        let road = world.resource::<Road>();
+        let follower_vehicle = world.get::<Vehicle>(follower).unwrap();
+        let (leader, _) = occupancy
+            .find_next(follower, follower_vehicle, road)
+            .unwrap();
+        assert_eq!(leader.vehicle, injected);
+        assert_eq!(leader.speed, 12.0);
+
+        world.run_system_once(apply_idm).unwrap();
+        let vehicle = world.get::<Vehicle>(injected).unwrap();
+        assert!(vehicle.speed < 12.0, "should brake for the stopped car");
+        assert!(vehicle.braking);
+    }
+
+    #[test]
+    fn test_player_max_speed_tracks_segment_limit() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let urban = road.add_segment(a, b, 13.9);
+        let rural = road.add_segment(b, c, 25.0);
+
+        let mut vehicle = Vehicle::new(urban, c, vec![urban, rural]);
+        let urban_max = player_max_speed(&road, &vehicle);
+        assert!((urban_max - 13.9 * 1.2).abs() < 1e-4);
+
+        vehicle.segment = rural;
+        let rural_max = player_max_speed(&road, &vehicle);
+        assert!((rural_max - 25.0 * 1.2).abs() < 1e-4);
+        assert!(rural_max > urban_max);
+    }
+
+    #[test]
+    fn test_heavier_origin_spawns_more() {
+        let mut road = Road::default();
+        let light = road.add_spawn_node(Vec3::new(0.0, 0.0, 0.0));
+        let heavy = road.add_spawn_node(Vec3::new(0.0, 100.0, 0.0));
+        let exit = road.add_despawn_node(Vec3::new(200.0, 50.0, 0.0));
+        let from_light = road.add_segment(light, exit, 13.9);
+        let from_heavy = road.add_segment(heavy, exit, 13.9);
+        road.nodes.get_mut(&heavy).spawn_weight = 5.0;
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SpawnSpacing>();
+        world.insert_resource(SimRng::seeded(7));
+        world.insert_resource(road);
+
+        // Occupancy is never updated, so neither the vehicle cap nor spacing interferes
+        for _ in 0..300 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(2.0));
+            world.run_system_once(spawn_vehicles).unwrap();
+        }
+
+        let mut query = world.query::<&Vehicle>();
+        let mut count = |segment| query.iter(&world).filter(|v| v.segment == segment).count();
+        let (light_count, heavy_count) = (count(from_light), count(from_heavy));
+        assert!(light_count > 0);
+        assert!(
+            heavy_count > light_count * 3,
+            "light {light_count}, heavy {heavy_count}"
+        );
+    }
+
+    #[test]
+    fn test_max_vehicles_caps_live_count() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(2000.0, 0.0, 0.0));
+        road.add_segment(a, b, 13.9);
+        let mut app = crate::headless::app(road);
+        app.insert_resource(SpawnConfig {
+            max_vehicles: 3,
+            spawn_probability: 5.0,
+        });
+
+        let mut most = 0;
+        for _ in 0..600 {
+            crate::headless::advance(&mut app, 1, 0.05);
+            let world = app.world_mut();
+            most = most.max(world.query::<&Vehicle>().iter(world).count());
+        }
+        assert_eq!(most, 3);
+
+        // No cap at all means no traffic
+        app.insert_resource(SpawnConfig {
+            max_vehicles: 0,
+            ..Default::default()
+        });
+        let world = app.world_mut();
+        let existing: Vec<_> = world
+            .query_filtered::<Entity, With<Vehicle>>()
+            .iter(world)
+            .collect();
+        for entity in existing {
+            world.despawn(entity);
+        }
+        crate::headless::advance(&mut app, 200, 0.05);
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Vehicle>().iter(world).count(), 0);
+    }
+
+    #[test]
+    fn test_demand_profile_shapes_spawn_frequency() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SpawnSpacing>();
+        world.insert_resource(SimRng::seeded(5));
+        world.insert_resource(road);
+        world.insert_resource(DemandProfile::new([
+            (50.0, 0.05),
+            (75.0, 2.0),
+            (100.0, 2.0),
+            (150.0, 0.05),
+        ]));
+
+        // Vehicles spawned in each 50 s window; occupancy is never updated, so only the
+        // spawn headway limits the count
+        let mut windows = [0; 4];
+        for window in &mut windows {
+            let before = world.query::<&Vehicle>().iter(&world).count();
+            for _ in 0..500 {
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_millis(100));
+                world.run_system_once(spawn_vehicles).unwrap();
+            }
+            *window = world.query::<&Vehicle>().iter(&world).count() - before;
+        }
+
+        // Quiet, peaking between 50 s and 100 s, then tailing off
+        assert!(windows[1] > windows[0] * 3, "{windows:?}");
+        assert!(windows[1] > windows[2], "{windows:?}");
+        assert!(windows[2] > windows[3], "{windows:?}");
+    }
+
+    #[test]
+    fn test_od_matrix_restricts_trips_to_configured_pairs() {
+        // Two entries and two exits, all joined through a shared middle node
+        let mut road = Road::default();
+        let west = road.add_spawn_node(Vec3::new(0.0, 0.0, 0.0));
+        let south = road.add_spawn_node(Vec3::new(100.0, -100.0, 0.0));
+        let middle = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let east = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let north = road.add_despawn_node(Vec3::new(100.0, 100.0, 0.0));
+        let from_west = road.add_segment(west, middle, 13.9);
+        road.add_segment(south, middle, 13.9);
+        road.add_segment(middle, east, 13.9);
+        road.add_segment(middle, north, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SpawnSpacing>();
+        world.insert_resource(SimRng::seeded(2));
+        world.insert_resource(road);
+        world.insert_resource(OdMatrix::default().with_trips(west, east, 1.0));
+
+        for _ in 0..200 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(2));
+            world.run_system_once(spawn_vehicles).unwrap();
+        }
+
+        let vehicles: Vec<_> = world.query::<&Vehicle>().iter(&world).collect();
+        assert!(!vehicles.is_empty());
+        for vehicle in vehicles {
+            assert_eq!(vehicle.segment, from_west);
+            assert_eq!(vehicle.destination, east);
+        }
+    }
+
+    /// 3x3 one-way grid with 100 m blocks flowing east and north; ids[8] is the exit
+    fn one_way_grid() -> (Road, Vec<Id<Node>>) {
+        let mut road = Road::default();
+        let ids: Vec<_> = (0..9)
+            .map(|i| road.add_node(Vec3::new((i % 3) as f32, (i / 3) as f32, 0.0) * 100.0))
+            .collect();
+        for y in 0..3 {
+            for x in 0..3 {
+                if x + 1 < 3 {
+                    road.add_segment(ids[y * 3 + x], ids[y * 3 + x + 1], 13.9);
+                }
+                if y + 1 < 3 {
+                    road.add_segment(ids[y * 3 + x], ids[(y + 1) * 3 + x], 13.9);
+                }
+            }
+        }
+        (road, ids)
+    }
+
+    #[test]
+    fn test_vehicle_follows_planned_route_to_destination() {
+        let (road, ids) = one_way_grid();
+        let between = |from, to| {
+            road.segments
+                .iter_with_ids()
+                .find(|(_, segment)| segment.from == from && segment.to == to)
+                .unwrap()
+                .0
+        };
+        // Zig-zag east, north, east, north: as short as any other route, so only
+        // following the plan gives exactly this path
+        let route = vec![
+            between(ids[0], ids[1]),
+            between(ids[1], ids[4]),
+            between(ids[4], ids[5]),
+            between(ids[5], ids[8]),
+        ];
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SimulationStats>();
+        world.insert_resource(road);
+        let entity = world
+            .spawn(Vehicle::new(route[0], ids[8], route.clone()).with_speed(20.0))
+            .id();
+
+        let mut driven = vec![route[0]];
+        for _ in 0..400 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.1));
+            world.run_system_once(move_and_despawn_vehicles).unwrap();
+            let Some(vehicle) = world.get::<Vehicle>(entity) else {
+                break;
+            };
+            if driven.last() != Some(&vehicle.segment) {
+                assert_eq!(vehicle.route[0], vehicle.segment);
+                assert_eq!(vehicle.gap.waiting_time, None);
+                driven.push(vehicle.segment);
+            }
+        }
+
+        assert_eq!(driven, route);
+        assert!(world.get_entity(entity).is_err());
+        assert_eq!(world.resource::<SimulationStats>().completed_trips, 1);
+    }
+
+    #[test]
+    fn test_remaining_distance_along_grid_route() {
+        let (road, ids) = one_way_grid();
+
+        let (first, route) = next_segment_toward(&road, ids[0], ids[8]).unwrap();
+        assert_eq!(route.len(), 4);
+
+        // A quarter along the first block, three blocks to go after it
+        let mut vehicle = Vehicle::new(first, ids[8], route.clone()).with_progress(0.25);
+        assert!((vehicle.remaining_distance(&road) - 375.0).abs() < 1e-3);
+
+        // Halfway along the last block
+        vehicle.segment = route[3];
+        vehicle.progress = 0.5;
+        assert!((vehicle.remaining_distance(&road) - 50.0).abs() < 1e-3);
+
+        // Stale or empty route: only what is left of the current segment
+        vehicle.route.clear();
+        assert!((vehicle.remaining_distance(&road) - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_frozen_vehicle_stays_put_and_blocks_followers() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(400.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SimulationStats>();
+        world.init_resource::<SimRng>();
+        world.insert_resource(road);
+
+        let frozen = world
+            .spawn((
+                Vehicle::new(segment, b, vec![segment])
+                    .with_progress(0.5)
+                    .with_speed(10.0),
+                Frozen,
+            ))
+            .id();
+        let follower = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.2))
+            .id();
+        let other = world
+            .spawn(Vehicle::new(segment, b, vec![segment]).with_progress(0.6))
+            .id();
+
+        for _ in 0..600 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.05));
+            world.run_system_once(update_occupancy).unwrap();
+            world.run_system_once(apply_idm).unwrap();
+            world.run_system_once(move_and_despawn_vehicles).unwrap();
+        }
+
+        let frozen = world.get::<Vehicle>(frozen).unwrap();
+        assert_eq!(frozen.progress, 0.5);
+        assert_eq!(frozen.speed, 0.0);
+
+        // The free car ahead drove off the end of the road
+        assert!(world.get::<Vehicle>(other).is_none());
+
+        // The follower closed up and came to a stop behind the frozen car
+        let follower = world.get::<Vehicle>(follower).unwrap();
+        let gap = (frozen.progress - follower.progress) * 400.0 - frozen.length;
+        assert!(follower.speed < 0.1);
+        assert!(gap > 0.0 && gap < 5.0, "gap {gap}");
+    }
+
+    #[test]
+    fn test_mismatched_route_is_replanned_or_despawned() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let island = road.add_despawn_node(Vec3::new(0.0, 100.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+
+        let mut world = World::new();
+        world.insert_resource(road);
+
+        // Already moved onto bc, but the route still starts at ab
+        let stale = world
+            .spawn(Vehicle::new(bc, c, vec![ab, bc]).with_progress(0.3))
+            .id();
+        // No road leads to the destination at all
+        let stranded = world.spawn(Vehicle::new(ab, island, vec![bc])).id();
+        let consistent = world.spawn(Vehicle::new(ab, c, vec![ab, bc])).id();
+
+        world.run_system_once(restore_route_consistency).unwrap();
+
+        let vehicle = world.get::<Vehicle>(stale).unwrap();
+        assert_eq!(vehicle.route, vec![bc]);
+        assert_eq!(vehicle.segment, bc);
+        assert_eq!(vehicle.progress, 0.3);
+        assert!(world.get_entity(stranded).is_err());
+        assert_eq!(
+            world.get::<Vehicle>(consistent).unwrap().route,
+            vec![ab, bc]
+        );
+    }
+
+    #[test]
+    fn test_routes_over_removed_segments_are_replanned() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let detour = road.add_node(Vec3::new(150.0, 50.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+        let b_detour = road.add_segment(b, detour, 13.9);
+        let detour_c = road.add_segment(detour, c, 13.9);
+
+        let mut world = World::new();
+        let rerouted = world.spawn(Vehicle::new(ab, c, vec![ab, bc])).id();
+        let on_removed = world.spawn(Vehicle::new(bc, c, vec![bc])).id();
+
+        road.apply(RoadEdit::RemoveSegment(bc));
+        world.insert_resource(road);
+        world.run_system_once(restore_route_consistency).unwrap();
+
+        assert_eq!(
+            world.get::<Vehicle>(rerouted).unwrap().route,
+            vec![ab, b_detour, detour_c]
+        );
+        assert!(world.get_entity(on_removed).is_err());
+    }
+}
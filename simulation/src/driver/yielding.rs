@@ -1,20 +1,258 @@
+use bevy_ecs::prelude::*;
 use glam::Vec3;
 
 use crate::driver::Blinker;
 
-#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum YieldResolver {
-    #[default]
-    RightOfWay,
+    RightOfWay(SidePriority),
     Roundabout,
+    /// Give-way signs on the minor approaches: minor traffic yields to major traffic
+    /// without having to stop first. Approaches within the side threshold of
+    /// `major_axis` (either direction) form the major road.
+    YieldSign {
+        major_axis: Vec3,
+    },
+    /// Two-phase signal: north-south approaches get green for `phase_duration` seconds,
+    /// then east-west for `east_west_duration` (or `phase_duration` again), shifted by
+    /// `offset` seconds
+    TrafficLight {
+        phase_duration: f32,
+        offset: f32,
+        #[cfg_attr(feature = "serde", serde(default))]
+        east_west_duration: Option<f32>,
+    },
+    /// Every approach stops first; vehicles then go in the order they came to a halt
+    AllWayStop,
+}
+
+impl Default for YieldResolver {
+    fn default() -> Self {
+        YieldResolver::RightOfWay(SidePriority::RIGHT_HAND)
+    }
+}
+
+/// Sign on an intersection approach, on top of the intersection's [`YieldResolver`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrafficControl {
+    #[default]
+    None,
+    /// Vehicles come to a full stop at the line before entering, even with nothing coming
+    Stop,
+    /// Vehicles give way without having to stop
+    Yield,
 }
 
-/// Threshold for deadlock detection - if both cars waiting this long, use arrival order
-const DEADLOCK_THRESHOLD: f32 = 0.5;
+/// Default seconds two vehicles wait on each other before arrival order decides, see
+/// [`YieldingConfig::deadlock_threshold`]
+pub const DEFAULT_DEADLOCK_THRESHOLD: f32 = 0.5;
+
+/// Default distance (m) from the junction within which an approaching vehicle is always
+/// given way to, see [`YieldingConfig::min_safe_distance`]
+pub const DEFAULT_MIN_SAFE_DISTANCE: f32 = 3.0;
+
+/// How cautiously vehicles negotiate intersections
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct YieldingConfig {
+    /// Seconds two conflicting vehicles wait on each other before arrival order decides
+    /// who goes, breaking circular right-of-way
+    pub deadlock_threshold: f32,
+    /// Minimum physical distance (m) to an approaching vehicle before yielding to it,
+    /// whatever gap its speed suggests
+    pub min_safe_distance: f32,
+}
+
+impl Default for YieldingConfig {
+    fn default() -> Self {
+        Self {
+            deadlock_threshold: DEFAULT_DEADLOCK_THRESHOLD,
+            min_safe_distance: DEFAULT_MIN_SAFE_DISTANCE,
+        }
+    }
+}
+
+/// Default |cross| of two headings above which the other vehicle counts as coming from a side.
+/// 0.3 is roughly 17.5 degrees away from parallel.
+pub const SIDE_THRESHOLD: f32 = 0.3;
+
+/// Turn paths whose cross values differ by less than this are considered equally long
+pub const PATH_TIE_THRESHOLD: f32 = 0.1;
+
+/// Which side has priority at an uncontrolled junction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Handedness {
+    /// Yield to traffic from the right (right-hand traffic)
+    #[default]
+    Right,
+    /// Yield to traffic from the left (left-hand traffic)
+    Left,
+}
+
+/// Where another vehicle approaches from, relative to our heading
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    /// Opposing or same direction, within the threshold band around parallel
+    Parallel,
+}
+
+/// How otherwise equal vehicles are ordered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieBreak {
+    /// Order of entering the intersection's waiting zone
+    #[default]
+    ArrivalOrder,
+    /// Order of reaching the stop line, falling back to arrival order if either hasn't
+    StopLine,
+}
+
+impl TieBreak {
+    /// Whether `me` goes before `them`
+    pub fn first(&self, me: &Approach, them: &Approach) -> bool {
+        if let (TieBreak::StopLine, Some(mine), Some(theirs)) =
+            (self, me.arrived_at_line, them.arrived_at_line)
+        {
+            if mine != theirs {
+                return mine < theirs;
+            }
+        }
+
+        me.arrival_order < them.arrival_order
+    }
+}
+
+/// Configuration of the priority-to-side rule used by [`YieldResolver::RightOfWay`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SidePriority {
+    pub handedness: Handedness,
+    /// |cross| of the two headings at or below which approaches are treated as parallel
+    pub side_threshold: f32,
+    pub tie_break: TieBreak,
+}
+
+impl Default for SidePriority {
+    fn default() -> Self {
+        Self::RIGHT_HAND
+    }
+}
+
+impl SidePriority {
+    pub const RIGHT_HAND: Self = Self {
+        handedness: Handedness::Right,
+        side_threshold: SIDE_THRESHOLD,
+        tie_break: TieBreak::ArrivalOrder,
+    };
+
+    pub const LEFT_HAND: Self = Self {
+        handedness: Handedness::Left,
+        side_threshold: SIDE_THRESHOLD,
+        tie_break: TieBreak::ArrivalOrder,
+    };
+
+    /// Side the other vehicle approaches from, given both headings into the junction.
+    ///
+    /// Headings are used rather than positions: a vehicle heading across our path from
+    /// left to right (negative cross) approaches from our left.
+    pub fn side_of(&self, my_direction: Vec3, their_direction: Vec3) -> Side {
+        let cross = my_direction.cross(their_direction).z;
+        if cross < -self.side_threshold {
+            Side::Left
+        } else if cross > self.side_threshold {
+            Side::Right
+        } else {
+            Side::Parallel
+        }
+    }
+
+    /// Whether the rule gives a vehicle on `side` priority over us
+    pub fn favors(&self, side: Side) -> bool {
+        matches!(
+            (self.handedness, side),
+            (Handedness::Right, Side::Right) | (Handedness::Left, Side::Left)
+        )
+    }
+
+    /// Length of a turn path for the tiebreaker; the turn toward the curb is shortest
+    fn path_length(&self, turn_type: TurnType) -> f32 {
+        match self.handedness {
+            Handedness::Right => turn_type.cross(),
+            Handedness::Left => -turn_type.cross(),
+        }
+    }
+}
+
+/// A vehicle's situation when approaching a conflict, as seen by [`YieldResolver`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Approach {
+    pub turn_type: TurnType,
+    /// Heading into the intersection
+    pub direction: Vec3,
+    /// Whether the approach has a green light; always true without a signal
+    pub green: bool,
+    /// FIFO order of entering the waiting zone; `u32::MAX` if not there yet
+    pub arrival_order: u32,
+    pub waiting_time: f32,
+    /// Simulation time the vehicle reached the stop line, if it has
+    pub arrived_at_line: Option<f32>,
+    /// Simulation time the vehicle came to a halt at an all-way stop, if it has
+    pub stopped_at: Option<f32>,
+    /// An emergency vehicle outranks every regular one, whatever the resolver
+    pub emergency: bool,
+    /// On a priority road, which goes before side streets under [`YieldResolver::RightOfWay`]
+    pub priority_road: bool,
+}
+
+impl Approach {
+    pub fn new(
+        turn_type: TurnType,
+        direction: Vec3,
+        arrival_order: u32,
+        waiting_time: f32,
+    ) -> Self {
+        Self {
+            turn_type,
+            direction,
+            green: true,
+            arrival_order,
+            waiting_time,
+            arrived_at_line: None,
+            stopped_at: None,
+            emergency: false,
+            priority_road: false,
+        }
+    }
+}
 
 impl YieldResolver {
+    /// Whether the signal shows green to traffic heading in `approach_direction` at
+    /// `sim_time`. Approaches closer to the Y axis run in the first phase of each cycle.
+    /// Unsignalized junctions are always green.
+    pub fn current_green(&self, sim_time: f32, approach_direction: Vec3) -> bool {
+        let YieldResolver::TrafficLight {
+            phase_duration,
+            offset,
+            east_west_duration,
+        } = self
+        else {
+            return true;
+        };
+
+        let north_south_green = phase_duration.max(0.1);
+        let cycle = north_south_green + east_west_duration.unwrap_or(*phase_duration).max(0.1);
+        let in_first_phase = (sim_time + offset).rem_euclid(cycle) < north_south_green;
+        let north_south = approach_direction.y.abs() >= approach_direction.x.abs();
+        in_first_phase == north_south
+    }
+
     /// Determines if the current vehicle has priority over another vehicle.
     /// Uses arrival_order (FIFO) for deadlock resolution - earlier arrivals get priority.
+    #[allow(clippy::too_many_arguments)]
     pub fn has_priority(
         &self,
         my_turn_type: TurnType,
@@ -26,49 +264,141 @@ impl YieldResolver {
         their_arrival_order: u32,
         their_waiting_time: f32,
     ) -> bool {
+        self.has_priority_with(
+            my_turn_type,
+            my_direction,
+            my_arrival_order,
+            my_waiting_time,
+            their_turn_type,
+            their_direction,
+            their_arrival_order,
+            their_waiting_time,
+            &YieldingConfig::default(),
+        )
+    }
+
+    /// [`YieldResolver::has_priority`] with the given tuning
+    #[allow(clippy::too_many_arguments)]
+    pub fn has_priority_with(
+        &self,
+        my_turn_type: TurnType,
+        my_direction: Vec3,
+        my_arrival_order: u32,
+        my_waiting_time: f32,
+        their_turn_type: TurnType,
+        their_direction: Vec3,
+        their_arrival_order: u32,
+        their_waiting_time: f32,
+        config: &YieldingConfig,
+    ) -> bool {
+        self.has_priority_over_with(
+            &Approach::new(
+                my_turn_type,
+                my_direction,
+                my_arrival_order,
+                my_waiting_time,
+            ),
+            &Approach::new(
+                their_turn_type,
+                their_direction,
+                their_arrival_order,
+                their_waiting_time,
+            ),
+            config,
+        )
+    }
+
+    /// Determines if vehicle `me` has priority over vehicle `them`
+    pub fn has_priority_over(&self, me: &Approach, them: &Approach) -> bool {
+        self.has_priority_over_with(me, them, &YieldingConfig::default())
+    }
+
+    /// [`YieldResolver::has_priority_over`] with the given tuning
+    pub fn has_priority_over_with(
+        &self,
+        me: &Approach,
+        them: &Approach,
+        config: &YieldingConfig,
+    ) -> bool {
+        if me.emergency != them.emergency {
+            return me.emergency;
+        }
+
         match self {
-            YieldResolver::RightOfWay => {
+            YieldResolver::RightOfWay(rule) => {
+                // Side streets always give way to the priority road; the rules below only
+                // order two approaches of equal rank
+                if me.priority_road != them.priority_road {
+                    return me.priority_road;
+                }
+
                 // 0. FIFO queue priority: vehicles in the queue go before those not yet in queue
                 // arrival_order = u32::MAX means vehicle hasn't entered waiting zone yet
-                if their_arrival_order == u32::MAX && my_arrival_order != u32::MAX {
+                if them.arrival_order == u32::MAX && me.arrival_order != u32::MAX {
                     return true; // I'm in queue, they're not - I have priority
                 }
-                if my_arrival_order == u32::MAX && their_arrival_order != u32::MAX {
+                if me.arrival_order == u32::MAX && them.arrival_order != u32::MAX {
                     return false; // They're in queue, I'm not - they have priority
                 }
 
                 // DEADLOCK OVERRIDE: If both vehicles have been waiting a long time,
                 // use pure FIFO (arrival order) to break any circular dependencies
-                if my_waiting_time > DEADLOCK_THRESHOLD && their_waiting_time > DEADLOCK_THRESHOLD {
-                    return my_arrival_order < their_arrival_order;
+                if me.waiting_time > config.deadlock_threshold
+                    && them.waiting_time > config.deadlock_threshold
+                {
+                    return me.arrival_order < them.arrival_order;
                 }
 
-                // 1. Yield to right (highest priority rule)
-                // Cross product of heading directions - but we need to know where they're COMING FROM
-                // (approach direction = opposite of heading), so we flip the comparison
-                let direction_cross = my_direction.cross(their_direction).z;
-                if direction_cross < -0.3 {
-                    return true; // they are to our left, we have priority
-                } else if direction_cross > 0.3 {
-                    return false; // they are to our right, they have priority
+                // 1. Yield to the priority side (highest priority rule)
+                match rule.side_of(me.direction, them.direction) {
+                    Side::Parallel => {}
+                    side => return !rule.favors(side),
                 }
 
                 // 2. Opposing/same direction: shorter turn path wins
                 // In right-hand traffic: right turn (negative) < straight (0) < left turn (positive)
-                // So more negative = shorter physical path = higher priority
-                let my_path = my_turn_type.cross();
-                let their_path = their_turn_type.cross();
-                if (my_path - their_path).abs() > 0.1 {
+                // So more negative = shorter physical path = higher priority (mirrored for left-hand)
+                let my_path = rule.path_length(me.turn_type);
+                let their_path = rule.path_length(them.turn_type);
+                if (my_path - their_path).abs() > PATH_TIE_THRESHOLD {
                     return my_path < their_path;
                 }
 
-                // 3. Deterministic tiebreaker: earlier arrival wins (FIFO)
-                my_arrival_order < their_arrival_order
+                // 3. Deterministic tiebreaker
+                rule.tie_break.first(me, them)
+            }
+            YieldResolver::YieldSign { major_axis } => {
+                let i_am_major = on_major_road(me.direction, *major_axis);
+                let they_are_major = on_major_road(them.direction, *major_axis);
+
+                // Major road always goes first, regardless of queue order or waiting time
+                if i_am_major != they_are_major {
+                    return i_am_major;
+                }
+
+                // Same road: regular right-of-way between the two
+                YieldResolver::default().has_priority_over_with(me, them, config)
+            }
+            YieldResolver::TrafficLight { .. } => {
+                // Green beats red; between two greens (or two reds) regular right-of-way applies
+                if me.green != them.green {
+                    return me.green;
+                }
+                YieldResolver::default().has_priority_over_with(me, them, config)
+            }
+            YieldResolver::AllWayStop => {
+                // Whoever stopped first goes first; a vehicle still rolling up waits its turn
+                match (me.stopped_at, them.stopped_at) {
+                    (Some(mine), Some(theirs)) if mine != theirs => mine < theirs,
+                    (Some(_), None) => true,
+                    (None, Some(_)) => false,
+                    _ => me.arrival_order < them.arrival_order,
+                }
             }
             YieldResolver::Roundabout => {
                 // Simple rule: vehicles in the circle ALWAYS have priority over entering vehicles
-                let i_am_entering = my_turn_type == TurnType::RoundaboutEntry;
-                let they_are_in_circle = their_turn_type == TurnType::RoundaboutCircle;
+                let i_am_entering = me.turn_type == TurnType::RoundaboutEntry;
+                let they_are_in_circle = them.turn_type == TurnType::RoundaboutCircle;
 
                 if i_am_entering && they_are_in_circle {
                     return false; // They're in circle, I must yield
@@ -81,6 +411,14 @@ impl YieldResolver {
     }
 }
 
+/// Whether an approach heading runs along the major axis of a yield-sign junction
+pub fn on_major_road(direction: Vec3, major_axis: Vec3) -> bool {
+    SidePriority::RIGHT_HAND.side_of(
+        direction.normalize_or_zero(),
+        major_axis.normalize_or_zero(),
+    ) == Side::Parallel
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,7 +435,7 @@ mod tests {
 
     #[test]
     fn test_yield_to_right_both_straight() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
 
         // DOWN = facing south, coming from NORTH
         // LEFT = facing west, coming from EAST
@@ -130,7 +468,7 @@ mod tests {
 
     #[test]
     fn test_yield_to_right_overrides_turn_type() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
 
         // DOWN = from north, LEFT = from east
         // North has priority over east (east is to north's left)
@@ -149,7 +487,7 @@ mod tests {
 
     #[test]
     fn test_opposing_directions_right_turn_beats_straight() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
 
         // Opposing traffic (north vs south): direction_cross ≈ 0
         // Right turn has shorter path than straight
@@ -180,7 +518,7 @@ mod tests {
 
     #[test]
     fn test_opposing_directions_right_turn_beats_left_turn() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
 
         // Both from opposing directions, one turning right, one turning left
         // Right turn (shorter path) wins
@@ -209,7 +547,7 @@ mod tests {
 
     #[test]
     fn test_arrival_order_tiebreaker() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
 
         // Same everything - earlier arrival (lower number) wins
         assert!(resolver.has_priority(
@@ -237,7 +575,7 @@ mod tests {
 
     #[test]
     fn test_deadlock_breaks_with_arrival_order() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
 
         // When both vehicles have been waiting > 0.5s, pure FIFO (arrival order) wins
         // regardless of direction or turn type
@@ -281,7 +619,7 @@ mod tests {
 
     #[test]
     fn test_queue_priority_over_non_queue() {
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
         const NOT_IN_QUEUE: u32 = u32::MAX;
 
         // Vehicle in queue (FIRST) has priority over vehicle not in queue
@@ -315,7 +653,7 @@ mod tests {
     fn test_yield_to_right_from_positions() {
         // Simulate actual direction computation from road.rs:
         // direction = (intersection_center - from_position).normalize()
-        let resolver = YieldResolver::RightOfWay;
+        let resolver = YieldResolver::default();
         let center = Vec3::ZERO;
 
         // Car from north (pos 0,10,0): direction = DOWN (facing south)
@@ -381,9 +719,302 @@ mod tests {
             0.0,
         ));
     }
+
+    #[test]
+    fn test_deadlock_threshold_comes_from_config() {
+        // The westbound vehicle comes from the right, but arrived second
+        let resolver = YieldResolver::RightOfWay(SidePriority::RIGHT_HAND);
+        let priority = |config: &YieldingConfig| {
+            resolver.has_priority_with(
+                TurnType::Straight,
+                heading(60.0),
+                FIRST,
+                1.0,
+                TurnType::Straight,
+                heading(180.0),
+                SECOND,
+                1.0,
+                config,
+            )
+        };
+
+        // A second each is past the default threshold, so arrival order decides
+        assert!(priority(&YieldingConfig::default()));
+        let patient = YieldingConfig {
+            deadlock_threshold: 5.0,
+            ..Default::default()
+        };
+        assert!(!priority(&patient));
+    }
+
+    /// Heading into the junction at `degrees` counter-clockwise from east
+    fn heading(degrees: f32) -> Vec3 {
+        let radians = degrees.to_radians();
+        Vec3::new(radians.cos(), radians.sin(), 0.0)
+    }
+
+    #[test]
+    fn test_skewed_t_junction() {
+        // Main road runs east-west; the stem joins from the south-west at 60 degrees
+        let eastbound = heading(0.0);
+        let westbound = heading(180.0);
+        let stem = heading(60.0);
+
+        let right_hand = YieldResolver::RightOfWay(SidePriority::RIGHT_HAND);
+        let priority = |resolver: YieldResolver, me: Vec3, them: Vec3| {
+            resolver.has_priority(
+                TurnType::Straight,
+                me,
+                FIRST,
+                0.0,
+                TurnType::Straight,
+                them,
+                SECOND,
+                0.0,
+            )
+        };
+
+        // Eastbound traffic comes from the stem's left, westbound from its right
+        assert_eq!(
+            SidePriority::RIGHT_HAND.side_of(stem, eastbound),
+            Side::Left
+        );
+        assert_eq!(
+            SidePriority::RIGHT_HAND.side_of(stem, westbound),
+            Side::Right
+        );
+        assert!(priority(right_hand, stem, eastbound));
+        assert!(!priority(right_hand, stem, westbound));
+        assert!(!priority(right_hand, eastbound, stem));
+        assert!(priority(right_hand, westbound, stem));
+
+        // Left-hand traffic mirrors every decision
+        let left_hand = YieldResolver::RightOfWay(SidePriority::LEFT_HAND);
+        assert!(!priority(left_hand, stem, eastbound));
+        assert!(priority(left_hand, stem, westbound));
+
+        // A wider band treats the 60 degree stem (|cross| ~ 0.87) as parallel, leaving the
+        // decision to the turn-path and arrival-order tiebreakers
+        let wide = SidePriority {
+            side_threshold: 0.9,
+            ..SidePriority::RIGHT_HAND
+        };
+        assert_eq!(wide.side_of(stem, westbound), Side::Parallel);
+        assert!(priority(YieldResolver::RightOfWay(wide), stem, westbound));
+    }
+
+    #[test]
+    fn test_shallow_merge_falls_in_parallel_band() {
+        // 10 degrees apart is well inside the default band: shorter path wins instead
+        let rule = SidePriority::RIGHT_HAND;
+        assert_eq!(rule.side_of(heading(90.0), heading(100.0)), Side::Parallel);
+        assert_eq!(rule.side_of(heading(90.0), heading(260.0)), Side::Parallel);
+
+        let resolver = YieldResolver::RightOfWay(rule);
+        assert!(resolver.has_priority(
+            TurnType::Right(-0.7),
+            heading(90.0),
+            SECOND,
+            0.0,
+            TurnType::Straight,
+            heading(100.0),
+            FIRST,
+            0.0,
+        ));
+    }
+
+    #[test]
+    fn test_all_way_stop_priority_follows_stop_order() {
+        let resolver = YieldResolver::AllWayStop;
+        let stopped = |direction: Vec3, arrival_order: u32, stopped_at: Option<f32>| Approach {
+            stopped_at,
+            ..Approach::new(TurnType::Straight, direction, arrival_order, 0.0)
+        };
+
+        // Stopped first beats the priority side and an earlier arrival order
+        let first = stopped(DOWN, SECOND, Some(1.0));
+        let later = stopped(LEFT, FIRST, Some(2.0));
+        assert!(resolver.has_priority_over(&first, &later));
+        assert!(!resolver.has_priority_over(&later, &first));
+
+        // A vehicle that hasn't stopped yet waits for one that has
+        let rolling = stopped(RIGHT, FIRST, None);
+        assert!(resolver.has_priority_over(&later, &rolling));
+        assert!(!resolver.has_priority_over(&rolling, &later));
+
+        // Simultaneous stops fall back to arrival order
+        let a = stopped(UP, FIRST, Some(3.0));
+        let b = stopped(LEFT, SECOND, Some(3.0));
+        assert!(resolver.has_priority_over(&a, &b));
+        assert!(!resolver.has_priority_over(&b, &a));
+    }
+
+    #[test]
+    fn test_traffic_light_priority_flips_each_phase() {
+        let resolver = YieldResolver::TrafficLight {
+            phase_duration: 10.0,
+            offset: 0.0,
+            east_west_duration: None,
+        };
+        let at = |time: f32, direction: Vec3| Approach {
+            green: resolver.current_green(time, direction),
+            ..Approach::new(TurnType::Straight, direction, FIRST, 0.0)
+        };
+
+        // One full cycle: north-south green, east-west green, north-south green again
+        for (time, north_south_green) in [(5.0, true), (15.0, false), (25.0, true)] {
+            assert_eq!(resolver.current_green(time, DOWN), north_south_green);
+            assert_eq!(resolver.current_green(time, UP), north_south_green);
+            assert_eq!(resolver.current_green(time, LEFT), !north_south_green);
+            assert_eq!(resolver.current_green(time, RIGHT), !north_south_green);
+
+            // Regardless of which side would win at an uncontrolled junction
+            let from_north = at(time, DOWN);
+            let from_east = at(time, LEFT);
+            assert_eq!(
+                resolver.has_priority_over(&from_north, &from_east),
+                north_south_green
+            );
+            assert_eq!(
+                resolver.has_priority_over(&from_east, &from_north),
+                !north_south_green
+            );
+        }
+
+        // The offset shifts the cycle
+        let shifted = YieldResolver::TrafficLight {
+            phase_duration: 10.0,
+            offset: 10.0,
+            east_west_duration: None,
+        };
+        assert!(!shifted.current_green(5.0, DOWN));
+
+        // Uneven split: 30 s north-south, then 10 s east-west
+        let split = YieldResolver::TrafficLight {
+            phase_duration: 30.0,
+            offset: 0.0,
+            east_west_duration: Some(10.0),
+        };
+        for (time, north_south_green) in [(25.0, true), (35.0, false), (45.0, true)] {
+            assert_eq!(split.current_green(time, DOWN), north_south_green);
+            assert_eq!(split.current_green(time, LEFT), !north_south_green);
+        }
+
+        // Both green (opposing approaches): regular right-of-way between them
+        let right_turn = Approach {
+            turn_type: TurnType::Right(0.7),
+            ..at(5.0, UP)
+        };
+        let left_turn = Approach {
+            turn_type: TurnType::Left(0.7),
+            ..at(5.0, DOWN)
+        };
+        assert!(resolver.has_priority_over(&right_turn, &left_turn));
+        assert!(!resolver.has_priority_over(&left_turn, &right_turn));
+
+        // Unsignalized junctions are always green
+        assert!(YieldResolver::default().current_green(15.0, DOWN));
+    }
+
+    #[test]
+    fn test_yield_sign_major_road_first() {
+        let resolver = YieldResolver::YieldSign {
+            major_axis: Vec3::X,
+        };
+
+        // North approach is minor: it yields to east even though east is on its left,
+        // and even when it queued first
+        assert!(!resolver.has_priority(
+            TurnType::Straight,
+            DOWN,
+            FIRST,
+            0.0,
+            TurnType::Straight,
+            LEFT,
+            SECOND,
+            0.0,
+        ));
+        assert!(resolver.has_priority(
+            TurnType::Straight,
+            LEFT,
+            SECOND,
+            0.0,
+            TurnType::Straight,
+            DOWN,
+            FIRST,
+            0.0,
+        ));
+
+        // Two major approaches fall back to the regular tiebreakers
+        assert!(resolver.has_priority(
+            TurnType::Right(-0.7),
+            LEFT,
+            SECOND,
+            0.0,
+            TurnType::Straight,
+            RIGHT,
+            FIRST,
+            0.0,
+        ));
+    }
+
+    #[test]
+    fn test_priority_road_beats_side_street_from_either_side() {
+        let resolver = YieldResolver::default();
+        let approach = |direction: Vec3, arrival_order: u32, priority_road: bool| Approach {
+            priority_road,
+            ..Approach::new(TurnType::Straight, direction, arrival_order, 0.0)
+        };
+
+        // East-west is the priority road. Coming from the north, the eastern approach is on
+        // the minor car's left, so it would normally go first; it yields here even though
+        // it queued first
+        let minor = approach(DOWN, FIRST, false);
+        let major = approach(LEFT, SECOND, true);
+        assert!(!resolver.has_priority_over(&minor, &major));
+        assert!(resolver.has_priority_over(&major, &minor));
+
+        // Coming from the south, the major car is on the minor car's left as well
+        let minor = approach(UP, FIRST, false);
+        let major = approach(RIGHT, SECOND, true);
+        assert!(!resolver.has_priority_over(&minor, &major));
+        assert!(resolver.has_priority_over(&major, &minor));
+
+        // Two priority approaches fall back to the right-hand rule: north yields to west,
+        // which is on its right
+        let from_north = approach(DOWN, FIRST, true);
+        let from_west = approach(RIGHT, SECOND, true);
+        assert!(!resolver.has_priority_over(&from_north, &from_west));
+        assert!(resolver.has_priority_over(&from_west, &from_north));
+    }
+
+    #[test]
+    fn test_stop_line_tie_break_favors_first_at_line() {
+        // Opposing straight movements tie on side and path; the vehicle that entered the
+        // waiting zone later reached the stop line first
+        let mut early_at_line = Approach::new(TurnType::Straight, DOWN, SECOND, 0.0);
+        early_at_line.arrived_at_line = Some(3.0);
+        let mut late_at_line = Approach::new(TurnType::Straight, UP, FIRST, 0.0);
+        late_at_line.arrived_at_line = Some(4.5);
+
+        let by_arrival = YieldResolver::default();
+        assert!(!by_arrival.has_priority_over(&early_at_line, &late_at_line));
+
+        let by_stop_line = YieldResolver::RightOfWay(SidePriority {
+            tie_break: TieBreak::StopLine,
+            ..SidePriority::RIGHT_HAND
+        });
+        assert!(by_stop_line.has_priority_over(&early_at_line, &late_at_line));
+        assert!(!by_stop_line.has_priority_over(&late_at_line, &early_at_line));
+
+        // Not at the line yet: fall back to arrival order
+        late_at_line.arrived_at_line = None;
+        assert!(!by_stop_line.has_priority_over(&early_at_line, &late_at_line));
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TurnType {
     Straight,
     Right(f32),
@@ -397,10 +1028,11 @@ pub enum TurnType {
 }
 
 impl TurnType {
+    /// Signed turn sharpness: positive for left turns, negative for right turns
     pub fn cross(&self) -> f32 {
         match self {
             TurnType::Straight => 0.0,
-            TurnType::Right(cross) => *cross,
+            TurnType::Right(cross) => -cross.abs(),
             TurnType::Left(cross) => *cross,
             TurnType::RoundaboutCircle => 0.0,
             TurnType::RoundaboutEntry => -0.7,
@@ -0,0 +1,335 @@
+//! Discrete, invertible edits to a road network for editor undo/redo.
+//!
+//! Edits operate on the authored graph: apply them before [`Road::finalize`],
+//! and finalize again once editing is done.
+
+use glam::Vec3;
+
+use crate::{Id, Node, Road, Segment};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoadEdit {
+    AddNode {
+        position: Vec3,
+        is_spawn: bool,
+        is_despawn: bool,
+    },
+    /// Remove a node that no segment references anymore
+    RemoveNode(Id<Node>),
+    AddSegment {
+        from: Id<Node>,
+        to: Id<Node>,
+        speed_limit: f32,
+    },
+    RemoveSegment(Id<Segment>),
+    /// Put a removed node back under its old id, as the inverse of `RemoveNode`
+    RestoreNode {
+        id: Id<Node>,
+        node: Box<Node>,
+    },
+    /// Put a removed segment back under its old id and at its old place in its nodes'
+    /// segment lists, as the inverse of `RemoveSegment`
+    RestoreSegment {
+        id: Id<Segment>,
+        segment: Box<Segment>,
+        outgoing_index: usize,
+        incoming_index: usize,
+    },
+    MoveNode {
+        node: Id<Node>,
+        position: Vec3,
+    },
+    SetSpeedLimit {
+        segment: Id<Segment>,
+        speed_limit: f32,
+    },
+}
+
+impl Road {
+    /// Apply an edit and return the edit that reverts it.
+    ///
+    /// Removed items are restored under their old ids, so earlier inverses on an undo stack
+    /// stay valid. Panics on `RemoveNode` if segments still reference the node; undoing
+    /// edits in reverse order always removes those segments first.
+    pub fn apply(&mut self, edit: RoadEdit) -> RoadEdit {
+        self.mark_changed();
+        let inverse = match edit {
+            RoadEdit::AddNode {
+                position,
+                is_spawn,
+                is_despawn,
+            } => {
+                let id = self.add_node(position);
+                let node = self.nodes.get_mut(&id);
+                node.is_spawn = is_spawn;
+                node.is_despawn = is_despawn;
+                RoadEdit::RemoveNode(id)
+            }
+            RoadEdit::RemoveNode(id) => {
+                let node = self.nodes.get(&id);
+                assert!(
+                    node.incoming.is_empty() && node.outgoing.is_empty(),
+                    "cannot remove node {id} while segments still reference it"
+                );
+                let node = self.nodes.remove(&id).unwrap();
+                RoadEdit::RestoreNode {
+                    id,
+                    node: Box::new(node),
+                }
+            }
+            RoadEdit::AddSegment {
+                from,
+                to,
+                speed_limit,
+            } => RoadEdit::RemoveSegment(self.add_segment(from, to, speed_limit)),
+            RoadEdit::RemoveSegment(id) => {
+                let segment = self.segments.remove(&id).unwrap();
+                let outgoing = &mut self.nodes.get_mut(&segment.from).outgoing;
+                let outgoing_index = outgoing.iter().position(|&s| s == id).unwrap_or(0);
+                outgoing.retain(|&s| s != id);
+                let incoming = &mut self.nodes.get_mut(&segment.to).incoming;
+                let incoming_index = incoming.iter().position(|&s| s == id).unwrap_or(0);
+                incoming.retain(|&s| s != id);
+                RoadEdit::RestoreSegment {
+                    id,
+                    segment: Box::new(segment),
+                    outgoing_index,
+                    incoming_index,
+                }
+            }
+            RoadEdit::RestoreNode { id, node } => {
+                self.nodes.restore(&id, *node);
+                RoadEdit::RemoveNode(id)
+            }
+            RoadEdit::RestoreSegment {
+                id,
+                segment,
+                outgoing_index,
+                incoming_index,
+            } => {
+                let outgoing = &mut self.nodes.get_mut(&segment.from).outgoing;
+                outgoing.insert(outgoing_index.min(outgoing.len()), id);
+                let incoming = &mut self.nodes.get_mut(&segment.to).incoming;
+                incoming.insert(incoming_index.min(incoming.len()), id);
+                self.segments.restore(&id, *segment);
+                RoadEdit::RemoveSegment(id)
+            }
+            RoadEdit::MoveNode { node, position } => {
+                let previous = self.nodes.get(&node).position;
+                self.move_node(node, position);
+
+                RoadEdit::MoveNode {
+                    node,
+                    position: previous,
+                }
+            }
+            RoadEdit::SetSpeedLimit {
+                segment,
+                speed_limit,
+            } => {
+                let previous = self.segments.get(&segment).speed_limit;
+                self.segments.get_mut(&segment).speed_limit = speed_limit;
+                RoadEdit::SetSpeedLimit {
+                    segment,
+                    speed_limit: previous,
+                }
+            }
+        };
+
+        if self.spatial_index.is_some() {
+            self.rebuild_spatial_index();
+        }
+
+        inverse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Id-independent description of the graph: node data and segment endpoints by position
+    fn snapshot(road: &Road) -> (Vec<String>, Vec<String>) {
+        let mut nodes: Vec<_> = road
+            .nodes
+            .iter()
+            .map(|n| {
+                format!(
+                    "{:?} in={} out={} spawn={} despawn={}",
+                    n.position,
+                    n.incoming.len(),
+                    n.outgoing.len(),
+                    n.is_spawn,
+                    n.is_despawn
+                )
+            })
+            .collect();
+        let mut segments: Vec<_> = road
+            .segments
+            .iter()
+            .map(|s| {
+                format!(
+                    "{:?} -> {:?} limit={} length={}",
+                    road.nodes.get(&s.from).position,
+                    road.nodes.get(&s.to).position,
+                    s.speed_limit,
+                    s.length
+                )
+            })
+            .collect();
+        nodes.sort();
+        segments.sort();
+        (nodes, segments)
+    }
+
+    #[test]
+    fn test_apply_then_inverse_restores_graph() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(100.0, 100.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        road.add_segment(b, c, 13.9);
+
+        let original = snapshot(&road);
+
+        let edits = vec![
+            RoadEdit::AddNode {
+                position: Vec3::new(-50.0, 0.0, 0.0),
+                is_spawn: true,
+                is_despawn: false,
+            },
+            RoadEdit::AddSegment {
+                from: c,
+                to: a,
+                speed_limit: 8.3,
+            },
+            RoadEdit::RemoveSegment(ab),
+            RoadEdit::MoveNode {
+                node: b,
+                position: Vec3::new(120.0, 10.0, 0.0),
+            },
+            RoadEdit::SetSpeedLimit {
+                segment: road.nodes.get(&c).incoming[0],
+                speed_limit: 5.5,
+            },
+        ];
+
+        for edit in edits {
+            let before = snapshot(&road);
+            let inverse = road.apply(edit);
+            assert_ne!(snapshot(&road), before);
+            road.apply(inverse);
+            assert_eq!(snapshot(&road), before);
+        }
+
+        assert_eq!(snapshot(&road), original);
+    }
+
+    #[test]
+    fn test_undo_stack_in_reverse_order() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let original = snapshot(&road);
+
+        let mut undo = vec![];
+        let add_node = road.apply(RoadEdit::AddNode {
+            position: Vec3::new(10.0, 0.0, 0.0),
+            is_spawn: false,
+            is_despawn: false,
+        });
+        let RoadEdit::RemoveNode(b) = add_node else {
+            panic!("expected RemoveNode inverse, got {add_node:?}");
+        };
+        undo.push(add_node);
+        undo.push(road.apply(RoadEdit::AddSegment {
+            from: a,
+            to: b,
+            speed_limit: 13.9,
+        }));
+
+        while let Some(edit) = undo.pop() {
+            road.apply(edit);
+        }
+
+        assert_eq!(snapshot(&road), original);
+    }
+
+    /// Every node and segment with its id, for exact comparison
+    #[allow(clippy::type_complexity)]
+    fn state(road: &Road) -> (Vec<(Id<Node>, Node)>, Vec<(Id<Segment>, Segment)>) {
+        (
+            road.nodes
+                .iter_with_ids()
+                .map(|(id, node)| (id, node.clone()))
+                .collect(),
+            road.segments
+                .iter_with_ids()
+                .map(|(id, segment)| (id, segment.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_undo_and_redo_across_removals_keep_ids_and_data() {
+        use crate::driver::{TurnType, VehicleClass, VehicleClasses, YieldResolver};
+        use crate::SegmentGeometry;
+
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let lone = road.add_node(Vec3::new(50.0, 80.0, 0.0));
+        road.nodes.get_mut(&lone).yield_resolver = Some(YieldResolver::AllWayStop);
+        road.nodes.get_mut(&lone).roundabout_radius = Some(12.0);
+        let ab = road.add_segment(a, b, 13.9);
+        road.add_segment(b, a, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+        let segment = road.segments.get_mut(&bc);
+        segment.geometry = SegmentGeometry::Bezier {
+            control1: Vec3::new(130.0, 20.0, 0.0),
+            control2: Vec3::new(170.0, -20.0, 0.0),
+        };
+        segment.turn_type = TurnType::Left(0.3);
+        segment.allowed_classes = VehicleClasses::ALL.without(VehicleClass::Truck);
+        segment.lanes = 2;
+        segment.toll = 1.5;
+        segment.is_priority_road = true;
+        let original = state(&road);
+
+        // Later edits refer to items that earlier ones removed, once those are undone
+        let edits = vec![
+            RoadEdit::SetSpeedLimit {
+                segment: bc,
+                speed_limit: 8.0,
+            },
+            RoadEdit::RemoveSegment(bc),
+            RoadEdit::RemoveNode(lone),
+            RoadEdit::MoveNode {
+                node: b,
+                position: Vec3::new(100.0, 30.0, 0.0),
+            },
+            RoadEdit::AddSegment {
+                from: a,
+                to: c,
+                speed_limit: 20.0,
+            },
+            RoadEdit::RemoveSegment(ab),
+        ];
+        let mut undo: Vec<_> = edits.into_iter().map(|edit| road.apply(edit)).collect();
+        let edited = state(&road);
+
+        for _ in 0..2 {
+            let mut redo = vec![];
+            while let Some(edit) = undo.pop() {
+                redo.push(road.apply(edit));
+            }
+            assert_eq!(state(&road), original);
+
+            while let Some(edit) = redo.pop() {
+                undo.push(road.apply(edit));
+            }
+            assert_eq!(state(&road), edited);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Running the simulation without a window, e.g. for batch experiments, CLIs and CI.
+
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_time::TimeUpdateStrategy;
+use glam::Vec3;
+
+use crate::{driver::Vehicle, Id, Road, Segment, SimulationPlugin, TrafficMetrics};
+
+/// State of one vehicle at the end of a headless run
+#[derive(Clone, Debug, PartialEq)]
+pub struct VehicleSnapshot {
+    pub entity: Entity,
+    pub segment: Id<Segment>,
+    pub progress: f32,
+    pub speed: f32,
+    pub position: Vec3,
+}
+
+/// Outcome of a headless run
+#[derive(Debug)]
+pub struct Snapshot {
+    pub vehicles: Vec<VehicleSnapshot>,
+    pub metrics: TrafficMetrics,
+}
+
+/// App with only the simulation and a manually advanced clock, driving `road`
+pub fn app(road: Road) -> App {
+    let mut app = App::new();
+    app.add_plugins(SimulationPlugin::default());
+    app.insert_resource(road);
+    // The clock starts on the first update, so later updates each advance it fully
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    app.update();
+    app
+}
+
+/// Run `ticks` frames of `dt` seconds each; the simulation steps at its fixed timestep
+/// as often as the accumulated time allows
+pub fn advance(app: &mut App, ticks: u64, dt: f32) {
+    let frame = Duration::from_secs_f32(dt.max(0.0));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+/// Vehicles ordered by entity and the metrics collected so far
+pub fn snapshot(app: &mut App) -> Snapshot {
+    let world = app.world_mut();
+    let mut query = world.query::<(Entity, &Vehicle)>();
+    let road = world.resource::<Road>();
+    let mut vehicles: Vec<_> = query
+        .iter(world)
+        .map(|(entity, vehicle)| VehicleSnapshot {
+            entity,
+            segment: vehicle.segment,
+            progress: vehicle.progress,
+            speed: vehicle.speed,
+            position: road.lane_position_on(vehicle.segment, vehicle.progress, vehicle.lane),
+        })
+        .collect();
+    vehicles.sort_by_key(|vehicle| vehicle.entity.index());
+
+    Snapshot {
+        vehicles,
+        metrics: world
+            .get_resource::<TrafficMetrics>()
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+/// Simulate `road` for `ticks` steps of `dt` seconds and return the final state
+pub fn run(road: Road, ticks: u64, dt: f32) -> Snapshot {
+    let mut app = app(road);
+    advance(&mut app, ticks, dt);
+    snapshot(&mut app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::YieldResolver;
+    use bevy_time::{Fixed, Time};
+
+    /// Run the grid for `steps` fixed steps, with frame lengths cycling through `quarters`
+    /// (in quarter timesteps)
+    fn run_with_frames(quarters: &[u32], steps: u32) -> Snapshot {
+        let mut app = app(Road::generate_grid(
+            2,
+            2,
+            100.0,
+            13.9,
+            YieldResolver::default(),
+        ));
+        let timestep = app.world().resource::<Time<Fixed>>().timestep();
+
+        let (mut elapsed, mut previous_end) = (0, Duration::ZERO);
+        for &length in quarters.iter().cycle() {
+            elapsed = (elapsed + length).min(steps * 4);
+            let end = timestep * elapsed / 4;
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(end - previous_end));
+            app.update();
+            previous_end = end;
+            if elapsed == steps * 4 {
+                break;
+            }
+        }
+        snapshot(&mut app)
+    }
+
+    #[test]
+    fn test_long_frame_runs_a_capped_number_of_steps() {
+        let mut app = app(Road::generate_grid(
+            1,
+            1,
+            100.0,
+            13.9,
+            YieldResolver::default(),
+        ));
+        advance(&mut app, 1, 10.0);
+
+        let steps = app.world().resource::<crate::SimulationStats>().tick;
+        let max_substeps = SimulationPlugin::default().max_substeps as u64;
+        assert!((1..=max_substeps).contains(&steps), "{steps} steps");
+    }
+
+    #[test]
+    fn test_outcome_does_not_depend_on_frame_rate() {
+        let steady = run_with_frames(&[4], 3000);
+        let uneven = run_with_frames(&[1, 9, 0, 3, 12, 2, 5], 3000);
+
+        assert!(steady.metrics.arrived > 0);
+        assert_eq!(steady.vehicles, uneven.vehicles);
+        assert_eq!(steady.metrics.spawned, uneven.metrics.spawned);
+        assert_eq!(steady.metrics.arrived, uneven.metrics.arrived);
+        assert_eq!(
+            steady.metrics.average_travel_time(),
+            uneven.metrics.average_travel_time()
+        );
+    }
+
+    #[test]
+    fn test_grid_runs_headless() {
+        let road = Road::generate_grid(2, 2, 100.0, 13.9, YieldResolver::default());
+        let snapshot = run(road, 1000, 0.05);
+
+        assert!(snapshot.metrics.spawned > 0);
+        assert!(snapshot.metrics.arrived > 0);
+        assert!(!snapshot.vehicles.is_empty());
+        assert!(snapshot.vehicles.iter().any(|vehicle| vehicle.speed > 0.0));
+    }
+
+    #[test]
+    fn test_snapshots_leave_metrics_running() {
+        let mut app = app(Road::generate_grid(
+            2,
+            2,
+            100.0,
+            13.9,
+            YieldResolver::default(),
+        ));
+        advance(&mut app, 600, 0.05);
+        let first = snapshot(&mut app).metrics;
+        advance(&mut app, 600, 0.05);
+        let second = snapshot(&mut app).metrics;
+
+        assert!(first.spawned > 0);
+        assert!(second.spawned > first.spawned);
+        assert!(second.arrived >= first.arrived);
+        assert!((second.elapsed - 2.0 * first.elapsed).abs() < 0.1);
+    }
+}
@@ -1,11 +1,28 @@
+use std::time::Duration;
+
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
+use bevy_time::{Fixed, Time, TimePlugin, TimeSystems, Virtual};
 
 mod arena;
+mod detector;
 pub mod driver;
+mod edit;
+pub mod headless;
 pub mod prelude;
+mod rng;
 mod road;
+#[cfg(feature = "serde")]
+mod scenario;
+#[cfg(feature = "serde")]
+mod signal_timing;
+mod spatial;
 mod spawner;
+mod stats;
+mod telemetry;
+mod time_scale;
+mod validate;
+mod viewer;
 
 /// Log to console (works in both native and WASM)
 #[macro_export]
@@ -19,31 +36,327 @@ macro_rules! log {
 }
 
 pub use arena::*;
+pub use detector::*;
+pub use edit::*;
+pub use rng::*;
 pub use road::*;
+#[cfg(feature = "serde")]
+pub use scenario::*;
+#[cfg(feature = "serde")]
+pub use signal_timing::*;
+pub use spatial::*;
 pub use spawner::*;
+pub use stats::*;
+pub use telemetry::*;
+pub use time_scale::*;
+pub use validate::*;
+pub use viewer::*;
 
 use crate::driver::{
-    apply_gap_acceptance, apply_idm, move_and_despawn_vehicles, spawn_vehicles, update_blinkers,
-    update_occupancy, SegmentOccupancy, Vehicle,
+    apply_gap_acceptance, apply_idm, change_lanes, detect_collisions, move_and_despawn_vehicles,
+    reroute_congested_vehicles, restore_route_consistency, spawn_vehicles, update_blinkers,
+    update_occupancy, CongestionRerouter, IntersectionCleared, RouteCache, RoutingConfig,
+    RoutingMode, SegmentOccupancy, VehicleArrived, VehicleCollision, VehicleDespawned, VehicleMix,
+    VehicleSpawned, YieldingConfig,
 };
 
-pub struct SimulationPlugin;
+/// Default seconds per simulation step
+pub const DEFAULT_TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Runs the simulation in `FixedUpdate`, so vehicles move in equal steps however unevenly
+/// frames arrive
+pub struct SimulationPlugin {
+    /// Seconds per simulation step
+    pub timestep: f32,
+    /// Most steps run at normal speed to catch up after a long frame; time beyond that is
+    /// dropped instead of making the next frame longer still. Fast-forward raises the cap in
+    /// proportion, so at 8× a frame may run up to eight times as many steps.
+    pub max_substeps: u32,
+}
+
+impl Default for SimulationPlugin {
+    fn default() -> Self {
+        Self {
+            timestep: DEFAULT_TIMESTEP,
+            max_substeps: 8,
+        }
+    }
+}
 
 impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<TimePlugin>() {
+            app.add_plugins(TimePlugin);
+        }
+        let timestep = self.timestep.max(0.001);
+        app.insert_resource(Time::<Fixed>::from_seconds(timestep as f64));
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs_f32(
+                timestep * self.max_substeps.max(1) as f32,
+            ));
+
         app.init_resource::<SegmentOccupancy>();
+        app.init_resource::<DetectorStates>();
+        app.init_resource::<SimulationStats>();
+        app.init_resource::<SpawnSpacing>();
+        app.init_resource::<SpawnConfig>();
+        app.init_resource::<DemandProfile>();
+        app.init_resource::<SimRng>();
+        app.init_resource::<RoutingMode>();
+        app.init_resource::<RoutingConfig>();
+        app.init_resource::<YieldingConfig>();
+        app.init_resource::<VehicleMix>();
+        app.init_resource::<SimulationSpeed>();
+        app.add_systems(First, apply_simulation_speed.before(TimeSystems));
+        app.init_resource::<CongestionRerouter>();
+        app.init_resource::<RouteCache>();
+        app.add_message::<VehicleSpawned>();
+        app.add_message::<VehicleArrived>();
+        app.add_message::<VehicleDespawned>();
+        app.add_message::<IntersectionCleared>();
+        app.add_message::<VehicleCollision>();
+        app.init_resource::<TrafficMetrics>();
+        app.init_resource::<SpatialHash>();
 
         app.add_systems(
-            Update,
+            FixedUpdate,
             (
+                restore_route_consistency,
                 spawn_vehicles,
+                run_vehicle_spawners,
                 update_occupancy,
+                reroute_congested_vehicles,
+                change_lanes,
                 apply_gap_acceptance,
                 apply_idm,
                 update_blinkers,
                 move_and_despawn_vehicles,
+                update_spatial_hash,
+                detect_collisions,
+                update_detectors,
+                update_stats,
+                update_route_load,
+                collect_metrics,
+                write_telemetry,
             )
                 .chain(),
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{DespawnReason, Vehicle, YieldResolver};
+    use glam::Vec3;
+
+    #[test]
+    fn test_plugin_runs_gap_acceptance_before_idm() {
+        let mut app = headless::app(Road::cross_intersection(
+            Vec3::ZERO,
+            80.0,
+            13.9,
+            YieldResolver::default(),
+        ));
+
+        let mut waited = false;
+        for _ in 0..1200 {
+            headless::advance(&mut app, 1, 0.05);
+
+            let world = app.world_mut();
+            waited |= world
+                .query::<&Vehicle>()
+                .iter(world)
+                .any(|vehicle| vehicle.gap.waiting_time.is_some());
+            if waited {
+                break;
+            }
+        }
+        assert!(waited, "no vehicle ever waited at the junction");
+    }
+
+    /// Positions of every vehicle after `ticks` steps of the grid seeded with `seed`
+    fn grid_snapshot(seed: u64, ticks: u64) -> Vec<(Id<Segment>, f32, f32)> {
+        let mut app = headless::app(Road::generate_grid(
+            2,
+            2,
+            100.0,
+            13.9,
+            YieldResolver::default(),
+        ));
+        app.insert_resource(SimRng::seeded(seed));
+        headless::advance(&mut app, ticks, 0.05);
+
+        let mut vehicles: Vec<_> = headless::snapshot(&mut app)
+            .vehicles
+            .into_iter()
+            .map(|vehicle| (vehicle.segment, vehicle.progress, vehicle.speed))
+            .collect();
+        vehicles.sort_by(|a, b| (a.0.id, a.1).partial_cmp(&(b.0.id, b.1)).unwrap());
+        vehicles
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_the_run() {
+        let first = grid_snapshot(42, 600);
+        assert!(!first.is_empty());
+        assert_eq!(first, grid_snapshot(42, 600));
+        assert_ne!(first, grid_snapshot(43, 600));
+    }
+
+    #[test]
+    fn test_lifecycle_messages_report_arrivals() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        road.add_segment(a, b, 13.9);
+        road.add_segment(b, c, 13.9);
+        let mut app = headless::app(road);
+
+        let mut spawned = app
+            .world()
+            .resource::<Messages<VehicleSpawned>>()
+            .get_cursor();
+        let mut arrived = app
+            .world()
+            .resource::<Messages<VehicleArrived>>()
+            .get_cursor();
+        let mut despawned = app
+            .world()
+            .resource::<Messages<VehicleDespawned>>()
+            .get_cursor();
+        let (mut spawns, mut arrivals, mut departures) = (vec![], vec![], vec![]);
+        for _ in 0..1200 {
+            headless::advance(&mut app, 1, 0.05);
+
+            let world = app.world();
+            spawns.extend(spawned.read(world.resource()).copied());
+            arrivals.extend(arrived.read(world.resource()).copied());
+            departures.extend(despawned.read(world.resource()).copied());
+        }
+
+        assert!(!arrivals.is_empty());
+        assert!(spawns.len() >= arrivals.len());
+        assert!(spawns.iter().all(|spawn| spawn.origin == a));
+        for arrival in &arrivals {
+            assert_eq!(arrival.destination, c);
+            // 200 m at no more than 20% over the limit, without getting stuck
+            assert!(
+                (12.0..60.0).contains(&arrival.travel_time),
+                "{}",
+                arrival.travel_time
+            );
+            assert!(spawns.iter().any(|spawn| spawn.entity == arrival.entity));
+            assert!(departures.contains(&VehicleDespawned {
+                entity: arrival.entity,
+                reason: DespawnReason::Arrived,
+            }));
+        }
+    }
+
+    #[test]
+    fn test_metrics_grow_consistently_on_grid() {
+        let mut app = headless::app(Road::generate_grid(
+            2,
+            2,
+            100.0,
+            13.9,
+            YieldResolver::default(),
+        ));
+
+        let mut previous = (0, 0, 0);
+        for _ in 0..60 {
+            headless::advance(&mut app, 20, 0.05);
+
+            let metrics = app.world().resource::<TrafficMetrics>();
+            let cleared: usize = metrics.intersection_cleared.values().sum();
+            let current = (metrics.spawned, metrics.arrived, cleared);
+            assert!(current.0 >= previous.0 && current.1 >= previous.1 && current.2 >= previous.2);
+            assert!(metrics.arrived <= metrics.spawned);
+            previous = current;
+        }
+
+        let metrics = app.world().resource::<TrafficMetrics>();
+        assert!(metrics.arrived > 0);
+        assert!(metrics.intersection_cleared.len() > 1);
+        assert!((metrics.elapsed - 60.0).abs() < 0.05);
+        assert!(metrics.throughput_per_minute() > 0.0);
+        assert!(metrics.average_travel_time().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_signals_and_spawn_headway_follow_simulation_speed() {
+        // Four real seconds at `speed`: the signal time and spawn count that results
+        let run = |speed: f32| {
+            let mut road = Road::default();
+            let a = road.add_node(Vec3::ZERO);
+            let b = road.add_despawn_node(Vec3::new(500.0, 0.0, 0.0));
+            let segment = road.add_segment(a, b, 13.9);
+
+            let mut app = headless::app(road);
+            app.insert_resource(SimulationSpeed(speed));
+            // Fast enough that the 1.5 s spawn headway is the only limit
+            app.world_mut()
+                .spawn(VehicleSpawner::new(segment, 10.0).with_speed(13.9));
+            headless::advance(&mut app, 80, 0.05);
+
+            let world = app.world_mut();
+            let now = world.resource::<Time<Fixed>>().elapsed_secs();
+            let spawned = world.query::<&Vehicle>().iter(world).count();
+            (now, spawned)
+        };
+        let light = YieldResolver::TrafficLight {
+            phase_duration: 3.0,
+            offset: 0.0,
+            east_west_duration: None,
+        };
+
+        // Spawns at 0.1 s and every 1.5 s after
+        let (now, spawned) = run(1.0);
+        assert!((now - 4.0).abs() < 0.02, "{now}");
+        assert_eq!(spawned, 3);
+        assert!(!light.current_green(now, Vec3::Y));
+
+        let (now, spawned) = run(2.0);
+        assert!((now - 8.0).abs() < 0.02, "{now}");
+        assert_eq!(spawned, 6);
+        assert!(light.current_green(now, Vec3::Y));
+
+        assert_eq!(run(0.0), (0.0, 0));
+    }
+
+    #[derive(Resource, Default)]
+    struct StepSizes(Vec<f32>);
+
+    fn record_step_size(time: Res<Time>, mut steps: ResMut<StepSizes>) {
+        steps.0.push(time.delta_secs());
+    }
+
+    #[test]
+    fn test_fast_forward_runs_more_steps_of_the_same_size() {
+        // Sizes of the steps run during one real second at `speed`
+        let steps = |speed: f32| {
+            let mut app = headless::app(Road::default());
+            app.insert_resource(SimulationSpeed(speed));
+            app.init_resource::<StepSizes>();
+            app.add_systems(FixedUpdate, record_step_size);
+            headless::advance(&mut app, 60, DEFAULT_TIMESTEP);
+            app.world_mut().remove_resource::<StepSizes>().unwrap().0
+        };
+
+        let normal = steps(1.0);
+        assert!(normal.len().abs_diff(60) <= 1, "{}", normal.len());
+        for speed in [2, 8] {
+            let fast = steps(speed as f32);
+            assert!(
+                fast.len().abs_diff(normal.len() * speed) <= 1,
+                "{}",
+                fast.len()
+            );
+            assert!(fast.iter().all(|&dt| dt == normal[0]));
+        }
+        assert!((normal[0] - DEFAULT_TIMESTEP).abs() < 1e-6);
+    }
+}
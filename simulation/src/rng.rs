@@ -0,0 +1,80 @@
+use bevy_ecs::prelude::*;
+use rand::{rngs::StdRng, seq::IndexedRandom, Rng, SeedableRng};
+
+/// Seed used when no explicit seed is configured, so runs are reproducible by default
+pub const DEFAULT_SEED: u64 = 0x5eed;
+
+/// Random source for stochastic driver behavior; seeding it makes a run reproducible
+#[derive(Resource)]
+pub struct SimRng(StdRng);
+
+impl SimRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    /// Fresh seed for [`SimRng::stream`]
+    pub fn next_seed(&mut self) -> u64 {
+        self.0.random()
+    }
+
+    /// Generator for one `stream` of `seed`, e.g. one per vehicle, so work split across
+    /// threads draws the same numbers whatever order it runs in
+    pub fn stream(seed: u64, stream: u64) -> Self {
+        Self::seeded(seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// Uniform sample in [0, 1)
+    pub fn uniform(&mut self) -> f32 {
+        self.0.random()
+    }
+
+    /// Uniformly chosen element, `None` if `items` is empty
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        items.choose(&mut self.0)
+    }
+
+    /// Element chosen with probability proportional to `weight`, `None` if no element has a
+    /// positive weight
+    pub fn choose_weighted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f32,
+    ) -> Option<&'a T> {
+        let total: f32 = items.iter().map(|item| weight(item).max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = self.uniform() * total;
+        let mut last = None;
+        for item in items.iter().filter(|item| weight(item) > 0.0) {
+            pick -= weight(item);
+            if pick < 0.0 {
+                return Some(item);
+            }
+            last = Some(item);
+        }
+        // Rounding left a sliver of `pick`
+        last
+    }
+
+    /// Standard normal sample (mean 0, standard deviation 1), via Box-Muller
+    pub fn gaussian(&mut self) -> f32 {
+        // 1 - u keeps the logarithm's argument in (0, 1]
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+    }
+
+    /// Exponentially distributed sample with the given mean, via inverse transform
+    pub fn exponential(&mut self, mean: f32) -> f32 {
+        -mean * (1.0 - self.uniform()).ln()
+    }
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        Self::seeded(DEFAULT_SEED)
+    }
+}
@@ -10,14 +10,18 @@
 //! - 80 km/h ≈ 22.2 m/s (highway)
 //! - 120 km/h ≈ 33.3 m/s (motorway)
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use bevy_ecs::prelude::*;
 use glam::Vec3;
 
 use crate::{
-    driver::{TurnType, YieldResolver},
-    Arena, Id,
+    driver::{TrafficControl, TurnType, VehicleClasses, YieldResolver},
+    spatial::{closest_of, segment_bounds, DEFAULT_CELL_SIZE},
+    Arena, Detector, Id, SegmentGrid,
 };
 
 /// Speed limit constants in m/s
@@ -32,15 +36,80 @@ pub mod speed {
     pub const HIGHWAY: f32 = 22.2;
 }
 
+/// Width of one lane in meters
+pub const LANE_WIDTH: f32 = 3.5;
+
+/// Options for [`Road::finalize_with`]
+#[derive(Clone, Debug)]
+pub struct FinalizeConfig {
+    /// Speed limit (m/s) of generated left-turn segments
+    pub left_turn_speed: f32,
+    /// Speed limit (m/s) of generated right-turn segments
+    pub right_turn_speed: f32,
+    /// Speed limit (m/s) of generated straight-through segments
+    pub through_speed: f32,
+}
+
+impl Default for FinalizeConfig {
+    fn default() -> Self {
+        Self {
+            left_turn_speed: 4.2,
+            right_turn_speed: speed::SLOW,
+            through_speed: speed::RESIDENTIAL,
+        }
+    }
+}
+
+impl FinalizeConfig {
+    /// Speed limit for a generated intersection movement
+    pub fn turn_speed(&self, turn_type: TurnType) -> f32 {
+        match turn_type {
+            TurnType::Left(_) => self.left_turn_speed,
+            TurnType::Right(_) => self.right_turn_speed,
+            TurnType::Straight => self.through_speed,
+            _ => speed::SLOW,
+        }
+    }
+}
+
 #[derive(Resource, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Road {
     pub nodes: Arena<Node>,
     pub segments: Arena<Segment>,
     pub intersections: Arena<Intersection>,
+    pub detectors: Arena<Detector>,
+    /// Optional acceleration structure for spatial queries, see [`Road::rebuild_spatial_index`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub spatial_index: Option<SegmentGrid>,
+    /// Bumped by every method that changes the graph, see [`Road::version`]
+    #[cfg_attr(feature = "serde", serde(skip, default = "fresh_version"))]
+    version: u64,
+}
+
+/// Source of road versions, shared by all roads so a replaced road never reuses the
+/// version of the one it replaces
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+fn fresh_version() -> u64 {
+    NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
 }
 
 impl Road {
+    /// Changes whenever nodes or segments are added, moved, edited or finalized, so derived
+    /// data such as [`crate::driver::RouteCache`] knows to refresh. Versions are unique
+    /// across roads. Code changing the public fields directly should call
+    /// [`Road::mark_changed`].
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn mark_changed(&mut self) {
+        self.version = fresh_version();
+    }
+
     pub fn add_node(&mut self, position: Vec3) -> Id<Node> {
+        self.mark_changed();
         self.nodes.alloc(Node {
             position,
             incoming: vec![],
@@ -48,6 +117,9 @@ impl Road {
             is_spawn: false,
             is_despawn: false,
             yield_resolver: None,
+            spawn_weight: 1.0,
+            name: None,
+            roundabout_radius: None,
         })
     }
 
@@ -56,51 +128,34 @@ impl Road {
         position: Vec3,
         yield_resolver: YieldResolver,
     ) -> Id<Node> {
-        self.nodes.alloc(Node {
-            position,
-            incoming: vec![],
-            outgoing: vec![],
-            is_spawn: false,
-            is_despawn: false,
-            yield_resolver: Some(yield_resolver),
-        })
+        let id = self.add_node(position);
+        self.nodes.get_mut(&id).yield_resolver = Some(yield_resolver);
+        id
     }
 
     pub fn add_spawn_node(&mut self, position: Vec3) -> Id<Node> {
-        self.nodes.alloc(Node {
-            position,
-            incoming: vec![],
-            outgoing: vec![],
-            is_spawn: true,
-            is_despawn: false,
-            yield_resolver: None,
-        })
+        let id = self.add_node(position);
+        self.nodes.get_mut(&id).is_spawn = true;
+        id
     }
 
     pub fn add_despawn_node(&mut self, position: Vec3) -> Id<Node> {
-        self.nodes.alloc(Node {
-            position,
-            incoming: vec![],
-            outgoing: vec![],
-            is_spawn: false,
-            is_despawn: true,
-            yield_resolver: None,
-        })
+        let id = self.add_node(position);
+        self.nodes.get_mut(&id).is_despawn = true;
+        id
     }
 
     pub fn add_edge_node(&mut self, position: Vec3) -> Id<Node> {
-        self.nodes.alloc(Node {
-            position,
-            incoming: vec![],
-            outgoing: vec![],
-            is_spawn: true,
-            is_despawn: true,
-            yield_resolver: None,
-        })
+        let id = self.add_node(position);
+        let node = self.nodes.get_mut(&id);
+        node.is_spawn = true;
+        node.is_despawn = true;
+        id
     }
 
     /// Add a segment between two nodes, automatically wiring up incoming/outgoing
     pub fn add_segment(&mut self, from: Id<Node>, to: Id<Node>, speed_limit: f32) -> Id<Segment> {
+        self.mark_changed();
         let from_pos = self.nodes.get(&from).position;
         let to_pos = self.nodes.get(&to).position;
         let geometry = SegmentGeometry::Straight;
@@ -113,15 +168,296 @@ impl Road {
             geometry,
             length,
             turn_type: TurnType::Straight,
+            allowed_classes: VehicleClasses::ALL,
+            lanes: 1,
+            yield_required: false,
+            toll: 0.0,
+            name: None,
+            control: TrafficControl::None,
+            is_priority_road: false,
         });
 
         // Wire up the connections
         self.nodes.get_mut(&from).outgoing.push(segment_id);
         self.nodes.get_mut(&to).incoming.push(segment_id);
 
+        if let Some(mut grid) = self.spatial_index.take() {
+            grid.insert(self, segment_id);
+            self.spatial_index = Some(grid);
+        }
+
         segment_id
     }
 
+    /// Build (or rebuild) the spatial index used by [`Road::nearest_segment`] and region queries
+    pub fn rebuild_spatial_index(&mut self) {
+        self.spatial_index = Some(SegmentGrid::build(self, DEFAULT_CELL_SIZE));
+    }
+
+    /// Closest segment to a point, returning (segment, progress along it, distance)
+    pub fn nearest_segment(&self, point: Vec3) -> Option<(Id<Segment>, f32, f32)> {
+        match &self.spatial_index {
+            Some(grid) => grid.nearest(self, point),
+            None => closest_of(self, self.segments.iter_with_ids().map(|(id, _)| id), point),
+        }
+    }
+
+    /// Segments whose bounding boxes overlap the axis-aligned region (XY only)
+    pub fn segments_in_region(&self, min: Vec3, max: Vec3) -> Vec<Id<Segment>> {
+        let overlaps = |id: &Id<Segment>| {
+            let (seg_min, seg_max) = segment_bounds(self, *id);
+            seg_min.x <= max.x && seg_max.x >= min.x && seg_min.y <= max.y && seg_max.y >= min.y
+        };
+
+        match &self.spatial_index {
+            Some(grid) => grid
+                .query_region(min, max)
+                .into_iter()
+                .filter(overlaps)
+                .collect(),
+            None => self
+                .segments
+                .iter_with_ids()
+                .map(|(id, _)| id)
+                .filter(overlaps)
+                .collect(),
+        }
+    }
+
+    /// World position at a given progress (0.0 to 1.0) along a segment
+    pub fn position_on(&self, segment: Id<Segment>, progress: f32) -> Vec3 {
+        let segment = self.segments.get(&segment);
+        let from = self.nodes.get(&segment.from).position;
+        let to = self.nodes.get(&segment.to).position;
+        segment.geometry.position_at(from, to, progress)
+    }
+
+    /// World position at a given progress along `lane` of a segment, see
+    /// [`SegmentGeometry::position_in_lane`]
+    pub fn lane_position_on(&self, segment: Id<Segment>, progress: f32, lane: u8) -> Vec3 {
+        let segment = self.segments.get(&segment);
+        let from = self.nodes.get(&segment.from).position;
+        let to = self.nodes.get(&segment.to).position;
+        segment
+            .geometry
+            .position_in_lane(from, to, progress, lane, segment.lanes)
+    }
+
+    /// Whether a vehicle following `route` may drive in `lane` of the route's first segment:
+    /// on an intersection approach the lane has to serve the next turn
+    pub fn lane_serves_route(&self, route: &[Id<Segment>], lane: u8) -> bool {
+        let (Some(approach), Some(next)) = (route.first(), route.get(1)) else {
+            return true;
+        };
+        if self.segments.get(approach).lanes <= 1 {
+            return lane == 0;
+        }
+
+        match self
+            .intersections
+            .iter()
+            .find(|i| i.incoming.contains(next))
+        {
+            Some(intersection) => intersection
+                .lane_movements
+                .get(&(*approach, lane))
+                .is_some_and(|turns| turns.contains(next)),
+            None => true,
+        }
+    }
+
+    /// Axis-aligned bounding box (min, max) of all nodes and segment paths
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        const CURVE_SAMPLES: usize = 16;
+
+        let curve_points = self
+            .segments
+            .iter()
+            .filter(|segment| !matches!(segment.geometry, SegmentGeometry::Straight))
+            .flat_map(|segment| {
+                let from = self.nodes.get(&segment.from).position;
+                let to = self.nodes.get(&segment.to).position;
+                segment.geometry.sample(from, to, CURVE_SAMPLES)
+            });
+
+        self.nodes
+            .iter()
+            .map(|node| node.position)
+            .chain(curve_points)
+            .fold(None, |bounds, p| match bounds {
+                None => Some((p, p)),
+                Some((min, max)) => Some((p.min(min), p.max(max))),
+            })
+    }
+
+    /// Lane to use on the first segment of a route so the vehicle is in a lane serving the
+    /// next turn. Prefers the rightmost suitable lane; 0 when unrestricted.
+    pub fn lane_for_route(&self, route: &[Id<Segment>]) -> u8 {
+        let (Some(approach), Some(next)) = (route.first(), route.get(1)) else {
+            return 0;
+        };
+
+        let lanes = self.segments.get(approach).lanes;
+        self.intersections
+            .iter()
+            .find(|i| i.incoming.contains(next))
+            .and_then(|intersection| {
+                (0..lanes).find(|lane| {
+                    intersection
+                        .lane_movements
+                        .get(&(*approach, *lane))
+                        .is_some_and(|turns| turns.contains(next))
+                })
+            })
+            .unwrap_or(0)
+    }
+
+    /// Finalized road with a single junction at `center` and four two-way arms of
+    /// `arm_length` meters ending in spawn/despawn edge nodes (north, east, south, west)
+    pub fn cross_intersection(
+        center: Vec3,
+        arm_length: f32,
+        speed_limit: f32,
+        yield_resolver: YieldResolver,
+    ) -> Road {
+        let mut road = Road::default();
+        let junction = road.add_intersection_node(center, yield_resolver);
+        for direction in [Vec3::Y, Vec3::X, Vec3::NEG_Y, Vec3::NEG_X] {
+            let edge = road.add_edge_node(center + direction * arm_length);
+            road.add_bidirectional(edge, junction, speed_limit);
+        }
+        road.finalize();
+        road
+    }
+
+    /// Finalized `rows` x `cols` lattice of junctions `spacing` meters apart, starting at the
+    /// origin and growing along +X (columns) and +Y (rows). Every junction connects to its
+    /// neighbors and every perimeter junction to spawn/despawn edge nodes another `spacing`
+    /// out, so each junction has four arms. Zero rows or columns give an empty road.
+    pub fn generate_grid(
+        rows: usize,
+        cols: usize,
+        spacing: f32,
+        speed_limit: f32,
+        resolver: YieldResolver,
+    ) -> Road {
+        let mut road = Road::default();
+        if rows == 0 || cols == 0 {
+            return road;
+        }
+
+        let position = |row: isize, col: isize| Vec3::new(col as f32, row as f32, 0.0) * spacing;
+        let junctions: Vec<Vec<Id<Node>>> = (0..rows as isize)
+            .map(|row| {
+                (0..cols as isize)
+                    .map(|col| road.add_intersection_node(position(row, col), resolver))
+                    .collect()
+            })
+            .collect();
+
+        for row in 0..rows {
+            for col in 0..cols {
+                if col + 1 < cols {
+                    road.add_bidirectional(
+                        junctions[row][col],
+                        junctions[row][col + 1],
+                        speed_limit,
+                    );
+                }
+                if row + 1 < rows {
+                    road.add_bidirectional(
+                        junctions[row][col],
+                        junctions[row + 1][col],
+                        speed_limit,
+                    );
+                }
+            }
+        }
+
+        // Perimeter arms: west and east of every row, south and north of every column
+        let (last_row, last_col) = (rows as isize - 1, cols as isize - 1);
+        let mut arms = vec![];
+        for row in 0..rows as isize {
+            arms.push(((row, 0), position(row, -1)));
+            arms.push(((row, last_col), position(row, last_col + 1)));
+        }
+        for col in 0..cols as isize {
+            arms.push(((0, col), position(-1, col)));
+            arms.push(((last_row, col), position(last_row + 1, col)));
+        }
+        for ((row, col), edge) in arms {
+            let edge = road.add_edge_node(edge);
+            road.add_bidirectional(edge, junctions[row as usize][col as usize], speed_limit);
+        }
+
+        road.finalize();
+        road
+    }
+
+    /// Create the offset node replacing `old_id` for one group of straight segment ends.
+    /// Two ends meet at the miter point, so both segments stay `lane_offset` from their
+    /// center lines; other groups use the average perpendicular.
+    fn replace_with_offset_node(
+        &mut self,
+        old_id: Id<Node>,
+        group: &[(Id<Segment>, Vec3, bool)],
+        lane_offset: f32,
+        replacements: &mut HashMap<(Id<Node>, Id<Segment>), Id<Node>>,
+    ) {
+        let offset = match group {
+            [(_, a, ..), (_, b, ..)] if 1.0 + a.dot(*b) > 0.1 => (*a + *b) / (1.0 + a.dot(*b)),
+            _ => group
+                .iter()
+                .map(|(_, perpendicular, ..)| *perpendicular)
+                .sum::<Vec3>()
+                .normalize_or_zero(),
+        };
+        let departs = group.iter().any(|(.., arriving)| !arriving);
+        let arrives = group.iter().any(|(.., arriving)| *arriving);
+
+        // Source nodes can only be spawn points, destination nodes only despawn points
+        let old = self.nodes.get(&old_id);
+        let new_id = self.nodes.alloc(Node {
+            position: old.position + offset * lane_offset,
+            incoming: vec![],
+            outgoing: vec![],
+            is_spawn: old.is_spawn && departs,
+            is_despawn: old.is_despawn && arrives,
+            yield_resolver: None,
+            spawn_weight: old.spawn_weight,
+            name: old.name.clone(),
+            roundabout_radius: None,
+        });
+
+        // Clear old node's connections (no longer used for routing)
+        let old_node_mut = self.nodes.get_mut(&old_id);
+        for (segment, ..) in group {
+            old_node_mut.incoming.retain(|id| id != segment);
+            old_node_mut.outgoing.retain(|id| id != segment);
+            replacements.insert((old_id, *segment), new_id);
+        }
+    }
+
+    /// Roundabout at `center` with a two-way urban road to each of `arms`. [`Road::finalize`]
+    /// turns it into a one-way counter-clockwise ring of `radius` meters with a curved
+    /// on-ramp and off-ramp per arm. `resolver` decides who goes first where an on-ramp
+    /// merges into the ring; [`YieldResolver::Roundabout`] has entering vehicles give way to
+    /// circulating ones. Arms need to lie beyond the ring's ramps to leave room for them.
+    pub fn add_roundabout(
+        &mut self,
+        center: Vec3,
+        radius: f32,
+        arms: Vec<Id<Node>>,
+        resolver: YieldResolver,
+    ) -> Id<Node> {
+        let roundabout = self.add_intersection_node(center, resolver);
+        self.nodes.get_mut(&roundabout).roundabout_radius = Some(radius.max(LANE_WIDTH));
+        for arm in arms {
+            self.add_bidirectional(arm, roundabout, speed::URBAN);
+        }
+        roundabout
+    }
+
     /// Add a bidirectional road (two segments, one in each direction)
     pub fn add_bidirectional(
         &mut self,
@@ -134,7 +470,99 @@ impl Road {
         (a_to_b, b_to_a)
     }
 
+    /// Travel time (seconds) along a route when driving at the speed limit with no other traffic
+    pub fn free_flow_time(&self, route: &[Id<Segment>]) -> f32 {
+        route
+            .iter()
+            .map(|id| {
+                let segment = self.segments.get(id);
+                segment.length / segment.speed_limit
+            })
+            .sum()
+    }
+
+    /// Move a node and refit every segment attached to it.
+    ///
+    /// Straight segments only need their length updated. Curved segments keep the
+    /// tangent at their other (fixed) endpoint and get a new arc through the moved one,
+    /// so turns inside a finalized intersection still leave the approach lane smoothly.
+    /// Bezier segments carry the control point next to the moved node along with it.
+    pub fn move_node(&mut self, id: Id<Node>, position: Vec3) {
+        self.mark_changed();
+        let previous = self.nodes.get(&id).position;
+        self.nodes.get_mut(&id).position = position;
+
+        let node = self.nodes.get(&id);
+        let mut attached: Vec<_> = node
+            .incoming
+            .iter()
+            .chain(&node.outgoing)
+            .copied()
+            .collect();
+        attached.dedup();
+
+        for segment_id in attached {
+            let segment = self.segments.get(&segment_id);
+            let from = self.nodes.get(&segment.from).position;
+            let to = self.nodes.get(&segment.to).position;
+
+            let geometry = match segment.geometry {
+                SegmentGeometry::Straight => SegmentGeometry::Straight,
+                SegmentGeometry::Curved { .. } if segment.to == id => {
+                    // Keep leaving `from` in the same direction as before
+                    let tangent = segment.geometry.direction_at(from, previous, 0.0);
+                    let (center, radius, clockwise) = compute_arc(from, tangent, to);
+                    SegmentGeometry::Curved {
+                        center,
+                        radius,
+                        clockwise,
+                    }
+                }
+                SegmentGeometry::Curved { .. } => {
+                    // Keep arriving at `to` in the same direction; fit the arc backwards
+                    let tangent = segment.geometry.direction_at(previous, to, 1.0);
+                    let (center, radius, clockwise) = compute_arc(to, -tangent, from);
+                    SegmentGeometry::Curved {
+                        center,
+                        radius,
+                        clockwise: !clockwise,
+                    }
+                }
+                SegmentGeometry::Bezier { control1, control2 } => {
+                    let shift = position - previous;
+                    if segment.to == id {
+                        SegmentGeometry::Bezier {
+                            control1,
+                            control2: control2 + shift,
+                        }
+                    } else {
+                        SegmentGeometry::Bezier {
+                            control1: control1 + shift,
+                            control2,
+                        }
+                    }
+                }
+            };
+
+            let segment = self.segments.get_mut(&segment_id);
+            segment.length = geometry.length(from, to);
+            segment.geometry = geometry;
+        }
+
+        if self.spatial_index.is_some() {
+            self.rebuild_spatial_index();
+        }
+    }
+
+    /// [`Road::finalize_with`] the default [`FinalizeConfig`]
     pub fn finalize(&mut self) {
+        self.finalize_with(&FinalizeConfig::default());
+    }
+
+    /// Build intersections: split junction nodes into per-lane edge nodes, generate turn
+    /// segments and their conflicts, and offset the remaining roads to the right lane
+    pub fn finalize_with(&mut self, config: &FinalizeConfig) {
+        self.mark_changed();
         const INTERSECTION_RADIUS: f32 = 8.0;
         const ROUNDABOUT_RADIUS: f32 = 8.0;
         const RAMP_LENGTH: f32 = 8.0; // Straight section before roundabout curve
@@ -159,7 +587,8 @@ impl Road {
             position: Vec3,
             entries: Vec<EntryData>,
             exits: Vec<ExitData>,
-            yield_resolver: YieldResolver,
+            /// Set when the junction is built as a roundabout
+            ring_radius: Option<f32>,
         }
 
         // Pass 1: collect all intersection data
@@ -168,18 +597,14 @@ impl Road {
             .iter_with_ids()
             .filter(|(_, node)| node.incoming.len() > 1 && node.outgoing.len() > 1)
             .map(|(intersection_id, intersection_node)| {
-                let is_roundabout =
-                    intersection_node.yield_resolver == Some(YieldResolver::Roundabout);
-                let radius = if is_roundabout {
-                    ROUNDABOUT_RADIUS
-                } else {
-                    INTERSECTION_RADIUS
-                };
+                let ring_radius = intersection_node.roundabout_radius.or((intersection_node
+                    .yield_resolver
+                    == Some(YieldResolver::Roundabout))
+                .then_some(ROUNDABOUT_RADIUS));
                 // For roundabouts, edge nodes are further out to allow for straight ramps
-                let edge_distance = if is_roundabout {
-                    radius + RAMP_LENGTH
-                } else {
-                    radius
+                let edge_distance = match ring_radius {
+                    Some(radius) => radius + RAMP_LENGTH,
+                    None => INTERSECTION_RADIUS,
                 };
 
                 let entries = intersection_node
@@ -241,7 +666,7 @@ impl Road {
                     position: intersection_node.position,
                     entries,
                     exits,
-                    yield_resolver: intersection_node.yield_resolver.unwrap_or_default(),
+                    ring_radius,
                 }
             })
             .collect();
@@ -288,7 +713,7 @@ impl Road {
             let mut intersection_outgoing: Vec<Id<Segment>> = Vec::new();
             let mut entry_directions: HashMap<Id<Segment>, Vec3> = HashMap::new();
 
-            if data.yield_resolver == YieldResolver::Roundabout {
+            if let Some(ring_radius) = data.ring_radius {
                 // ROUNDABOUT: Arc triangles + inner circle design
                 // Each arm has: on-ramp → A node → bypass to B node → off-ramp
                 // Plus: A node → inner circle → B nodes for through/left turns
@@ -301,39 +726,6 @@ impl Road {
                 let mut sorted_exits: Vec<_> = data.exits.iter().enumerate().collect();
                 sorted_exits.sort_by(|a, b| a.1.angle.partial_cmp(&b.1.angle).unwrap());
 
-                // Helper to compute arc center and clockwise flag given start point, start direction, and end point
-                // Returns (center, radius, clockwise) for an arc that starts at p1 heading in dir1 and ends at p2
-                let compute_arc = |p1: Vec3, dir1: Vec3, p2: Vec3| -> (Vec3, f32, bool) {
-                    // Perpendicular to start direction (potential center lies on this line from p1)
-                    let perp1 = Vec3::new(-dir1.y, dir1.x, 0.0);
-                    // Midpoint between p1 and p2
-                    let mid = (p1 + p2) / 2.0;
-                    // Perpendicular bisector direction
-                    let diff = p2 - p1;
-                    let perp_bisector = Vec3::new(-diff.y, diff.x, 0.0).normalize();
-
-                    // Solve: p1 + t * perp1 = mid + s * perp_bisector
-                    let det = perp1.x * (-perp_bisector.y) - perp1.y * (-perp_bisector.x);
-                    let center = if det.abs() < 0.0001 {
-                        // Nearly parallel, use midpoint as fallback
-                        mid
-                    } else {
-                        let t = ((mid.x - p1.x) * (-perp_bisector.y)
-                            - (mid.y - p1.y) * (-perp_bisector.x))
-                            / det;
-                        p1 + perp1 * t
-                    };
-
-                    let radius = (p1 - center).length();
-
-                    // Determine clockwise flag based on which side of the path the center is
-                    // Right of direction = clockwise, Left = counter-clockwise
-                    let right_dir = Vec3::new(dir1.y, -dir1.x, 0.0);
-                    let clockwise = (center - p1).dot(right_dir) > 0.0;
-
-                    (center, radius, clockwise)
-                };
-
                 // Create shared circle nodes - each serves as both:
                 // - A (landing) for one entry
                 // - B (departure) for one exit
@@ -344,7 +736,7 @@ impl Road {
                     // Node at 45° CCW from approach direction
                     let node_angle = entry.angle + std::f32::consts::FRAC_PI_4;
                     let node_pos = data.position
-                        + Vec3::new(node_angle.cos(), node_angle.sin(), 0.0) * ROUNDABOUT_RADIUS;
+                        + Vec3::new(node_angle.cos(), node_angle.sin(), 0.0) * ring_radius;
                     let node = self.add_node(node_pos);
                     circle_nodes.push(node);
                 }
@@ -374,6 +766,13 @@ impl Road {
                         geometry,
                         turn_type: TurnType::RoundaboutEntry,
                         length,
+                        allowed_classes: VehicleClasses::ALL,
+                        lanes: 1,
+                        yield_required: false,
+                        toll: 0.0,
+                        name: None,
+                        control: TrafficControl::None,
+                        is_priority_road: false,
                     });
 
                     entry_directions.insert(segment_id, entry.direction);
@@ -394,7 +793,7 @@ impl Road {
 
                     let geometry = SegmentGeometry::Curved {
                         center: data.position,
-                        radius: ROUNDABOUT_RADIUS,
+                        radius: ring_radius,
                         clockwise: false, // counter-clockwise flow
                     };
                     let length = geometry.length(from_pos, to_pos);
@@ -406,6 +805,13 @@ impl Road {
                         geometry,
                         turn_type: TurnType::RoundaboutCircle,
                         length,
+                        allowed_classes: VehicleClasses::ALL,
+                        lanes: 1,
+                        yield_required: false,
+                        toll: 0.0,
+                        name: None,
+                        control: TrafficControl::None,
+                        is_priority_road: false,
                     });
 
                     // Tangent for counter-clockwise: 90° counter-clockwise from outward
@@ -450,6 +856,13 @@ impl Road {
                         geometry,
                         turn_type: TurnType::RoundaboutExit,
                         length,
+                        allowed_classes: VehicleClasses::ALL,
+                        lanes: 1,
+                        yield_required: false,
+                        toll: 0.0,
+                        name: None,
+                        control: TrafficControl::None,
+                        is_priority_road: false,
                     });
 
                     entry_directions.insert(segment_id, tangent);
@@ -521,10 +934,17 @@ impl Road {
                         let segment_id = self.segments.alloc(Segment {
                             from: entry_node_id,
                             to: exit_node_id,
-                            speed_limit: speed::SLOW,
+                            speed_limit: config.turn_speed(turn_type),
                             geometry,
                             turn_type,
                             length,
+                            allowed_classes: VehicleClasses::ALL,
+                            lanes: 1,
+                            yield_required: false,
+                            toll: 0.0,
+                            name: None,
+                            control: TrafficControl::None,
+                            is_priority_road: false,
                         });
                         entry_directions.insert(segment_id, entry.direction);
 
@@ -537,7 +957,34 @@ impl Road {
                 }
             }
 
-            // 2d. Create Intersection record
+            // 2d. Assign turns to the lanes of multi-lane approaches, right turns rightmost
+            let mut lane_movements = HashMap::new();
+            for (entry, &entry_node_id) in data.entries.iter().zip(&entry_node_ids) {
+                let lanes = self.segments.get(&entry.segment_id).lanes;
+                if lanes <= 1 {
+                    continue;
+                }
+
+                let mut turns = self.nodes.get(&entry_node_id).outgoing.clone();
+                // Signed turn sharpness: sharpest right first, sharpest left last
+                let leftness = |turn: &Id<Segment>| match self.segments.get(turn).turn_type {
+                    TurnType::Right(cross) => -cross,
+                    TurnType::Left(cross) => cross,
+                    _ => 0.0,
+                };
+                turns.sort_by(|a, b| leftness(a).total_cmp(&leftness(b)));
+
+                for (index, turn) in turns.iter().enumerate() {
+                    for lane in lanes_for_turn(index, turns.len(), lanes) {
+                        lane_movements
+                            .entry((entry.segment_id, lane))
+                            .or_insert_with(Vec::new)
+                            .push(*turn);
+                    }
+                }
+            }
+
+            // 2e. Create Intersection record
             let mut all_edge_nodes = entry_node_ids.clone();
             all_edge_nodes.extend(exit_node_ids);
 
@@ -547,6 +994,7 @@ impl Road {
                 outgoing: intersection_outgoing,
                 edge_nodes: all_edge_nodes,
                 conflicts: HashMap::new(),
+                conflict_points: HashMap::new(),
                 entry_directions,
                 yield_resolver: self
                     .nodes
@@ -554,6 +1002,9 @@ impl Road {
                     .yield_resolver
                     .unwrap_or_default(),
                 arrival_counter: 0,
+                lane_movements,
+                reservations: HashMap::new(),
+                stop_times: HashMap::new(),
             });
 
             // Clear the original intersection node's connections (it's no longer used for routing)
@@ -570,108 +1021,88 @@ impl Road {
             .flat_map(|i| i.edge_nodes.iter().copied())
             .collect();
 
-        // Collect segment data for offsetting (to avoid borrow issues)
-        // Must capture node flags NOW before we start modifying them
-        // Note: spawn nodes are segment sources, despawn nodes are segment destinations
-        let segments_to_offset: Vec<_> = self
-            .segments
-            .iter_with_ids()
-            .filter(|(_, seg)| matches!(seg.geometry, SegmentGeometry::Straight))
-            .map(|(seg_id, seg)| {
-                let from_node = self.nodes.get(&seg.from);
-                let to_node = self.nodes.get(&seg.to);
-                let direction = (to_node.position - from_node.position).normalize();
-                let perpendicular = direction.cross(Vec3::Z);
-
-                (
-                    seg_id,
-                    seg.from,
-                    seg.to,
-                    from_node.position + perpendicular * LANE_OFFSET,
-                    to_node.position + perpendicular * LANE_OFFSET,
-                    edge_node_ids.contains(&seg.from),
-                    edge_node_ids.contains(&seg.to),
-                    from_node.is_spawn, // Source can be spawn point
-                    to_node.is_despawn, // Destination can be despawn point
-                )
-            })
-            .collect();
+        // Straight segment ends at each remaining node: (segment, right-hand perpendicular,
+        // arriving here); captured before any node is modified
+        type End = (Id<Segment>, Vec3, bool);
+        let mut ends: HashMap<Id<Node>, Vec<End>> = HashMap::new();
+        let mut straight_segments = vec![];
+        for (seg_id, seg) in self.segments.iter_with_ids() {
+            if !matches!(seg.geometry, SegmentGeometry::Straight) {
+                continue;
+            }
+            straight_segments.push(seg_id);
+
+            let from = self.nodes.get(&seg.from).position;
+            let to = self.nodes.get(&seg.to).position;
+            let perpendicular = (to - from).normalize().cross(Vec3::Z);
+            for (node, arriving) in [(seg.from, false), (seg.to, true)] {
+                if !edge_node_ids.contains(&node) {
+                    ends.entry(node)
+                        .or_default()
+                        .push((seg_id, perpendicular, arriving));
+                }
+            }
+        }
 
-        // Create offset nodes and rewire segments
-        for (
-            seg_id,
-            old_from,
-            old_to,
-            from_offset_pos,
-            to_offset_pos,
-            from_is_edge,
-            to_is_edge,
-            from_is_spawn,
-            to_is_despawn,
-        ) in segments_to_offset
-        {
-            let new_from = if from_is_edge {
-                old_from // Already offset
-            } else {
-                // Create offset node - source nodes can only be spawn points
-                let new_node_id = self.nodes.alloc(Node {
-                    position: from_offset_pos,
-                    incoming: vec![],
-                    outgoing: vec![],
-                    is_spawn: from_is_spawn,
-                    is_despawn: false,
-                    yield_resolver: None,
-                });
-                // Clear old node's connections and flags (no longer used for routing)
-                let old_node_mut = self.nodes.get_mut(&old_from);
-                old_node_mut.outgoing.retain(|&id| id != seg_id);
-                old_node_mut.is_spawn = false;
-                old_node_mut.is_despawn = false;
-                new_node_id
-            };
+        // Segments continuing through a node (bending less than 120°, so not turning back
+        // the way they came) share a single offset node, so consecutive segments keep exactly matching endpoints.
+        // The opposite direction of a two-way road gets its own node on the other side.
+        let mut replacements: HashMap<(Id<Node>, Id<Segment>), Id<Node>> = HashMap::new();
+        let mut ends: Vec<_> = ends.into_iter().collect();
+        ends.sort_by_key(|(id, _)| id.id);
+        for (old_id, node_ends) in ends {
+            let continues = |a: &End, b: &End| a.2 != b.2 && a.1.dot(b.1) > -0.5;
+            let mut groups: Vec<Vec<End>> = vec![];
+            for end in node_ends {
+                let joined: Vec<usize> = (0..groups.len())
+                    .filter(|&g| groups[g].iter().any(|other| continues(&end, other)))
+                    .collect();
+                let mut group = vec![end];
+                for g in joined.into_iter().rev() {
+                    group.extend(groups.remove(g));
+                }
+                groups.push(group);
+            }
 
-            let new_to = if to_is_edge {
-                old_to // Already offset
-            } else {
-                // Create offset node - destination nodes can only be despawn points
-                let new_node_id = self.nodes.alloc(Node {
-                    position: to_offset_pos,
-                    incoming: vec![],
-                    outgoing: vec![],
-                    is_spawn: false,
-                    is_despawn: to_is_despawn,
-                    yield_resolver: None,
-                });
-                // Clear old node's connections and flags (no longer used for routing)
-                let old_node_mut = self.nodes.get_mut(&old_to);
-                old_node_mut.incoming.retain(|&id| id != seg_id);
-                old_node_mut.is_spawn = false;
-                old_node_mut.is_despawn = false;
-                new_node_id
-            };
+            for group in groups {
+                self.replace_with_offset_node(old_id, &group, LANE_OFFSET, &mut replacements);
+            }
+
+            let old_node_mut = self.nodes.get_mut(&old_id);
+            old_node_mut.is_spawn = false;
+            old_node_mut.is_despawn = false;
+        }
 
-            // Rewire segment
+        // Rewire segments onto the offset nodes
+        for seg_id in straight_segments {
             let segment = self.segments.get_mut(&seg_id);
-            segment.from = new_from;
-            segment.to = new_to;
+            let new_from = replacements.get(&(segment.from, seg_id)).copied();
+            let new_to = replacements.get(&(segment.to, seg_id)).copied();
+            segment.from = new_from.unwrap_or(segment.from);
+            segment.to = new_to.unwrap_or(segment.to);
+            let (from, to) = (segment.from, segment.to);
 
             // Update length based on new positions
-            let from_pos = self.nodes.get(&new_from).position;
-            let to_pos = self.nodes.get(&new_to).position;
+            let from_pos = self.nodes.get(&from).position;
+            let to_pos = self.nodes.get(&to).position;
+            let segment = self.segments.get_mut(&seg_id);
             segment.length = segment.geometry.length(from_pos, to_pos);
 
             // Wire up node connections (only for newly created nodes, not reused edge nodes)
-            if !from_is_edge {
-                self.nodes.get_mut(&new_from).outgoing.push(seg_id);
+            if new_from.is_some() {
+                self.nodes.get_mut(&from).outgoing.push(seg_id);
             }
-            if !to_is_edge {
-                self.nodes.get_mut(&new_to).incoming.push(seg_id);
+            if new_to.is_some() {
+                self.nodes.get_mut(&to).incoming.push(seg_id);
             }
         }
 
         // compute conflicts
         for intersection in self.intersections.iter_mut() {
-            let is_roundabout = intersection.yield_resolver == YieldResolver::Roundabout;
+            let is_roundabout = intersection
+                .incoming
+                .iter()
+                .any(|id| self.segments.get(id).turn_type == TurnType::RoundaboutCircle);
 
             for (i, &seg_a_id) in intersection.incoming.iter().enumerate() {
                 let seg_a = self.segments.get(&seg_a_id);
@@ -683,7 +1114,7 @@ impl Road {
                     let from_b = self.nodes.get(&seg_b.from).position;
                     let to_b = self.nodes.get(&seg_b.to).position;
 
-                    let conflicts = if is_roundabout {
+                    let conflict_point = if is_roundabout {
                         // Roundabout conflict rules:
                         // - Entry conflicts with circle segment ONLY if they merge at the same node
                         // - Circle segments don't conflict with each other (same direction)
@@ -693,19 +1124,15 @@ impl Road {
                         let a_is_circle = seg_a.turn_type == TurnType::RoundaboutCircle;
                         let b_is_circle = seg_b.turn_type == TurnType::RoundaboutCircle;
 
-                        // Entry vs circle: only conflict if they end at the same circle node
-                        if a_is_entry && b_is_circle {
-                            seg_a.to == seg_b.to // Entry merges where circle segment ends
-                        } else if b_is_entry && a_is_circle {
-                            seg_b.to == seg_a.to // Entry merges where circle segment ends
-                        } else {
-                            false // Circle-circle or entry-entry don't conflict
-                        }
+                        // Entry vs circle: only conflict if they end at the same circle node,
+                        // where the entry merges
+                        let merges = (a_is_entry && b_is_circle) || (b_is_entry && a_is_circle);
+                        (merges && seg_a.to == seg_b.to).then_some((1.0, 1.0))
                     } else {
-                        do_segments_conflict(seg_a, seg_b, from_a, to_a, from_b, to_b)
+                        conflict_point(seg_a, seg_b, from_a, to_a, from_b, to_b)
                     };
 
-                    if conflicts {
+                    if let Some((progress_a, progress_b)) = conflict_point {
                         intersection
                             .conflicts
                             .entry(seg_a_id)
@@ -716,43 +1143,105 @@ impl Road {
                             .entry(seg_b_id)
                             .or_default()
                             .push(seg_a_id);
+                        intersection
+                            .conflict_points
+                            .insert((seg_a_id, seg_b_id), progress_a);
+                        intersection
+                            .conflict_points
+                            .insert((seg_b_id, seg_a_id), progress_b);
                     }
                 }
             }
+
+            for id in &intersection.incoming {
+                self.segments.get_mut(id).yield_required = intersection
+                    .conflicts
+                    .get(id)
+                    .is_some_and(|conflicts| !conflicts.is_empty());
+            }
+        }
+
+        // Geometry changed everywhere, so an existing index is stale
+        if self.spatial_index.is_some() {
+            self.rebuild_spatial_index();
         }
 
-        // Debug: print graph structure
         crate::log!("=== FINALIZE COMPLETE ===");
-        crate::log!("Nodes:");
+        crate::log!("{}", self.debug_dump());
+    }
+
+    pub fn name_segment(&mut self, id: Id<Segment>, name: &str) {
+        self.segments.get_mut(&id).name = Some(name.to_string());
+    }
+
+    /// Put a stop or yield sign at the end of an intersection approach
+    pub fn set_control(&mut self, id: Id<Segment>, control: TrafficControl) {
+        self.mark_changed();
+        self.segments.get_mut(&id).control = control;
+    }
+
+    /// Mark an intersection approach as part of a priority road: side streets give way to
+    /// it whichever side they come from
+    pub fn set_priority(&mut self, id: Id<Segment>, priority: bool) {
+        self.mark_changed();
+        self.segments.get_mut(&id).is_priority_road = priority;
+    }
+
+    pub fn name_node(&mut self, id: Id<Node>, name: &str) {
+        self.nodes.get_mut(&id).name = Some(name.to_string());
+    }
+
+    /// Segment id followed by its name, if it has one
+    pub fn segment_label(&self, id: Id<Segment>) -> String {
+        match &self.segments.get(&id).name {
+            Some(name) => format!("{id:?} \"{name}\""),
+            None => format!("{id:?}"),
+        }
+    }
+
+    /// Node id followed by its name, if it has one
+    pub fn node_label(&self, id: Id<Node>) -> String {
+        match &self.nodes.get(&id).name {
+            Some(name) => format!("{id:?} \"{name}\""),
+            None => format!("{id:?}"),
+        }
+    }
+
+    /// Graph structure as text, one line per node and segment
+    pub fn debug_dump(&self) -> String {
+        let mut lines = vec!["Nodes:".to_string()];
         for (id, node) in self.nodes.iter_with_ids() {
-            crate::log!(
-                "  {:?}: pos=({:.1}, {:.1}), in={:?}, out={:?}, spawn={}, despawn={}",
-                id,
+            lines.push(format!(
+                "  {}: pos=({:.1}, {:.1}), in={:?}, out={:?}, spawn={}, despawn={}",
+                self.node_label(id),
                 node.position.x,
                 node.position.y,
                 node.incoming,
                 node.outgoing,
                 node.is_spawn,
                 node.is_despawn
-            );
+            ));
         }
-        crate::log!("Segments:");
+        lines.push("Segments:".to_string());
         for (id, seg) in self.segments.iter_with_ids() {
-            crate::log!(
-                "  {:?}: {:?} -> {:?}, turn={:?}",
-                id,
-                seg.from,
-                seg.to,
+            lines.push(format!(
+                "  {}: {} -> {}, turn={:?}",
+                self.segment_label(id),
+                self.node_label(seg.from),
+                self.node_label(seg.to),
                 seg.turn_type
-            );
+            ));
         }
 
         for intersection in self.intersections.iter() {
-            crate::log!("Conflicts: {:?}", intersection.conflicts);
+            lines.push(format!("Conflicts: {:?}", intersection.conflicts));
         }
+        lines.join("\n")
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     pub position: Vec3,
     pub incoming: Vec<Id<Segment>>,
@@ -760,8 +1249,59 @@ pub struct Node {
     pub is_spawn: bool,
     pub is_despawn: bool,
     pub yield_resolver: Option<YieldResolver>,
+    /// Scales how often vehicles spawn here relative to other spawn nodes
+    pub spawn_weight: f32,
+    /// Human-readable name for debug output
+    pub name: Option<String>,
+    /// Radius (m) of the ring [`Road::finalize`] builds here, see [`Road::add_roundabout`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub roundabout_radius: Option<f32>,
+}
+
+/// Lanes serving the `index`-th of `turns` turns (sorted right to left) on an approach
+/// with `lanes` lanes. Turns spread evenly over the lanes; a turn that straddles a lane
+/// boundary is shared by both lanes.
+fn lanes_for_turn(index: usize, turns: usize, lanes: u8) -> std::ops::RangeInclusive<u8> {
+    let lanes = lanes as usize;
+    let first = index * lanes / turns;
+    let last = ((index + 1) * lanes).div_ceil(turns) - 1;
+    first as u8..=last.max(first) as u8
+}
+
+/// Compute arc center and clockwise flag given start point, start direction, and end point
+///
+/// Returns (center, radius, clockwise) for an arc that starts at p1 heading in dir1 and ends at p2
+fn compute_arc(p1: Vec3, dir1: Vec3, p2: Vec3) -> (Vec3, f32, bool) {
+    // Perpendicular to start direction (potential center lies on this line from p1)
+    let perp1 = Vec3::new(-dir1.y, dir1.x, 0.0);
+    // Midpoint between p1 and p2
+    let mid = (p1 + p2) / 2.0;
+    // Perpendicular bisector direction
+    let diff = p2 - p1;
+    let perp_bisector = Vec3::new(-diff.y, diff.x, 0.0).normalize();
+
+    // Solve: p1 + t * perp1 = mid + s * perp_bisector
+    let det = perp1.x * (-perp_bisector.y) - perp1.y * (-perp_bisector.x);
+    let center = if det.abs() < 0.0001 {
+        // Nearly parallel, use midpoint as fallback
+        mid
+    } else {
+        let t = ((mid.x - p1.x) * (-perp_bisector.y) - (mid.y - p1.y) * (-perp_bisector.x)) / det;
+        p1 + perp1 * t
+    };
+
+    let radius = (p1 - center).length();
+
+    // Determine clockwise flag based on which side of the path the center is
+    // Right of direction = clockwise, Left = counter-clockwise
+    let right_dir = Vec3::new(dir1.y, -dir1.x, 0.0);
+    let clockwise = (center - p1).dot(right_dir) > 0.0;
+
+    (center, radius, clockwise)
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Segment {
     pub from: Id<Node>,
     pub to: Id<Node>,
@@ -769,8 +1309,27 @@ pub struct Segment {
     pub geometry: SegmentGeometry,
     pub turn_type: TurnType,
     pub length: f32,
+    /// Vehicle classes allowed to route over this segment
+    pub allowed_classes: VehicleClasses,
+    /// Number of lanes; on an intersection approach each lane serves a subset of the turns
+    pub lanes: u8,
+    /// Intersection movement that conflicts with at least one other, set by finalize.
+    /// Vehicles never need a gap to enter a movement without conflicts.
+    pub yield_required: bool,
+    /// Charge for driving this segment, weighed by [`crate::driver::CostWeights::toll`]
+    pub toll: f32,
+    /// Human-readable name for debug output, e.g. the street it belongs to
+    pub name: Option<String>,
+    /// Sign at the end of an intersection approach
+    pub control: TrafficControl,
+    /// Approach on a major road, which never yields to side streets under
+    /// [`YieldResolver::RightOfWay`], see [`Road::set_priority`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub is_priority_road: bool,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SegmentGeometry {
     Straight,
     Curved {
@@ -778,6 +1337,12 @@ pub enum SegmentGeometry {
         radius: f32,
         clockwise: bool,
     },
+    /// Cubic Bezier from the segment's start node to its end node, for hand-drawn S-curves
+    /// and merges. Progress is measured along the curve, so vehicles keep an even pace.
+    Bezier {
+        control1: Vec3,
+        control2: Vec3,
+    },
 }
 
 impl SegmentGeometry {
@@ -809,6 +1374,9 @@ impl SegmentGeometry {
 
                 radius * angle_diff.abs()
             }
+            SegmentGeometry::Bezier { control1, control2 } => {
+                cubic_length([from, *control1, *control2, to], BEZIER_TOLERANCE, 16)
+            }
         }
     }
 
@@ -859,6 +1427,101 @@ impl SegmentGeometry {
                     from.z, // preserve Z
                 )
             }
+            SegmentGeometry::Bezier { control1, control2 } => {
+                let points = [from, *control1, *control2, to];
+                cubic_point(points, cubic_parameter(points, progress))
+            }
+        }
+    }
+
+    /// Position at `progress` along `lane` of a segment with `lanes` lanes. Lane 0 is the
+    /// rightmost; lanes are spread `LANE_WIDTH` apart around the centerline.
+    ///
+    /// Only straight segments are offset so far; arcs and Bezier curves keep every lane on
+    /// the centerline until they get per-lane geometry.
+    pub fn position_in_lane(
+        &self,
+        from: Vec3,
+        to: Vec3,
+        progress: f32,
+        lane: u8,
+        lanes: u8,
+    ) -> Vec3 {
+        let center = self.position_at(from, to, progress);
+        match self {
+            SegmentGeometry::Straight => {
+                let direction = (to - from).normalize_or_zero();
+                let right = Vec3::new(direction.y, -direction.x, 0.0);
+                let offset = (lanes.max(1) - 1) as f32 / 2.0 - lane as f32;
+                center + right * offset * LANE_WIDTH
+            }
+            SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => center,
+        }
+    }
+
+    /// `n + 1` evenly spaced points along the path (`n` is at least 1); the first and last
+    /// are exactly `from` and `to`
+    pub fn sample(&self, from: Vec3, to: Vec3, n: usize) -> Vec<Vec3> {
+        let n = n.max(1);
+        (0..=n)
+            .map(|i| match i {
+                0 => from,
+                i if i == n => to,
+                i => self.position_at(from, to, i as f32 / n as f32),
+            })
+            .collect()
+    }
+
+    /// [`SegmentGeometry::sample`] paired with the unit direction of travel at each point
+    pub fn sample_with_tangents(&self, from: Vec3, to: Vec3, n: usize) -> Vec<(Vec3, Vec3)> {
+        let n = n.max(1);
+        self.sample(from, to, n)
+            .into_iter()
+            .enumerate()
+            .map(|(i, point)| (point, self.direction_at(from, to, i as f32 / n as f32)))
+            .collect()
+    }
+
+    /// Progress (0.0 to 1.0) of the point on the path closest to `point`
+    pub fn closest_progress(&self, from: Vec3, to: Vec3, point: Vec3) -> f32 {
+        match self {
+            SegmentGeometry::Straight => {
+                let along = to - from;
+                let length_sq = along.length_squared();
+                if length_sq <= f32::EPSILON {
+                    return 0.0;
+                }
+                ((point - from).dot(along) / length_sq).clamp(0.0, 1.0)
+            }
+            SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => {
+                // Coarse sampling, then refine around the best sample
+                const SAMPLES: usize = 32;
+                const REFINE_PASSES: usize = 3;
+
+                let distance_at = |t: f32| self.position_at(from, to, t).distance_squared(point);
+
+                let mut best = 0.0;
+                let mut step = 1.0 / SAMPLES as f32;
+                for i in 0..=SAMPLES {
+                    let t = i as f32 * step;
+                    if distance_at(t) < distance_at(best) {
+                        best = t;
+                    }
+                }
+
+                for _ in 0..REFINE_PASSES {
+                    let start = (best - step).max(0.0);
+                    step /= 8.0;
+                    for i in 0..=16 {
+                        let t = (start + i as f32 * step).min(1.0);
+                        if distance_at(t) < distance_at(best) {
+                            best = t;
+                        }
+                    }
+                }
+
+                best
+            }
         }
     }
 
@@ -881,31 +1544,306 @@ impl SegmentGeometry {
                     Vec3::new(-radial.y, radial.x, 0.0)
                 }
             }
+            SegmentGeometry::Bezier { control1, control2 } => {
+                let points = [from, *control1, *control2, to];
+                cubic_derivative(points, cubic_parameter(points, progress))
+                    .try_normalize()
+                    .unwrap_or_else(|| (to - from).normalize())
+            }
+        }
+    }
+
+    /// Fastest speed (m/s) at which the curve's tightest point keeps lateral acceleration
+    /// within `lateral_accel` (m/s²), from v²/r. `None` on straight segments.
+    pub fn max_comfortable_speed(&self, from: Vec3, to: Vec3, lateral_accel: f32) -> Option<f32> {
+        let radius = match self {
+            SegmentGeometry::Straight => return None,
+            SegmentGeometry::Curved { radius, .. } => *radius,
+            SegmentGeometry::Bezier { control1, control2 } => {
+                cubic_min_radius([from, *control1, *control2, to])?
+            }
+        };
+        Some((lateral_accel.max(0.0) * radius.max(0.0)).sqrt())
+    }
+
+    /// Roll (radians about the forward axis) of a vehicle at `speed` leaning into this
+    /// segment's curve; positive leans right. Zero on straight segments and Bezier curves,
+    /// which have no single radius.
+    pub fn bank_angle(&self, speed: f32) -> f32 {
+        match self {
+            SegmentGeometry::Straight | SegmentGeometry::Bezier { .. } => 0.0,
+            SegmentGeometry::Curved {
+                radius, clockwise, ..
+            } => {
+                let bank = bank_angle(speed, *radius);
+                if *clockwise {
+                    bank
+                } else {
+                    -bank
+                }
+            }
         }
     }
 }
 
+/// Largest gap (m) between a Bezier piece's control polygon and its chord before
+/// [`cubic_length`] splits it further
+const BEZIER_TOLERANCE: f32 = 0.001;
+
+/// Arc-length table resolution used to map progress onto a Bezier parameter
+const BEZIER_LENGTH_SAMPLES: usize = 32;
+
+/// Point at parameter `t` of the cubic Bezier with control points `p`
+fn cubic_point(p: [Vec3; 4], t: f32) -> Vec3 {
+    let u = 1.0 - t;
+    p[0] * (u * u * u) + p[1] * (3.0 * u * u * t) + p[2] * (3.0 * u * t * t) + p[3] * (t * t * t)
+}
+
+/// Derivative at parameter `t` of the cubic Bezier with control points `p`
+fn cubic_derivative(p: [Vec3; 4], t: f32) -> Vec3 {
+    let u = 1.0 - t;
+    (p[1] - p[0]) * (3.0 * u * u) + (p[2] - p[1]) * (6.0 * u * t) + (p[3] - p[2]) * (3.0 * t * t)
+}
+
+/// Second derivative at parameter `t` of the cubic Bezier with control points `p`
+fn cubic_second_derivative(p: [Vec3; 4], t: f32) -> Vec3 {
+    (p[2] - p[1] * 2.0 + p[0]) * (6.0 * (1.0 - t)) + (p[3] - p[2] * 2.0 + p[1]) * (6.0 * t)
+}
+
+/// Smallest radius of curvature along a cubic Bezier, sampled at the arc-length table
+/// resolution; `None` if it never bends
+fn cubic_min_radius(p: [Vec3; 4]) -> Option<f32> {
+    (0..=BEZIER_LENGTH_SAMPLES)
+        .filter_map(|i| {
+            let t = i as f32 / BEZIER_LENGTH_SAMPLES as f32;
+            let velocity = cubic_derivative(p, t);
+            let bend = velocity.cross(cubic_second_derivative(p, t)).length();
+            (bend > f32::EPSILON).then(|| velocity.length().powi(3) / bend)
+        })
+        .min_by(f32::total_cmp)
+}
+
+/// Arc length of a cubic Bezier. Splits in half until each piece's control polygon is
+/// within `tolerance` of its chord, then averages the two, which converges quickly.
+fn cubic_length(p: [Vec3; 4], tolerance: f32, depth: u32) -> f32 {
+    let chord = p[0].distance(p[3]);
+    let polygon = p[0].distance(p[1]) + p[1].distance(p[2]) + p[2].distance(p[3]);
+    if depth == 0 || polygon - chord <= tolerance {
+        return (chord + polygon) / 2.0;
+    }
+
+    // de Casteljau split at t = 0.5
+    let ab = p[0].lerp(p[1], 0.5);
+    let bc = p[1].lerp(p[2], 0.5);
+    let cd = p[2].lerp(p[3], 0.5);
+    let abc = ab.lerp(bc, 0.5);
+    let bcd = bc.lerp(cd, 0.5);
+    let mid = abc.lerp(bcd, 0.5);
+    cubic_length([p[0], ab, abc, mid], tolerance / 2.0, depth - 1)
+        + cubic_length([mid, bcd, cd, p[3]], tolerance / 2.0, depth - 1)
+}
+
+/// Bezier parameter at which `progress` (0.0 to 1.0) of the arc length is covered.
+/// Interpolates a table of chord lengths, so it rises monotonically with progress.
+fn cubic_parameter(p: [Vec3; 4], progress: f32) -> f32 {
+    let mut cumulative = [0.0; BEZIER_LENGTH_SAMPLES + 1];
+    let mut previous = p[0];
+    for i in 1..=BEZIER_LENGTH_SAMPLES {
+        let point = cubic_point(p, i as f32 / BEZIER_LENGTH_SAMPLES as f32);
+        cumulative[i] = cumulative[i - 1] + previous.distance(point);
+        previous = point;
+    }
+
+    let total = cumulative[BEZIER_LENGTH_SAMPLES];
+    if total <= f32::EPSILON {
+        return progress.clamp(0.0, 1.0);
+    }
+
+    let target = progress.clamp(0.0, 1.0) * total;
+    let i = cumulative
+        .partition_point(|length| *length < target)
+        .clamp(1, BEZIER_LENGTH_SAMPLES);
+    let span = cumulative[i] - cumulative[i - 1];
+    let within = if span > 0.0 {
+        (target - cumulative[i - 1]) / span
+    } else {
+        0.0
+    };
+    (i - 1) as f32 / BEZIER_LENGTH_SAMPLES as f32 + within / BEZIER_LENGTH_SAMPLES as f32
+}
+
+/// Largest roll (radians) a vehicle leans into a curve
+pub const MAX_BANK_ANGLE: f32 = 0.12;
+
+/// Roll (radians) per m/s² of lateral acceleration
+const BANK_PER_LATERAL_ACCELERATION: f32 = 0.02;
+
+/// Unsigned roll into a curve of `radius` at `speed`, proportional to the lateral
+/// acceleration v²/r and capped at [`MAX_BANK_ANGLE`]
+pub fn bank_angle(speed: f32, radius: f32) -> f32 {
+    if radius <= 0.0 {
+        return 0.0;
+    }
+    (speed * speed / radius * BANK_PER_LATERAL_ACCELERATION).min(MAX_BANK_ANGLE)
+}
+
+/// Maps keyed by ids, stored as lists of `(key, value)` entries since formats like JSON
+/// only allow string keys
+#[cfg(feature = "serde")]
+mod map_entries {
+    use std::{collections::HashMap, hash::Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K: Serialize, V: Serialize, S: Serializer>(
+        map: &HashMap<K, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Intersection {
     pub position: Vec3,
     pub incoming: Vec<Id<Segment>>,
     pub outgoing: Vec<Id<Segment>>,
     pub edge_nodes: Vec<Id<Node>>,
+    #[cfg_attr(feature = "serde", serde(with = "map_entries"))]
     pub conflicts: HashMap<Id<Segment>, Vec<Id<Segment>>>,
+    /// For each conflicting pair (a, b), the progress along a at which its path meets b's
+    #[cfg_attr(feature = "serde", serde(with = "map_entries"))]
+    pub conflict_points: HashMap<(Id<Segment>, Id<Segment>), f32>,
     pub yield_resolver: YieldResolver,
+    #[cfg_attr(feature = "serde", serde(with = "map_entries"))]
     pub entry_directions: HashMap<Id<Segment>, Vec3>,
     /// Counter for FIFO arrival order at this intersection
     pub arrival_counter: u32,
+    /// Turn segments reachable from each lane of a multi-lane approach, keyed by
+    /// (approach segment, lane); lane 0 is the rightmost
+    #[cfg_attr(feature = "serde", serde(with = "map_entries"))]
+    pub lane_movements: HashMap<(Id<Segment>, u8), Vec<Id<Segment>>>,
+    /// Turn segments claimed by vehicles cleared to enter but not yet through, so a
+    /// conflicting vehicle checked later in the same step holds instead of entering too
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reservations: HashMap<Id<Segment>, Entity>,
+    /// Simulation time each approaching vehicle came to a halt at the stop line, for
+    /// [`YieldResolver::AllWayStop`] ordering
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub stop_times: HashMap<Entity, f32>,
+}
+
+/// A single movement through an intersection: one approach taking one turn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Movement {
+    pub segment: Id<Segment>,
+    /// Heading of the approaching vehicle (into the intersection)
+    pub approach: Vec3,
+    pub turn_type: TurnType,
+}
+
+impl Intersection {
+    fn movement(&self, road: &Road, segment: Id<Segment>) -> Movement {
+        Movement {
+            segment,
+            approach: self
+                .entry_directions
+                .get(&segment)
+                .copied()
+                .unwrap_or(Vec3::ZERO),
+            turn_type: road.segments.get(&segment).turn_type,
+        }
+    }
+
+    /// Progress along movement `a` at which it meets conflicting movement `b`.
+    /// Defaults to the start of `a`, the conservative end, for pairs that don't conflict.
+    pub fn conflict_progress(&self, a: Id<Segment>, b: Id<Segment>) -> f32 {
+        self.conflict_points.get(&(a, b)).copied().unwrap_or(0.0)
+    }
+
+    fn movements_conflict(&self, a: Id<Segment>, b: Id<Segment>) -> bool {
+        self.conflicts.get(&a).is_some_and(|c| c.contains(&b))
+    }
+
+    /// Every pair of movements whose paths conflict, each pair listed once
+    pub fn conflicting_movement_pairs(&self, road: &Road) -> Vec<(Movement, Movement)> {
+        let mut pairs = vec![];
+        for (i, &a) in self.incoming.iter().enumerate() {
+            for &b in self.incoming.iter().skip(i + 1) {
+                if self.movements_conflict(a, b) {
+                    pairs.push((self.movement(road, a), self.movement(road, b)));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Groups of mutually non-conflicting movements that could share a signal phase.
+    /// Every movement appears in at least one group and no group can take another movement.
+    pub fn compatible_phase_groups(&self, road: &Road) -> Vec<Vec<Movement>> {
+        let mut groups: Vec<Vec<Id<Segment>>> = vec![];
+
+        // Greedily place each movement in the first group it doesn't conflict with
+        for &segment in &self.incoming {
+            let existing = groups.iter_mut().find(|group| {
+                group
+                    .iter()
+                    .all(|&other| !self.movements_conflict(segment, other))
+            });
+            match existing {
+                Some(group) => group.push(segment),
+                None => groups.push(vec![segment]),
+            }
+        }
+
+        // Grow each group to a maximal compatible set
+        for group in &mut groups {
+            for &segment in &self.incoming {
+                if !group.contains(&segment)
+                    && group
+                        .iter()
+                        .all(|&other| !self.movements_conflict(segment, other))
+                {
+                    group.push(segment);
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .map(|segment| self.movement(road, segment))
+                    .collect()
+            })
+            .collect()
+    }
 }
 
-fn do_segments_conflict(
+/// Where the paths of two segments come within conflict distance, as the progress along
+/// each at their closest such approach; `None` if they never get that close
+fn conflict_point(
     a: &Segment,
     b: &Segment,
     from_a: Vec3,
     to_a: Vec3,
     from_b: Vec3,
     to_b: Vec3,
-) -> bool {
+) -> Option<(f32, f32)> {
     const POINTS: usize = 10;
+    const CONFLICT_DISTANCE: f32 = 2.0;
 
     let a_points = (0..=POINTS)
         .map(|i| {
@@ -921,13 +1859,783 @@ fn do_segments_conflict(
         })
         .collect::<Vec<_>>();
 
-    for p_a in &a_points {
-        for p_b in &b_points {
-            if p_a.distance(*p_b) < 2.0 {
-                return true;
+    let mut closest: Option<(f32, f32, f32)> = None;
+    for (i, p_a) in a_points.iter().enumerate() {
+        for (j, p_b) in b_points.iter().enumerate() {
+            let distance = p_a.distance(*p_b);
+            if distance < CONFLICT_DISTANCE && closest.is_none_or(|(d, ..)| distance < d) {
+                closest = Some((distance, i as f32 / POINTS as f32, j as f32 / POINTS as f32));
+            }
+        }
+    }
+
+    closest.map(|(_, progress_a, progress_b)| (progress_a, progress_b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{next_segment_toward, SidePriority};
+    use std::collections::HashSet;
+
+    /// Four-arm junction at the origin with edge nodes `arm` meters away
+    fn plus_junction(arm: f32) -> Road {
+        Road::cross_intersection(
+            Vec3::ZERO,
+            arm,
+            speed::URBAN,
+            YieldResolver::RightOfWay(SidePriority::RIGHT_HAND),
+        )
+    }
+
+    #[test]
+    fn test_cross_intersection_has_four_approaches_with_all_turns() {
+        let road = plus_junction(50.0);
+        assert_eq!(road.intersections.iter().count(), 1);
+        let intersection = road.intersections.iter().next().unwrap();
+
+        let mut approaches: Vec<Vec3> = vec![];
+        for direction in intersection.entry_directions.values() {
+            if !approaches.iter().any(|a| a.dot(*direction) > 0.99) {
+                approaches.push(*direction);
+            }
+        }
+        assert_eq!(approaches.len(), 4);
+
+        // Every approach can go left, straight or right
+        let count = |matches: fn(&TurnType) -> bool| {
+            intersection
+                .incoming
+                .iter()
+                .filter(|id| matches(&road.segments.get(id).turn_type))
+                .count()
+        };
+        assert_eq!(intersection.incoming.len(), 12);
+        assert_eq!(count(|t| matches!(t, TurnType::Straight)), 4);
+        assert_eq!(count(|t| matches!(t, TurnType::Left(_))), 4);
+        assert_eq!(count(|t| matches!(t, TurnType::Right(_))), 4);
+
+        let spawns = road
+            .nodes
+            .iter()
+            .filter(|n| n.is_spawn && !n.outgoing.is_empty());
+        assert_eq!(spawns.count(), 4);
+    }
+
+    #[test]
+    fn test_grid_movements_are_classified_by_turn_direction() {
+        // 2x2 blocks of two-way streets: a four-way center, three-way edges, two-way corners
+        let mut road = Road::default();
+        let ids: Vec<_> = (0..9)
+            .map(|i| road.add_node(Vec3::new((i % 3) as f32, (i / 3) as f32, 0.0) * 100.0))
+            .collect();
+        for y in 0..3 {
+            for x in 0..3 {
+                let here = ids[y * 3 + x];
+                let mut connect = |there| {
+                    road.add_segment(here, there, 13.9);
+                    road.add_segment(there, here, 13.9);
+                };
+                if x + 1 < 3 {
+                    connect(ids[y * 3 + x + 1]);
+                }
+                if y + 1 < 3 {
+                    connect(ids[(y + 1) * 3 + x]);
+                }
+            }
+        }
+        road.finalize();
+
+        let direction_of = |segment: Id<Segment>, progress: f32| {
+            let segment = road.segments.get(&segment);
+            let from = road.nodes.get(&segment.from).position;
+            let to = road.nodes.get(&segment.to).position;
+            segment.geometry.direction_at(from, to, progress)
+        };
+
+        let mut movements = HashSet::new();
+        let mut straight_counts = vec![];
+        for intersection in road.intersections.iter() {
+            let mut straight = 0;
+            for &movement in &intersection.incoming {
+                movements.insert(movement);
+                let entry = intersection.entry_directions[&movement];
+                let exit_node = road.nodes.get(&road.segments.get(&movement).to);
+                let exit = direction_of(exit_node.outgoing[0], 0.0);
+                let cross = entry.cross(exit).z;
+
+                match road.segments.get(&movement).turn_type {
+                    TurnType::Straight => {
+                        assert!(entry.dot(exit) > 0.95);
+                        straight += 1;
+                    }
+                    TurnType::Left(magnitude) => {
+                        assert!(cross > 0.0);
+                        assert!((magnitude - cross.abs()).abs() < 1e-3);
+                    }
+                    TurnType::Right(magnitude) => {
+                        assert!(cross < 0.0);
+                        assert!((magnitude - cross.abs()).abs() < 1e-3);
+                    }
+                    other => panic!("unexpected {other:?} at a grid intersection"),
+                }
+            }
+            straight_counts.push(straight);
+        }
+        straight_counts.sort();
+
+        // Only the center (4 approaches) and edges (1 through street each) go straight
+        assert_eq!(straight_counts, vec![0, 0, 0, 0, 2, 2, 2, 2, 4]);
+
+        // Plain road segments outside intersections stay straight
+        for (id, segment) in road.segments.iter_with_ids() {
+            if !movements.contains(&id) {
+                assert_eq!(segment.turn_type, TurnType::Straight);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cross_intersection_conflicts_are_symmetric_and_geometric() {
+        let road = plus_junction(50.0);
+        let intersection = road.intersections.iter().next().unwrap();
+
+        // Entry directions point into the junction along the approach
+        for &movement in &intersection.incoming {
+            let direction = intersection.entry_directions[&movement];
+            let start = road.nodes.get(&road.segments.get(&movement).from).position;
+            assert!((direction.length() - 1.0).abs() < 1e-3);
+            assert!(direction.dot(intersection.position - start) > 0.0);
+        }
+
+        for (movement, conflicts) in &intersection.conflicts {
+            for other in conflicts {
+                assert_ne!(other, movement);
+                assert!(intersection.conflicts[other].contains(movement));
+
+                // Both paths record where they meet
+                let progress = intersection.conflict_points[&(*movement, *other)];
+                assert!((0.0..=1.0).contains(&progress));
+                assert!(intersection
+                    .conflict_points
+                    .contains_key(&(*other, *movement)));
+            }
+        }
+
+        // The movement entering along `direction` that makes `turn`
+        let movement = |direction: Vec3, turn: fn(&TurnType) -> bool| {
+            *intersection
+                .incoming
+                .iter()
+                .find(|id| {
+                    intersection.entry_directions[id].dot(direction) > 0.99
+                        && turn(&road.segments.get(id).turn_type)
+                })
+                .unwrap()
+        };
+        let conflict = |a, b| intersection.conflicts[&a].contains(&b);
+
+        let north_straight = movement(Vec3::Y, |t| matches!(t, TurnType::Straight));
+        let east_straight = movement(Vec3::X, |t| matches!(t, TurnType::Straight));
+        let south_straight = movement(Vec3::NEG_Y, |t| matches!(t, TurnType::Straight));
+        assert!(conflict(north_straight, east_straight));
+        assert!(!conflict(north_straight, south_straight));
+
+        // A left turn cuts across oncoming through traffic, but opposing left turns
+        // pass in front of each other without their arcs crossing
+        let north_left = movement(Vec3::Y, |t| matches!(t, TurnType::Left(_)));
+        let south_left = movement(Vec3::NEG_Y, |t| matches!(t, TurnType::Left(_)));
+        assert!(conflict(north_left, south_straight));
+        assert!(!conflict(north_left, south_left));
+
+        // A right turn stays clear of the opposing right turn and crossing through traffic
+        let north_right = movement(Vec3::Y, |t| matches!(t, TurnType::Right(_)));
+        let south_right = movement(Vec3::NEG_Y, |t| matches!(t, TurnType::Right(_)));
+        assert!(!conflict(north_right, south_right));
+        assert!(!conflict(north_right, south_straight));
+    }
+
+    #[test]
+    fn test_intersection_node_resolver_carries_through_finalize() {
+        let resolver = YieldResolver::YieldSign {
+            major_axis: Vec3::X,
+        };
+        let road = Road::cross_intersection(Vec3::ZERO, 50.0, speed::URBAN, resolver);
+
+        let intersection = road.intersections.iter().next().unwrap();
+        assert!(intersection.yield_resolver == resolver);
+
+        let center = road
+            .nodes
+            .iter()
+            .find(|node| node.position == Vec3::ZERO)
+            .unwrap();
+        assert!(center.yield_resolver == Some(resolver));
+        assert!(!center.is_spawn && !center.is_despawn);
+    }
+
+    #[test]
+    fn test_phase_groups_separate_crossing_through_movements() {
+        let road = plus_junction(50.0);
+        let intersection = road.intersections.iter().next().unwrap();
+        let groups = intersection.compatible_phase_groups(&road);
+
+        let through = |heading: Vec3| {
+            intersection
+                .incoming
+                .iter()
+                .copied()
+                .find(|id| {
+                    road.segments.get(id).turn_type == TurnType::Straight
+                        && intersection.entry_directions[id].dot(heading) > 0.99
+                })
+                .unwrap()
+        };
+        let southbound = through(Vec3::NEG_Y);
+        let northbound = through(Vec3::Y);
+        let westbound = through(Vec3::NEG_X);
+
+        let in_group =
+            |group: &Vec<Movement>, id: Id<Segment>| group.iter().any(|m| m.segment == id);
+
+        // Opposing throughs can run together
+        assert!(groups
+            .iter()
+            .any(|g| in_group(g, southbound) && in_group(g, northbound)));
+        // Crossing throughs never share a phase
+        assert!(!groups
+            .iter()
+            .any(|g| in_group(g, southbound) && in_group(g, westbound)));
+        // Every movement is served by some phase
+        for id in &intersection.incoming {
+            assert!(groups.iter().any(|g| in_group(g, *id)));
+        }
+        // Pairs agree with the groups
+        assert!(intersection
+            .conflicting_movement_pairs(&road)
+            .iter()
+            .any(|(a, b)| (a.segment, b.segment) == (southbound, westbound)
+                || (a.segment, b.segment) == (westbound, southbound)));
+    }
+
+    #[test]
+    fn test_free_flow_time_matches_hand_computation() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(100.0, 50.0, 0.0));
+        road.add_segment(a, b, 10.0);
+        road.add_segment(b, c, 5.0);
+
+        let (_, route) = next_segment_toward(&road, a, c).unwrap();
+
+        // 100m at 10 m/s + 50m at 5 m/s
+        assert_eq!(route.len(), 2);
+        assert!((road.free_flow_time(&route) - 20.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_left_turn_routed_into_left_lane() {
+        let mut road = Road::default();
+        let center = road.add_intersection_node(
+            Vec3::ZERO,
+            YieldResolver::RightOfWay(SidePriority::RIGHT_HAND),
+        );
+        let mut northbound = None;
+        for position in [
+            Vec3::new(0.0, 50.0, 0.0),
+            Vec3::new(50.0, 0.0, 0.0),
+            Vec3::new(0.0, -50.0, 0.0),
+            Vec3::new(-50.0, 0.0, 0.0),
+        ] {
+            let edge = road.add_edge_node(position);
+            let (inbound, _) = road.add_bidirectional(edge, center, speed::URBAN);
+            if position.y < 0.0 {
+                northbound = Some(inbound);
+            }
+        }
+        let northbound = northbound.unwrap();
+        road.segments.get_mut(&northbound).lanes = 2;
+        road.finalize();
+
+        // Lane nodes leaving the junction, found by position
+        let exit_near = |position: Vec3| {
+            road.nodes
+                .iter_with_ids()
+                .find(|(_, n)| {
+                    n.outgoing.is_empty()
+                        && !n.incoming.is_empty()
+                        && (n.position - position).length() < 3.0
+                })
+                .map(|(id, _)| id)
+                .unwrap()
+        };
+        let start = road.segments.get(&northbound).from;
+        let lane_toward = |destination: Vec3| {
+            let (first, route) = next_segment_toward(&road, start, exit_near(destination)).unwrap();
+            assert_eq!(first, northbound);
+            road.lane_for_route(&route)
+        };
+
+        assert_eq!(lane_toward(Vec3::new(-50.0, 0.0, 0.0)), 1, "left turn");
+        assert_eq!(lane_toward(Vec3::new(50.0, 0.0, 0.0)), 0, "right turn");
+        assert_eq!(lane_toward(Vec3::new(0.0, 50.0, 0.0)), 0, "straight");
+    }
+
+    /// Every segment ends exactly where each segment that can follow it starts
+    fn assert_continuous(road: &Road) {
+        for (id, segment) in road.segments.iter_with_ids() {
+            let end = segment.geometry.position_at(
+                road.nodes.get(&segment.from).position,
+                road.nodes.get(&segment.to).position,
+                1.0,
+            );
+            for next in &road.nodes.get(&segment.to).outgoing {
+                let start = road.position_on(*next, 0.0);
+                assert!(
+                    end.distance(start) < 1e-3,
+                    "gap of {} m between {id} and {next}",
+                    end.distance(start)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_consecutive_segments_share_endpoints_after_finalize() {
+        for resolver in [YieldResolver::default(), YieldResolver::Roundabout] {
+            assert_continuous(&Road::cross_intersection(Vec3::ZERO, 50.0, 13.9, resolver));
+        }
+
+        // One-way road bending 90° at a plain node
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(100.0, 100.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+        road.finalize();
+
+        assert_continuous(&road);
+        let corner = road.segments.get(&ab).to;
+        assert_eq!(road.segments.get(&bc).from, corner);
+        // Mitered: still one lane offset from both center lines
+        let position = road.nodes.get(&corner).position;
+        assert!((position - Vec3::new(100.0 + 1.75, -1.75, 0.0)).length() < 1e-4);
+
+        let from = road.segments.get(&ab).from;
+        let to = road.segments.get(&bc).to;
+        let (_, route) = next_segment_toward(&road, from, to).unwrap();
+        assert_eq!(route, vec![ab, bc]);
+    }
+
+    /// Roundabout of `radius` meters at the origin with arms 60 m out to the north, east,
+    /// south and west; returns the road and the arm positions
+    fn roundabout(radius: f32) -> (Road, [Vec3; 4]) {
+        let mut road = Road::default();
+        let positions = [Vec3::Y, Vec3::X, Vec3::NEG_Y, Vec3::NEG_X].map(|d| d * 60.0);
+        let arms = positions.map(|position| road.add_edge_node(position));
+        road.add_roundabout(Vec3::ZERO, radius, arms.to_vec(), YieldResolver::Roundabout);
+        road.finalize();
+        (road, positions)
+    }
+
+    #[test]
+    fn test_roundabout_ring_is_one_way() {
+        let (road, _) = roundabout(15.0);
+        assert_continuous(&road);
+
+        let ring: Vec<_> = road
+            .segments
+            .iter()
+            .filter(|segment| segment.turn_type == TurnType::RoundaboutCircle)
+            .collect();
+        assert_eq!(ring.len(), 4);
+
+        for segment in &ring {
+            let from = road.nodes.get(&segment.from).position;
+            let to = road.nodes.get(&segment.to).position;
+            assert!((from.length() - 15.0).abs() < 1e-3);
+            assert!(matches!(
+                segment.geometry,
+                SegmentGeometry::Curved {
+                    clockwise: false,
+                    ..
+                }
+            ));
+            // Counter-clockwise, and nothing on the ring runs the other way
+            assert!(from.cross(to).z > 0.0);
+            assert!(!ring
+                .iter()
+                .any(|other| other.from == segment.to && other.to == segment.from));
+            assert_eq!(
+                ring.iter().filter(|other| other.to == segment.from).count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn test_roundabout_arms_connect_through_ring() {
+        let (road, [north, east, south, west]) = roundabout(15.0);
+        let lane_node = |arm: Vec3, outbound: bool| {
+            road.nodes
+                .iter_with_ids()
+                .find(|(_, node)| {
+                    let links = if outbound {
+                        &node.outgoing
+                    } else {
+                        &node.incoming
+                    };
+                    node.position.distance(arm) < 3.0 && !links.is_empty()
+                })
+                .map(|(id, _)| id)
+                .unwrap()
+        };
+
+        // Counter-clockwise from the south: the east arm is the first exit, then north, then west
+        let from = lane_node(south, true);
+        let circulated: Vec<usize> = [east, north, west]
+            .into_iter()
+            .map(|arm| {
+                let (_, route) = next_segment_toward(&road, from, lane_node(arm, false)).unwrap();
+                let turns: Vec<_> = route
+                    .iter()
+                    .map(|id| road.segments.get(id).turn_type)
+                    .collect();
+                assert_eq!(turns[1], TurnType::RoundaboutEntry);
+                assert_eq!(turns[turns.len() - 2], TurnType::RoundaboutExit);
+                turns
+                    .iter()
+                    .filter(|turn| **turn == TurnType::RoundaboutCircle)
+                    .count()
+            })
+            .collect();
+        assert_eq!(circulated, [0, 1, 2]);
+
+        // Only merges into the ring need a gap
+        let intersection = road.intersections.iter().next().unwrap();
+        for id in &intersection.incoming {
+            let segment = road.segments.get(id);
+            if segment.turn_type == TurnType::RoundaboutEntry {
+                assert!(segment.yield_required);
             }
         }
     }
 
-    false
+    #[test]
+    fn test_move_node_updates_straight_lengths() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(30.0, 0.0, 0.0));
+        let c = road.add_node(Vec3::new(30.0, 50.0, 0.0));
+        let (ab, ba) = road.add_bidirectional(a, b, speed::URBAN);
+        let bc = road.add_segment(b, c, speed::URBAN);
+
+        road.move_node(b, Vec3::new(30.0, 40.0, 0.0));
+
+        assert!((road.segments.get(&ab).length - 50.0).abs() < 1e-4);
+        assert!((road.segments.get(&ba).length - 50.0).abs() < 1e-4);
+        assert!((road.segments.get(&bc).length - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_move_node_refits_curve_keeping_fixed_tangent() {
+        let mut road = plus_junction(20.0);
+        let (id, _) = road
+            .segments
+            .iter_with_ids()
+            .find(|(_, s)| matches!(s.geometry, SegmentGeometry::Curved { .. }))
+            .unwrap();
+        let segment = road.segments.get(&id);
+        let (from, to) = (segment.from, segment.to);
+        let from_pos = road.nodes.get(&from).position;
+        let to_pos = road.nodes.get(&to).position;
+        let tangent = segment.geometry.direction_at(from_pos, to_pos, 0.0);
+
+        let moved = to_pos + Vec3::new(1.0, -0.5, 0.0);
+        road.move_node(to, moved);
+
+        let segment = road.segments.get(&id);
+        let new_tangent = segment.geometry.direction_at(from_pos, moved, 0.0);
+        assert!(new_tangent.distance(tangent) < 1e-3);
+        assert!(
+            segment
+                .geometry
+                .position_at(from_pos, moved, 1.0)
+                .distance(moved)
+                < 1e-3
+        );
+        assert!((segment.length - segment.geometry.length(from_pos, moved)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sample_includes_exact_endpoints() {
+        let from = Vec3::new(10.0, 0.0, 0.0);
+        let to = Vec3::new(0.0, 10.0, 0.0);
+
+        let straight = SegmentGeometry::Straight.sample(from, to, 4);
+        assert_eq!(straight.len(), 5);
+        assert_eq!(straight[0], from);
+        assert_eq!(straight[4], to);
+        // Collinear and evenly spaced
+        for (i, point) in straight.iter().enumerate() {
+            assert!((*point - from.lerp(to, i as f32 / 4.0)).length() < 1e-5);
+        }
+
+        // Quarter circle around the origin, counter-clockwise
+        let curved = SegmentGeometry::Curved {
+            center: Vec3::ZERO,
+            radius: 10.0,
+            clockwise: false,
+        };
+        let points = curved.sample_with_tangents(from, to, 8);
+        assert_eq!(points.len(), 9);
+        assert_eq!(points[0].0, from);
+        assert_eq!(points[8].0, to);
+        for (point, tangent) in &points {
+            assert!((point.length() - 10.0).abs() < 1e-3);
+            assert!(tangent.dot(*point).abs() < 1e-3);
+        }
+        assert!((points[0].1 - Vec3::Y).length() < 1e-3);
+        assert!((points[8].1 - Vec3::NEG_X).length() < 1e-3);
+
+        // Zero samples still yields both endpoints
+        assert_eq!(curved.sample(from, to, 0), vec![from, to]);
+    }
+
+    #[test]
+    fn test_bezier_length_matches_known_shapes() {
+        // Control points on the line: a straight segment in disguise
+        let from = Vec3::ZERO;
+        let to = Vec3::new(90.0, 0.0, 0.0);
+        let straight = SegmentGeometry::Bezier {
+            control1: Vec3::new(30.0, 0.0, 0.0),
+            control2: Vec3::new(60.0, 0.0, 0.0),
+        };
+        assert!((straight.length(from, to) - 90.0).abs() < 1e-3);
+        assert!((straight.position_at(from, to, 0.5) - Vec3::new(45.0, 0.0, 0.0)).length() < 0.05);
+
+        // Standard cubic approximation of a quarter circle of radius 10
+        const K: f32 = 0.552_284_8;
+        let from = Vec3::new(10.0, 0.0, 0.0);
+        let to = Vec3::new(0.0, 10.0, 0.0);
+        let quarter = SegmentGeometry::Bezier {
+            control1: Vec3::new(10.0, 10.0 * K, 0.0),
+            control2: Vec3::new(10.0 * K, 10.0, 0.0),
+        };
+        let arc = std::f32::consts::FRAC_PI_2 * 10.0;
+        assert!((quarter.length(from, to) - arc).abs() < arc * 1e-3);
+        assert!((quarter.direction_at(from, to, 0.0) - Vec3::Y).length() < 1e-3);
+        assert!((quarter.direction_at(from, to, 1.0) - Vec3::NEG_X).length() < 1e-3);
+
+        // Its tightest point bends about as sharply as the circle
+        let cornering = quarter.max_comfortable_speed(from, to, 2.0).unwrap();
+        assert!((cornering - 20f32.sqrt()).abs() < 0.1, "{cornering}");
+        assert_eq!(
+            straight.max_comfortable_speed(Vec3::ZERO, Vec3::new(90.0, 0.0, 0.0), 2.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bezier_progress_advances_evenly() {
+        // Lopsided S-curve whose raw parameter bunches up near the start
+        let from = Vec3::ZERO;
+        let to = Vec3::new(100.0, 0.0, 0.0);
+        let curve = SegmentGeometry::Bezier {
+            control1: Vec3::new(5.0, 40.0, 0.0),
+            control2: Vec3::new(90.0, -40.0, 0.0),
+        };
+        let length = curve.length(from, to);
+
+        let points = curve.sample(from, to, 50);
+        let steps: Vec<f32> = points.windows(2).map(|p| p[0].distance(p[1])).collect();
+        for step in &steps {
+            assert!(
+                (step - length / 50.0).abs() < length / 50.0 * 0.05,
+                "{step}"
+            );
+        }
+        let travelled: f32 = steps.iter().sum();
+        assert!((travelled - length).abs() < length * 1e-3);
+    }
+
+    #[test]
+    fn test_bank_angle_grows_with_speed_and_shrinks_with_radius() {
+        assert!(bank_angle(8.0, 20.0) > bank_angle(5.0, 20.0));
+        assert!(bank_angle(5.0, 40.0) < bank_angle(5.0, 20.0));
+        assert_eq!(bank_angle(0.0, 20.0), 0.0);
+        assert_eq!(bank_angle(50.0, 5.0), MAX_BANK_ANGLE);
+
+        let curve = |clockwise| SegmentGeometry::Curved {
+            center: Vec3::ZERO,
+            radius: 20.0,
+            clockwise,
+        };
+        assert_eq!(SegmentGeometry::Straight.bank_angle(10.0), 0.0);
+        assert!(curve(true).bank_angle(5.0) > 0.0);
+        assert_eq!(curve(false).bank_angle(5.0), -curve(true).bank_angle(5.0));
+    }
+
+    #[test]
+    fn test_names_show_up_in_debug_dump() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let main = road.add_segment(a, b, 13.9);
+        road.add_segment(b, c, 13.9);
+        road.name_segment(main, "Main Street");
+        road.name_node(b, "Market Square");
+        road.finalize();
+
+        let dump = road.debug_dump();
+        assert!(dump.contains(&format!("{main:?} \"Main Street\"")));
+        assert!(dump.contains("\"Market Square\""));
+        assert_eq!(
+            road.segment_label(main),
+            format!("{main:?} \"Main Street\"")
+        );
+        assert_eq!(road.node_label(a), format!("{a:?}"));
+    }
+
+    #[test]
+    fn test_turn_speeds_follow_movement_type() {
+        let road = plus_junction(50.0);
+        let intersection = road.intersections.iter().next().unwrap();
+        let limits = |matches: fn(&TurnType) -> bool| -> Vec<f32> {
+            intersection
+                .incoming
+                .iter()
+                .map(|id| road.segments.get(id))
+                .filter(|s| matches(&s.turn_type))
+                .map(|s| s.speed_limit)
+                .collect()
+        };
+
+        let config = FinalizeConfig::default();
+        let lefts = limits(|t| matches!(t, TurnType::Left(_)));
+        let throughs = limits(|t| matches!(t, TurnType::Straight));
+        assert!(lefts.iter().all(|&l| l == config.left_turn_speed));
+        assert!(throughs.iter().all(|&l| l == config.through_speed));
+        assert!(config.left_turn_speed < config.right_turn_speed);
+        assert!(config.right_turn_speed < config.through_speed);
+
+        // Custom speeds are applied
+        let mut road = Road::default();
+        let center = road.add_intersection_node(Vec3::ZERO, YieldResolver::default());
+        for direction in [Vec3::Y, Vec3::X, Vec3::NEG_Y, Vec3::NEG_X] {
+            let edge = road.add_edge_node(direction * 50.0);
+            road.add_bidirectional(edge, center, speed::URBAN);
+        }
+        road.finalize_with(&FinalizeConfig {
+            left_turn_speed: 3.0,
+            ..FinalizeConfig::default()
+        });
+        let intersection = road.intersections.iter().next().unwrap();
+        assert!(intersection
+            .incoming
+            .iter()
+            .map(|id| road.segments.get(id))
+            .filter(|s| matches!(s.turn_type, TurnType::Left(_)))
+            .all(|s| s.speed_limit == 3.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_finalized_grid_survives_serialization() {
+        let road = Road::generate_grid(3, 3, 100.0, speed::URBAN, YieldResolver::default());
+
+        let text = serde_json::to_string(&road).unwrap();
+        let restored: Road = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(restored.nodes.len(), road.nodes.len());
+        assert_eq!(restored.segments.len(), road.segments.len());
+        assert_eq!(restored.intersections.len(), road.intersections.len());
+        for (id, node) in road.nodes.iter_with_ids() {
+            let copy = restored.nodes.get(&id);
+            assert_eq!(copy.position, node.position);
+            assert_eq!(copy.incoming, node.incoming);
+            assert_eq!(copy.outgoing, node.outgoing);
+        }
+        for (id, segment) in road.segments.iter_with_ids() {
+            let copy = restored.segments.get(&id);
+            assert_eq!((copy.from, copy.to), (segment.from, segment.to));
+            assert_eq!(copy.turn_type, segment.turn_type);
+            assert_eq!(copy.length, segment.length);
+            assert_eq!(copy.yield_required, segment.yield_required);
+        }
+        for (original, copy) in road.intersections.iter().zip(restored.intersections.iter()) {
+            assert_eq!(copy.conflicts, original.conflicts);
+            assert!(copy.yield_resolver == original.yield_resolver);
+        }
+
+        // Routes across the grid come out the same without finalizing again
+        let start = road
+            .nodes
+            .iter_with_ids()
+            .find(|(_, node)| node.is_spawn && !node.outgoing.is_empty())
+            .unwrap()
+            .0;
+        let end = road
+            .nodes
+            .iter_with_ids()
+            .filter(|(_, node)| node.is_despawn && !node.incoming.is_empty())
+            .last()
+            .unwrap()
+            .0;
+        let route = next_segment_toward(&road, start, end);
+        assert!(route.is_some());
+        assert_eq!(next_segment_toward(&restored, start, end), route);
+    }
+
+    #[test]
+    fn test_generated_grid_counts() {
+        // (intersections, spawn points, straight segments) for each size; every junction
+        // has four arms and so twelve turn segments
+        for (rows, cols, links) in [(1, 1, 4), (2, 2, 12), (3, 3, 24)] {
+            let road =
+                Road::generate_grid(rows, cols, 100.0, speed::URBAN, YieldResolver::default());
+            let junctions = rows * cols;
+            let perimeter = 2 * (rows + cols);
+            assert_eq!(road.intersections.len(), junctions);
+
+            let spawns = road
+                .nodes
+                .iter()
+                .filter(|node| node.is_spawn && !node.outgoing.is_empty())
+                .count();
+            assert_eq!(spawns, perimeter);
+
+            let turns = road
+                .intersections
+                .iter()
+                .map(|intersection| intersection.incoming.len())
+                .sum::<usize>();
+            assert_eq!(turns, junctions * 12);
+            assert_eq!(road.segments.len(), turns + 2 * links);
+        }
+
+        assert!(
+            Road::generate_grid(0, 3, 100.0, speed::URBAN, YieldResolver::default())
+                .nodes
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_bounds_cover_generated_grid() {
+        // Junctions at x 0..200 and y 0..100, edge nodes one spacing further out
+        let road = Road::generate_grid(2, 3, 100.0, speed::URBAN, YieldResolver::default());
+        let (min, max) = road.bounds().unwrap();
+        // Finalize splits edge nodes sideways by half a lane
+        assert!((min - Vec3::new(-100.0, -100.0, 0.0)).abs().max_element() <= LANE_WIDTH / 2.0);
+        assert!((max - Vec3::new(300.0, 200.0, 0.0)).abs().max_element() <= LANE_WIDTH / 2.0);
+        for node in road.nodes.iter() {
+            assert!(node.position.cmpge(min).all() && node.position.cmple(max).all());
+        }
+
+        assert_eq!(Road::default().bounds(), None);
+    }
 }
@@ -0,0 +1,471 @@
+//! Headless runs of a whole experiment described in a single RON file.
+//!
+//! A scenario lists the authored road (finalized on load), which nodes spawn traffic and
+//! how heavily, fixed-rate spawners, origin-destination weights, the RNG seed and how long
+//! to run. Running it returns the final statistics.
+//!
+//! [`Road::save_scenario`] and [`Road::load_scenario`] store a whole network as built in
+//! code as JSON instead, so layouts can be shared without recompiling.
+
+use std::{fmt, path::Path};
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    driver::YieldResolver, headless, Id, Node, OdMatrix, Road, Segment, SimRng, SimulationStats,
+    SpawnDistribution, VehicleSpawner,
+};
+
+/// Default simulation step in seconds
+pub const SCENARIO_STEP: f32 = 0.05;
+
+/// Format version written by [`Road::save_scenario`]; other versions are rejected on load
+pub const ROAD_FILE_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scenario {
+    pub road: RoadDefinition,
+    /// Spawners on individual segments, in addition to the spawn nodes
+    #[serde(default)]
+    pub spawners: Vec<SpawnerDefinition>,
+    /// Relative number of trips between spawn and despawn nodes (see [`OdMatrix`]); when
+    /// empty, every reachable destination is equally likely
+    #[serde(default)]
+    pub trips: Vec<TripDefinition>,
+    #[serde(default)]
+    pub seed: u64,
+    /// Simulated seconds to run
+    pub duration: f32,
+    /// Seconds per step
+    #[serde(default = "default_step")]
+    pub step: f32,
+}
+
+/// Authored road network; nodes are referenced by their index in `nodes`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RoadDefinition {
+    pub nodes: Vec<NodeDefinition>,
+    pub segments: Vec<SegmentDefinition>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeDefinition {
+    pub position: Vec3,
+    /// Whether vehicles spawn here
+    #[serde(default)]
+    pub spawn: bool,
+    /// Whether vehicles may end their trip here
+    #[serde(default)]
+    pub despawn: bool,
+    /// Relative spawn rate of this node (1.0 = default)
+    #[serde(default = "default_spawn_weight")]
+    pub spawn_weight: f32,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Makes the node an intersection controlled by this resolver, e.g. a traffic light
+    #[serde(default)]
+    pub yield_resolver: Option<YieldResolver>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SegmentDefinition {
+    pub from: usize,
+    pub to: usize,
+    pub speed_limit: f32,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Spawner on the segment from node `from` to node `to`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpawnerDefinition {
+    pub from: usize,
+    pub to: usize,
+    /// Vehicles per second
+    pub rate: f32,
+    /// Speed of spawned vehicles; the spawner default if unset
+    #[serde(default)]
+    pub speed: Option<f32>,
+    #[serde(default)]
+    pub distribution: SpawnDistribution,
+}
+
+/// Weight of trips from node `origin` to node `destination`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TripDefinition {
+    pub origin: usize,
+    pub destination: usize,
+    pub weight: f32,
+}
+
+fn default_step() -> f32 {
+    SCENARIO_STEP
+}
+
+fn default_spawn_weight() -> f32 {
+    1.0
+}
+
+/// On-disk layout of a saved road network
+#[derive(Serialize)]
+struct RoadFile<'a> {
+    version: u32,
+    road: &'a Road,
+}
+
+/// Read before the road itself so files from another version fail cleanly
+#[derive(Deserialize)]
+struct RoadFileHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct LoadedRoadFile {
+    road: Road,
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// A road file that isn't valid JSON, or couldn't be written as JSON
+    Json(serde_json::Error),
+    /// A segment references a node index that doesn't exist
+    UnknownNode(usize),
+    /// A spawner sits on a segment between these nodes that doesn't exist
+    UnknownSegment(usize, usize),
+    /// A road file written by an incompatible version
+    UnsupportedVersion(u32),
+    /// The node at this index isn't controlled by a traffic light
+    NotSignalized(usize),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "could not read scenario: {error}"),
+            ScenarioError::Parse(error) => write!(f, "invalid scenario: {error}"),
+            ScenarioError::Serialize(error) => write!(f, "could not write scenario: {error}"),
+            ScenarioError::Json(error) => write!(f, "invalid road file: {error}"),
+            ScenarioError::UnknownNode(index) => {
+                write!(f, "segment references unknown node {index}")
+            }
+            ScenarioError::UnknownSegment(from, to) => {
+                write!(f, "no segment from node {from} to node {to}")
+            }
+            ScenarioError::UnsupportedVersion(version) => write!(
+                f,
+                "road file version {version} is not supported (expected {ROAD_FILE_VERSION})"
+            ),
+            ScenarioError::NotSignalized(index) => {
+                write!(f, "node {index} is not controlled by a traffic light")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl RoadDefinition {
+    /// Build and finalize the road
+    pub fn build(&self) -> Result<Road, ScenarioError> {
+        self.build_indexed().map(|(road, _)| road)
+    }
+
+    /// Build and finalize the road, along with the id given to each segment
+    fn build_indexed(&self) -> Result<(Road, Vec<Id<Segment>>), ScenarioError> {
+        let mut road = Road::default();
+        let nodes: Vec<Id<Node>> = self
+            .nodes
+            .iter()
+            .map(|definition| {
+                let id = road.add_node(definition.position);
+                let node = road.nodes.get_mut(&id);
+                node.is_spawn = definition.spawn;
+                node.is_despawn = definition.despawn;
+                node.spawn_weight = definition.spawn_weight;
+                node.name = definition.name.clone();
+                node.yield_resolver = definition.yield_resolver;
+                id
+            })
+            .collect();
+
+        let node = |index: usize| {
+            nodes
+                .get(index)
+                .copied()
+                .ok_or(ScenarioError::UnknownNode(index))
+        };
+        let mut segments = Vec::with_capacity(self.segments.len());
+        for segment in &self.segments {
+            let id = road.add_segment(node(segment.from)?, node(segment.to)?, segment.speed_limit);
+            road.segments.get_mut(&id).name = segment.name.clone();
+            segments.push(id);
+        }
+
+        road.finalize();
+        Ok((road, segments))
+    }
+}
+
+impl Scenario {
+    pub fn from_ron(source: &str) -> Result<Self, ScenarioError> {
+        ron::from_str(source).map_err(ScenarioError::Parse)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let source = std::fs::read_to_string(path).map_err(ScenarioError::Io)?;
+        Self::from_ron(&source)
+    }
+
+    /// Simulate the scenario for its full duration without rendering
+    pub fn run(&self) -> Result<SimulationStats, ScenarioError> {
+        let (road, segments) = self.road.build_indexed()?;
+        let od = self.od_matrix(&road, &segments)?;
+
+        let mut app = headless::app(road);
+        app.insert_resource(SimRng::seeded(self.seed));
+
+        for spawner in &self.spawners {
+            let segment = self
+                .road
+                .segments
+                .iter()
+                .position(|segment| segment.from == spawner.from && segment.to == spawner.to)
+                .map(|index| segments[index])
+                .ok_or(ScenarioError::UnknownSegment(spawner.from, spawner.to))?;
+            let mut component =
+                VehicleSpawner::new(segment, spawner.rate).with_distribution(spawner.distribution);
+            if let Some(speed) = spawner.speed {
+                component = component.with_speed(speed);
+            }
+            app.world_mut().spawn(component);
+        }
+
+        if let Some(od) = od {
+            app.insert_resource(od);
+        }
+
+        let steps = (self.duration / self.step.max(0.001)).ceil() as u64;
+        headless::advance(&mut app, steps, self.step);
+
+        Ok(app
+            .world_mut()
+            .remove_resource::<SimulationStats>()
+            .unwrap_or_default())
+    }
+
+    /// The trip weights between the finalized nodes, if any are given. Finalizing replaces
+    /// each authored node with per-lane ones, so trips start at the nodes its segments now
+    /// leave from and end at those its segments now arrive at.
+    fn od_matrix(
+        &self,
+        road: &Road,
+        segments: &[Id<Segment>],
+    ) -> Result<Option<OdMatrix>, ScenarioError> {
+        if self.trips.is_empty() {
+            return Ok(None);
+        }
+
+        let ends = |index: usize, departing: bool| {
+            if index >= self.road.nodes.len() {
+                return Err(ScenarioError::UnknownNode(index));
+            }
+            Ok(self
+                .road
+                .segments
+                .iter()
+                .zip(segments)
+                .filter_map(|(definition, id)| {
+                    let segment = road.segments.get(id);
+                    match departing {
+                        true => (definition.from == index).then_some(segment.from),
+                        false => (definition.to == index).then_some(segment.to),
+                    }
+                })
+                .collect::<Vec<Id<Node>>>())
+        };
+
+        let mut od = OdMatrix::default();
+        for trip in &self.trips {
+            let destinations = ends(trip.destination, false)?;
+            for origin in ends(trip.origin, true)? {
+                for &destination in &destinations {
+                    od.set(origin, destination, trip.weight);
+                }
+            }
+        }
+        Ok(Some(od))
+    }
+}
+
+impl Road {
+    /// Write the whole network to a JSON file. Save before [`Road::finalize`] to keep the
+    /// file editable; a finalized road loads ready to simulate.
+    pub fn save_scenario(&self, path: impl AsRef<Path>) -> Result<(), ScenarioError> {
+        let file = RoadFile {
+            version: ROAD_FILE_VERSION,
+            road: self,
+        };
+        let text = serde_json::to_string_pretty(&file).map_err(ScenarioError::Json)?;
+        std::fs::write(path, text).map_err(ScenarioError::Io)
+    }
+
+    /// Read a network written by [`Road::save_scenario`], finalizing it if `finalize` is set.
+    /// Only ask for that on files saved before finalizing.
+    pub fn load_scenario(path: impl AsRef<Path>, finalize: bool) -> Result<Road, ScenarioError> {
+        let text = std::fs::read_to_string(path).map_err(ScenarioError::Io)?;
+
+        let header: RoadFileHeader = serde_json::from_str(&text).map_err(ScenarioError::Json)?;
+        if header.version != ROAD_FILE_VERSION {
+            return Err(ScenarioError::UnsupportedVersion(header.version));
+        }
+
+        let mut road = serde_json::from_str::<LoadedRoadFile>(&text)
+            .map_err(ScenarioError::Json)?
+            .road;
+        if finalize {
+            road.finalize();
+        }
+        Ok(road)
+    }
+}
+
+/// Load a scenario file and run it headless, returning the final statistics
+pub fn run_scenario(path: impl AsRef<Path>) -> Result<SimulationStats, ScenarioError> {
+    Scenario::load(path)?.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::speed;
+
+    const CORRIDOR: &str = r#"(
+        seed: 7,
+        duration: 60.0,
+        road: (
+            nodes: [
+                (position: (0.0, 0.0, 0.0)),
+                (position: (100.0, 0.0, 0.0)),
+                (position: (200.0, 0.0, 0.0), despawn: true),
+            ],
+            segments: [
+                (from: 0, to: 1, speed_limit: 13.9),
+                (from: 1, to: 2, speed_limit: 13.9),
+            ],
+        ),
+        spawners: [
+            (from: 0, to: 1, rate: 0.5, speed: Some(10.0), distribution: Poisson),
+        ],
+    )"#;
+
+    #[test]
+    fn test_embedded_scenario_completes_trips() {
+        let scenario = Scenario::from_ron(CORRIDOR).unwrap();
+        assert_eq!(scenario.step, SCENARIO_STEP);
+
+        let stats = scenario.run().unwrap();
+        assert!(stats.completed_trips > 0);
+        assert!(stats.tick > 0);
+    }
+
+    #[test]
+    fn test_trips_weight_destinations() {
+        let mut scenario = Scenario::from_ron(CORRIDOR).unwrap();
+        scenario.spawners.clear();
+        scenario.road.nodes[0].spawn = true;
+        scenario.trips = vec![TripDefinition {
+            origin: 0,
+            destination: 2,
+            weight: 0.0,
+        }];
+        assert_eq!(scenario.run().unwrap().completed_trips, 0);
+
+        scenario.trips[0].weight = 1.0;
+        assert!(scenario.run().unwrap().completed_trips > 0);
+    }
+
+    #[test]
+    fn test_spawner_on_missing_segment_is_rejected() {
+        let mut scenario = Scenario::from_ron(CORRIDOR).unwrap();
+        scenario.spawners[0].to = 2;
+        assert!(matches!(
+            scenario.run(),
+            Err(ScenarioError::UnknownSegment(0, 2))
+        ));
+    }
+
+    /// Unfinalized plus junction with four two-way arms
+    fn authored_junction() -> Road {
+        let mut road = Road::default();
+        let junction = road.add_intersection_node(Vec3::ZERO, YieldResolver::default());
+        for direction in [Vec3::Y, Vec3::X, Vec3::NEG_Y, Vec3::NEG_X] {
+            let edge = road.add_edge_node(direction * 80.0);
+            road.add_bidirectional(edge, junction, 13.9);
+        }
+        road
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}.json", std::process::id()))
+    }
+
+    #[test]
+    fn test_saved_road_reloads_and_finalizes() {
+        let path = temp_path("saved-road");
+        let authored = authored_junction();
+        authored.save_scenario(&path).unwrap();
+
+        let mut finalized = authored;
+        finalized.finalize();
+        let loaded = Road::load_scenario(&path, true).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.nodes.len(), finalized.nodes.len());
+        assert_eq!(loaded.segments.len(), finalized.segments.len());
+        assert_eq!(loaded.intersections.len(), 1);
+        for (id, node) in finalized.nodes.iter_with_ids() {
+            assert_eq!(loaded.nodes.get(&id).outgoing, node.outgoing);
+        }
+    }
+
+    #[test]
+    fn test_finalized_grid_round_trips_through_json_file() {
+        let path = temp_path("saved-grid");
+        let grid = Road::generate_grid(3, 3, 100.0, speed::URBAN, YieldResolver::default());
+        grid.save_scenario(&path).unwrap();
+        let loaded = Road::load_scenario(&path, false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.nodes.len(), grid.nodes.len());
+        assert_eq!(loaded.segments.len(), grid.segments.len());
+        assert_eq!(loaded.intersections.len(), grid.intersections.len());
+        for (original, copy) in grid.intersections.iter().zip(loaded.intersections.iter()) {
+            assert_eq!(copy.conflicts, original.conflicts);
+            assert_eq!(copy.lane_movements, original.lane_movements);
+        }
+    }
+
+    #[test]
+    fn test_other_road_file_versions_are_rejected() {
+        let path = temp_path("future-road");
+        authored_junction().save_scenario(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let version = format!("\"version\": {ROAD_FILE_VERSION}");
+        std::fs::write(&path, text.replacen(&version, "\"version\": 99", 1)).unwrap();
+
+        let result = Road::load_scenario(&path, false);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ScenarioError::UnsupportedVersion(99))));
+    }
+
+    #[test]
+    fn test_unknown_node_is_rejected() {
+        let mut scenario = Scenario::from_ron(CORRIDOR).unwrap();
+        scenario.road.segments[0].to = 9;
+        assert!(matches!(scenario.run(), Err(ScenarioError::UnknownNode(9))));
+    }
+}
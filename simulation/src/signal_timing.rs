@@ -0,0 +1,166 @@
+//! Offline tuning of traffic light timings.
+//!
+//! [`optimize_signal`] runs a scenario headless once per candidate timing and keeps the
+//! green split with the lowest [`crate::SimulationStats::total_delay`]: time lost against
+//! free-flow travel, counting vehicles still queued at the end of the run as well as
+//! completed trips, so a timing can't look good by starving an approach.
+
+use crate::{driver::YieldResolver, Scenario, ScenarioError};
+
+/// Shortest green (s) the search tries for either phase
+pub const MIN_GREEN: f32 = 5.0;
+
+/// Smallest change (s) to a green time still worth a run
+const MIN_STEP: f32 = 1.0;
+
+/// Green times of a two-phase [`YieldResolver::TrafficLight`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhasePlan {
+    /// Seconds of green for the north-south approaches
+    pub north_south: f32,
+    /// Seconds of green for the east-west approaches
+    pub east_west: f32,
+}
+
+/// Search the green split of the traffic light at node `intersection` (an index into the
+/// scenario's nodes) for the lowest total delay, trying at most `iterations` timings
+/// besides the scenario's own. Every candidate is a full run of the scenario with the
+/// scenario's seed, so all timings face the same traffic and the result is reproducible.
+pub fn optimize_signal(
+    intersection: usize,
+    scenario: &Scenario,
+    iterations: usize,
+) -> Result<PhasePlan, ScenarioError> {
+    let mut best = phase_plan(scenario, intersection)?;
+    let mut best_delay = with_plan(scenario, intersection, best).run()?.total_delay();
+    let mut step = (best.north_south + best.east_west) / 4.0;
+    let mut evaluated = 0;
+
+    // Pattern search: lengthen or shorten one phase at a time, halving the step once
+    // no move improves on the best timing so far
+    while evaluated < iterations && step >= MIN_STEP {
+        let moves = [(step, 0.0), (-step, 0.0), (0.0, step), (0.0, -step)];
+        let mut improved = false;
+        for (north_south, east_west) in moves {
+            let candidate = PhasePlan {
+                north_south: (best.north_south + north_south).max(MIN_GREEN),
+                east_west: (best.east_west + east_west).max(MIN_GREEN),
+            };
+            if candidate == best {
+                continue;
+            }
+            if evaluated == iterations {
+                break;
+            }
+            evaluated += 1;
+
+            let candidate_delay = with_plan(scenario, intersection, candidate)
+                .run()?
+                .total_delay();
+            if candidate_delay < best_delay {
+                best = candidate;
+                best_delay = candidate_delay;
+                improved = true;
+                break;
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Timing of the traffic light at node `intersection`
+fn phase_plan(scenario: &Scenario, intersection: usize) -> Result<PhasePlan, ScenarioError> {
+    match scenario
+        .road
+        .nodes
+        .get(intersection)
+        .and_then(|node| node.yield_resolver)
+    {
+        Some(YieldResolver::TrafficLight {
+            phase_duration,
+            east_west_duration,
+            ..
+        }) => Ok(PhasePlan {
+            north_south: phase_duration,
+            east_west: east_west_duration.unwrap_or(phase_duration),
+        }),
+        _ => Err(ScenarioError::NotSignalized(intersection)),
+    }
+}
+
+/// The scenario with the light at node `intersection` running `plan`
+fn with_plan(scenario: &Scenario, intersection: usize, plan: PhasePlan) -> Scenario {
+    let mut scenario = scenario.clone();
+    let node = &mut scenario.road.nodes[intersection];
+    if let Some(YieldResolver::TrafficLight { offset, .. }) = node.yield_resolver {
+        node.yield_resolver = Some(YieldResolver::TrafficLight {
+            phase_duration: plan.north_south,
+            offset,
+            east_west_duration: Some(plan.east_west),
+        });
+    }
+    scenario
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signalized junction (node 0) with busy north and south arms and quiet east and west
+    /// ones, starting from a timing that starves the busy arms
+    const LOPSIDED_JUNCTION: &str = r#"(
+        seed: 3,
+        duration: 120.0,
+        road: (
+            nodes: [
+                (
+                    position: (0.0, 0.0, 0.0),
+                    yield_resolver: Some(TrafficLight(
+                        phase_duration: 5.0,
+                        offset: 0.0,
+                        east_west_duration: Some(40.0),
+                    )),
+                ),
+                (position: (0.0, 100.0, 0.0), spawn: true, despawn: true, spawn_weight: 0.04),
+                (position: (100.0, 0.0, 0.0), spawn: true, despawn: true, spawn_weight: 0.008),
+                (position: (0.0, -100.0, 0.0), spawn: true, despawn: true, spawn_weight: 0.04),
+                (position: (-100.0, 0.0, 0.0), spawn: true, despawn: true, spawn_weight: 0.008),
+            ],
+            segments: [
+                (from: 1, to: 0, speed_limit: 13.9),
+                (from: 0, to: 1, speed_limit: 13.9),
+                (from: 2, to: 0, speed_limit: 13.9),
+                (from: 0, to: 2, speed_limit: 13.9),
+                (from: 3, to: 0, speed_limit: 13.9),
+                (from: 0, to: 3, speed_limit: 13.9),
+                (from: 4, to: 0, speed_limit: 13.9),
+                (from: 0, to: 4, speed_limit: 13.9),
+            ],
+        ),
+    )"#;
+
+    #[test]
+    fn test_optimizer_beats_bad_initial_timing() {
+        let scenario = Scenario::from_ron(LOPSIDED_JUNCTION).unwrap();
+        let initial = phase_plan(&scenario, 0).unwrap();
+
+        let optimized = optimize_signal(0, &scenario, 4).unwrap();
+        // The busy arms get more of the cycle
+        assert!(optimized.north_south > initial.north_south);
+        assert!(optimized.north_south >= MIN_GREEN && optimized.east_west >= MIN_GREEN);
+
+        let delay_with = |plan| with_plan(&scenario, 0, plan).run().unwrap().total_delay();
+        let (before, after) = (delay_with(initial), delay_with(optimized));
+        assert!(after < before, "delay {after:.1} s, was {before:.1} s");
+
+        // Only traffic lights can be tuned
+        assert!(matches!(
+            optimize_signal(1, &scenario, 8),
+            Err(ScenarioError::NotSignalized(1))
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+//! Uniform grids for fast spatial queries: [`SegmentGrid`] over segment bounding boxes, and
+//! [`SpatialHash`] over vehicle positions.
+//!
+//! Units:
+//! - Distance/Position: meters (m)
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::prelude::*;
+use glam::Vec3;
+
+use crate::{
+    driver::{FreeDrive, Vehicle},
+    Id, Road, Segment, SegmentGeometry,
+};
+
+/// Default grid cell size in meters
+pub const DEFAULT_CELL_SIZE: f32 = 25.0;
+
+/// Default [`SpatialHash`] cell size in meters, a couple of car lengths
+pub const DEFAULT_VEHICLE_CELL_SIZE: f32 = 10.0;
+
+/// Extra margin around sampled bounding boxes so arcs between samples are still covered
+const BOUNDS_MARGIN: f32 = 1.0;
+
+pub struct SegmentGrid {
+    pub cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Id<Segment>>>,
+    /// Min/max cell coordinates covered by any segment
+    extent: Option<((i32, i32), (i32, i32))>,
+}
+
+impl SegmentGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(0.01),
+            cells: HashMap::new(),
+            extent: None,
+        }
+    }
+
+    pub fn build(road: &Road, cell_size: f32) -> Self {
+        let mut grid = Self::new(cell_size);
+        for (id, _) in road.segments.iter_with_ids() {
+            grid.insert(road, id);
+        }
+        grid
+    }
+
+    /// Add a segment to every cell its bounding box touches
+    pub fn insert(&mut self, road: &Road, id: Id<Segment>) {
+        let (min, max) = segment_bounds(road, id);
+        let min_cell = self.cell_of(min);
+        let max_cell = self.cell_of(max);
+
+        for x in min_cell.0..=max_cell.0 {
+            for y in min_cell.1..=max_cell.1 {
+                self.cells.entry((x, y)).or_default().push(id);
+            }
+        }
+
+        self.extent = Some(match self.extent {
+            None => (min_cell, max_cell),
+            Some((lo, hi)) => (
+                (lo.0.min(min_cell.0), lo.1.min(min_cell.1)),
+                (hi.0.max(max_cell.0), hi.1.max(max_cell.1)),
+            ),
+        });
+    }
+
+    /// Segments in cells overlapping the axis-aligned region (XY only)
+    pub fn query_region(&self, min: Vec3, max: Vec3) -> Vec<Id<Segment>> {
+        let min_cell = self.cell_of(min);
+        let max_cell = self.cell_of(max);
+
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+        for x in min_cell.0..=max_cell.0 {
+            for y in min_cell.1..=max_cell.1 {
+                for &id in self.cells.get(&(x, y)).into_iter().flatten() {
+                    if seen.insert(id) {
+                        result.push(id);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Candidate segments that may lie within `radius` of `point`
+    pub fn query_radius(&self, point: Vec3, radius: f32) -> Vec<Id<Segment>> {
+        let offset = Vec3::new(radius, radius, 0.0);
+        self.query_region(point - offset, point + offset)
+    }
+
+    /// Whether a square of half-size `radius` around `point` covers every occupied cell
+    fn covers_extent(&self, point: Vec3, radius: f32) -> bool {
+        let Some((lo, hi)) = self.extent else {
+            return true;
+        };
+        let offset = Vec3::new(radius, radius, 0.0);
+        let min_cell = self.cell_of(point - offset);
+        let max_cell = self.cell_of(point + offset);
+        min_cell.0 <= lo.0 && min_cell.1 <= lo.1 && max_cell.0 >= hi.0 && max_cell.1 >= hi.1
+    }
+
+    /// Closest segment to a point, searching outward ring by ring
+    pub fn nearest(&self, road: &Road, point: Vec3) -> Option<(Id<Segment>, f32, f32)> {
+        self.extent?;
+
+        // Grow the search square until it contains at least one candidate
+        let mut radius = self.cell_size;
+        let mut candidates = self.query_radius(point, radius);
+        while candidates.is_empty() {
+            if self.covers_extent(point, radius) {
+                return None;
+            }
+            radius *= 2.0;
+            candidates = self.query_radius(point, radius);
+        }
+
+        let best = closest_of(road, candidates.into_iter(), point)?;
+
+        // A closer segment may sit just outside the square but within the best distance
+        closest_of(road, self.query_radius(point, best.2).into_iter(), point)
+    }
+
+    fn cell_of(&self, position: Vec3) -> (i32, i32) {
+        cell_of(position, self.cell_size)
+    }
+}
+
+/// Vehicle positions bucketed into a uniform grid, rebuilt each tick by
+/// [`update_spatial_hash`]. Finds vehicles near a point without walking the road graph.
+#[derive(Resource, Clone, Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(Entity, Vec3)>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(DEFAULT_VEHICLE_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    /// Empty grid with cells `cell_size` meters wide. Queries cost roughly the number of
+    /// cells a radius spans plus the vehicles in them, so pick a size near the typical
+    /// query radius.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(0.01),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec3) {
+        self.cells
+            .entry(cell_of(position, self.cell_size))
+            .or_default()
+            .push((entity, position));
+    }
+
+    /// Vehicles within `radius` of `point` on the ground plane (XY only)
+    pub fn neighbors_within(&self, point: Vec3, radius: f32) -> Vec<Entity> {
+        let offset = Vec3::new(radius, radius, 0.0);
+        let min_cell = cell_of(point - offset, self.cell_size);
+        let max_cell = cell_of(point + offset, self.cell_size);
+
+        let mut result = Vec::new();
+        for x in min_cell.0..=max_cell.0 {
+            for y in min_cell.1..=max_cell.1 {
+                for &(entity, position) in self.cells.get(&(x, y)).into_iter().flatten() {
+                    if position.truncate().distance(point.truncate()) <= radius {
+                        result.push(entity);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Refills the [`SpatialHash`] with every vehicle on the road
+pub fn update_spatial_hash(
+    hash: Option<ResMut<SpatialHash>>,
+    road: Res<Road>,
+    vehicles: Query<(Entity, &Vehicle), Without<FreeDrive>>,
+) {
+    let Some(mut hash) = hash else {
+        return;
+    };
+    hash.clear();
+    for (entity, vehicle) in &vehicles {
+        if road.segments.get_checked(&vehicle.segment).is_none() {
+            continue;
+        }
+        let position = road.lane_position_on(vehicle.segment, vehicle.progress, vehicle.lane);
+        hash.insert(entity, position);
+    }
+}
+
+fn cell_of(position: Vec3, cell_size: f32) -> (i32, i32) {
+    (
+        (position.x / cell_size).floor() as i32,
+        (position.y / cell_size).floor() as i32,
+    )
+}
+
+/// Closest of the given segments to a point as (segment, progress, distance)
+pub(crate) fn closest_of(
+    road: &Road,
+    segments: impl Iterator<Item = Id<Segment>>,
+    point: Vec3,
+) -> Option<(Id<Segment>, f32, f32)> {
+    segments
+        .map(|id| {
+            let segment = road.segments.get(&id);
+            let from = road.nodes.get(&segment.from).position;
+            let to = road.nodes.get(&segment.to).position;
+            let progress = segment.geometry.closest_progress(from, to, point);
+            let distance = segment
+                .geometry
+                .position_at(from, to, progress)
+                .distance(point);
+            (id, progress, distance)
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2).then(a.0.id.cmp(&b.0.id)))
+}
+
+/// Axis-aligned bounding box of a segment's path
+pub(crate) fn segment_bounds(road: &Road, id: Id<Segment>) -> (Vec3, Vec3) {
+    const CURVE_SAMPLES: usize = 16;
+
+    let segment = road.segments.get(&id);
+    let from = road.nodes.get(&segment.from).position;
+    let to = road.nodes.get(&segment.to).position;
+
+    let steps = match segment.geometry {
+        SegmentGeometry::Straight => 1,
+        SegmentGeometry::Curved { .. } | SegmentGeometry::Bezier { .. } => CURVE_SAMPLES,
+    };
+
+    let mut min = from.min(to);
+    let mut max = from.max(to);
+    for p in segment.geometry.sample(from, to, steps) {
+        min = min.min(p);
+        max = max.max(p);
+    }
+
+    let margin = Vec3::splat(BOUNDS_MARGIN);
+    (min - margin, max + margin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{SidePriority, YieldResolver};
+
+    fn lattice(size: usize, spacing: f32) -> Road {
+        let mut road = Road::default();
+        let mut ids = vec![];
+        for y in 0..size {
+            for x in 0..size {
+                let position = Vec3::new(x as f32 * spacing, y as f32 * spacing, 0.0);
+                ids.push(road.add_intersection_node(
+                    position,
+                    YieldResolver::RightOfWay(SidePriority::RIGHT_HAND),
+                ));
+            }
+        }
+        for y in 0..size {
+            for x in 0..size {
+                let here = ids[y * size + x];
+                if x + 1 < size {
+                    road.add_bidirectional(here, ids[y * size + x + 1], 13.9);
+                }
+                if y + 1 < size {
+                    road.add_bidirectional(here, ids[(y + 1) * size + x], 13.9);
+                }
+            }
+        }
+        road.finalize();
+        road
+    }
+
+    #[test]
+    fn test_indexed_nearest_matches_brute_force() {
+        let mut road = lattice(6, 60.0);
+        let brute: Vec<_> = (0..40)
+            .flat_map(|i| (0..40).map(move |j| (i, j)))
+            .map(|(i, j)| Vec3::new(i as f32 * 8.3 - 20.0, j as f32 * 8.7 - 20.0, 0.0))
+            .map(|point| (point, road.nearest_segment(point).unwrap()))
+            .collect();
+
+        road.rebuild_spatial_index();
+
+        for (point, (brute_id, _, brute_distance)) in brute {
+            let (id, _, distance) = road.nearest_segment(point).unwrap();
+            assert!(
+                (distance - brute_distance).abs() < 1e-4,
+                "{point:?}: indexed {id:?} at {distance}, brute force {brute_id:?} at {brute_distance}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_updated_on_add_segment() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        road.add_segment(a, b, 13.9);
+        road.rebuild_spatial_index();
+
+        let c = road.add_node(Vec3::new(500.0, 500.0, 0.0));
+        let d = road.add_node(Vec3::new(600.0, 500.0, 0.0));
+        let far = road.add_segment(c, d, 13.9);
+
+        let (id, progress, distance) = road.nearest_segment(Vec3::new(550.0, 502.0, 0.0)).unwrap();
+        assert_eq!(id, far);
+        assert!((progress - 0.5).abs() < 1e-4);
+        assert!((distance - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_neighbors_within_returns_exactly_the_vehicles_in_range() {
+        let mut hash = SpatialHash::new(4.0);
+        let entity = |i| Entity::from_raw_u32(i).unwrap();
+        let layout = [
+            (1, Vec3::new(10.0, 10.0, 0.0)),  // the query point itself
+            (2, Vec3::new(13.0, 10.0, 0.0)),  // 3 m, same cell
+            (3, Vec3::new(10.0, 14.9, 0.0)),  // 4.9 m, next cell up
+            (4, Vec3::new(6.5, 6.5, 0.0)),    // ~4.95 m, diagonal cell
+            (5, Vec3::new(10.0, 5.0, 0.0)),   // exactly on the radius
+            (6, Vec3::new(14.0, 14.0, 0.0)),  // ~5.66 m, inside the search square only
+            (7, Vec3::new(10.0, 4.9, 0.0)),   // just outside
+            (8, Vec3::new(10.0, 10.0, 30.0)), // on a bridge overhead; height is ignored
+            (9, Vec3::new(-40.0, 60.0, 0.0)), // far away
+        ];
+        for (i, position) in layout {
+            hash.insert(entity(i), position);
+        }
+
+        let found: HashSet<_> = hash
+            .neighbors_within(Vec3::new(10.0, 10.0, 0.0), 5.0)
+            .into_iter()
+            .collect();
+        assert_eq!(found, HashSet::from([1, 2, 3, 4, 5, 8].map(entity)));
+
+        // Same answer whatever the cell size
+        let mut coarse = SpatialHash::new(50.0);
+        for (i, position) in layout {
+            coarse.insert(entity(i), position);
+        }
+        let found_coarse: HashSet<_> = coarse
+            .neighbors_within(Vec3::new(10.0, 10.0, 0.0), 5.0)
+            .into_iter()
+            .collect();
+        assert_eq!(found_coarse, found);
+
+        hash.clear();
+        assert!(hash
+            .neighbors_within(Vec3::new(10.0, 10.0, 0.0), 5.0)
+            .is_empty());
+    }
+}
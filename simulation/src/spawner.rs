@@ -1,7 +1,26 @@
+use std::collections::HashMap;
+
 use bevy_ecs::prelude::*;
 use bevy_time::Time;
 
-use crate::{driver::Idm, Id, Segment, Vehicle};
+use crate::{
+    driver::{
+        cached_route, RouteCache, RoutingConfig, RoutingMode, SegmentOccupancy, Vehicle,
+        VehicleMix, VehicleSpawned, DEFAULT_CAR_LENGTH,
+    },
+    sim_dt, sim_time, Id, Node, Road, Segment, SimRng,
+};
+
+/// How the time between a spawner's arrivals is chosen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpawnDistribution {
+    /// Exactly `1 / rate` seconds apart
+    #[default]
+    Fixed,
+    /// Exponentially distributed gaps with mean `1 / rate`, i.e. a Poisson arrival process
+    Poisson,
+}
 
 /// Spawns vehicles at a regular interval on a specific segment
 #[derive(Component)]
@@ -14,6 +33,10 @@ pub struct VehicleSpawner {
     pub timer: f32,
     /// Speed of spawned vehicles
     pub vehicle_speed: f32,
+    /// Progress along the segment where vehicles appear (0.0 = segment start)
+    pub spawn_progress: f32,
+    /// Spacing of arrivals over time
+    pub distribution: SpawnDistribution,
 }
 
 impl VehicleSpawner {
@@ -23,6 +46,8 @@ impl VehicleSpawner {
             rate,
             timer: 1.0 / rate,
             vehicle_speed: 2.0,
+            spawn_progress: 0.0,
+            distribution: SpawnDistribution::Fixed,
         }
     }
 
@@ -30,4 +55,490 @@ impl VehicleSpawner {
         self.vehicle_speed = speed;
         self
     }
+
+    pub fn with_progress(mut self, progress: f32) -> Self {
+        self.spawn_progress = progress.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_distribution(mut self, distribution: SpawnDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// Seconds until the arrival after this one, with the rate scaled by `demand` (see
+    /// [`DemandProfile::multiplier`]). Never, if there is no demand.
+    pub fn next_interval(&self, rng: &mut SimRng, demand: f32) -> f32 {
+        let rate = self.rate * demand.max(0.0);
+        if rate <= 0.0 {
+            return f32::INFINITY;
+        }
+        let mean = 1.0 / rate;
+        match self.distribution {
+            SpawnDistribution::Fixed => mean,
+            SpawnDistribution::Poisson => rng.exponential(mean),
+        }
+    }
+}
+
+/// How much traffic the random spawner at spawn nodes produces
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct SpawnConfig {
+    /// No new vehicles while this many are on the road; 0 disables spawning
+    pub max_vehicles: usize,
+    /// Chance per simulation step that a spawn node with weight 1.0 spawns a vehicle,
+    /// clamped to 0.0..=1.0
+    pub spawn_probability: f32,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            max_vehicles: 40,
+            spawn_probability: 0.1,
+        }
+    }
+}
+
+impl SpawnConfig {
+    /// [`SpawnConfig::spawn_probability`] clamped to a valid probability
+    pub fn probability(&self) -> f32 {
+        if self.spawn_probability.is_nan() {
+            return 0.0;
+        }
+        self.spawn_probability.clamp(0.0, 1.0)
+    }
+}
+
+/// Spawn-rate multiplier over simulation time, e.g. a morning peak, a midday lull and an
+/// evening peak. Keyframes are `(seconds, multiplier)` pairs, linearly interpolated between;
+/// without keyframes demand stays at 1.0.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct DemandProfile {
+    keyframes: Vec<(f32, f32)>,
+    /// Past the last keyframe, start over from the first instead of holding its multiplier
+    pub looping: bool,
+}
+
+impl DemandProfile {
+    pub fn new(keyframes: impl IntoIterator<Item = (f32, f32)>) -> Self {
+        let mut keyframes: Vec<_> = keyframes
+            .into_iter()
+            .map(|(time, multiplier)| (time, multiplier.max(0.0)))
+            .collect();
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            keyframes,
+            looping: false,
+        }
+    }
+
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    pub fn keyframes(&self) -> &[(f32, f32)] {
+        &self.keyframes
+    }
+
+    /// Spawn-rate multiplier at simulation time `time` (s)
+    pub fn multiplier(&self, time: f32) -> f32 {
+        let (Some(&(start, first)), Some(&(end, last))) =
+            (self.keyframes.first(), self.keyframes.last())
+        else {
+            return 1.0;
+        };
+
+        let time = if self.looping && end > start && time > end {
+            start + (time - start).rem_euclid(end - start)
+        } else {
+            time
+        };
+        if time <= start {
+            return first;
+        }
+        if time >= end {
+            return last;
+        }
+
+        let next = self.keyframes.partition_point(|&(at, _)| at <= time);
+        let (t0, m0) = self.keyframes[next - 1];
+        let (t1, m1) = self.keyframes[next];
+        m0 + (m1 - m0) * (time - t0) / (t1 - t0)
+    }
+}
+
+/// Relative number of trips from each spawn node to each despawn node. While this resource
+/// exists, vehicles pick destinations in proportion to these weights; pairs without a
+/// weight get no trips. Without it, every reachable destination is equally likely.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct OdMatrix {
+    pub weights: HashMap<(Id<Node>, Id<Node>), f32>,
+}
+
+impl OdMatrix {
+    pub fn with_trips(mut self, origin: Id<Node>, destination: Id<Node>, weight: f32) -> Self {
+        self.set(origin, destination, weight);
+        self
+    }
+
+    pub fn set(&mut self, origin: Id<Node>, destination: Id<Node>, weight: f32) {
+        self.weights.insert((origin, destination), weight.max(0.0));
+    }
+
+    /// Relative weight of trips from `origin` to `destination`, 0.0 if not configured
+    pub fn weight(&self, origin: Id<Node>, destination: Id<Node>) -> f32 {
+        self.weights
+            .get(&(origin, destination))
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+/// Minimum spacing between consecutive vehicles spawned at the same node
+#[derive(Resource)]
+pub struct SpawnSpacing {
+    /// Minimum seconds between spawns at one node
+    pub min_headway: f32,
+    /// Minimum free distance (m) from the segment start to the last spawned vehicle's rear
+    pub min_distance: f32,
+    /// Simulation time of the last spawn per node
+    pub last_spawn: HashMap<Id<Node>, f32>,
+}
+
+impl Default for SpawnSpacing {
+    fn default() -> Self {
+        Self {
+            min_headway: 1.5,
+            min_distance: DEFAULT_CAR_LENGTH * 2.0,
+            last_spawn: HashMap::new(),
+        }
+    }
+}
+
+impl SpawnSpacing {
+    /// Whether a new vehicle may spawn at `node` at time `now`
+    pub fn allows(
+        &self,
+        node: Id<Node>,
+        now: f32,
+        road: &Road,
+        occupancy: &SegmentOccupancy,
+    ) -> bool {
+        self.headway_passed(node, now)
+            && road
+                .nodes
+                .get(&node)
+                .outgoing
+                .iter()
+                .all(|segment| self.is_clear(*segment, 0.0, road, occupancy))
+    }
+
+    /// Whether a new vehicle may spawn `progress` of the way along `segment` at time `now`.
+    /// The headway counts per start node of the segment, like [`SpawnSpacing::allows`].
+    pub fn allows_at(
+        &self,
+        segment: Id<Segment>,
+        progress: f32,
+        now: f32,
+        road: &Road,
+        occupancy: &SegmentOccupancy,
+    ) -> bool {
+        let from = road.segments.get(&segment).from;
+        self.headway_passed(from, now)
+            && self.is_clear(
+                segment,
+                progress * road.segments.get(&segment).length,
+                road,
+                occupancy,
+            )
+    }
+
+    fn headway_passed(&self, node: Id<Node>, now: f32) -> bool {
+        self.last_spawn
+            .get(&node)
+            .is_none_or(|last| now - last >= self.min_headway)
+    }
+
+    /// Whether no vehicle in any lane of `segment` is within `min_distance` of the point
+    /// `spawn_at` meters along it
+    fn is_clear(
+        &self,
+        segment_id: Id<Segment>,
+        spawn_at: f32,
+        road: &Road,
+        occupancy: &SegmentOccupancy,
+    ) -> bool {
+        let segment = road.segments.get(&segment_id);
+        (0..segment.lanes.max(1)).all(|lane| {
+            occupancy.lane(segment_id, lane).iter().all(|occ| {
+                let center = occ.progress * segment.length;
+                center - occ.length / 2.0 >= spawn_at + self.min_distance
+                    || center + occ.length / 2.0 <= spawn_at - self.min_distance
+            })
+        })
+    }
+
+    pub fn record(&mut self, node: Id<Node>, now: f32) {
+        self.last_spawn.insert(node, now);
+    }
+}
+
+/// Spawn a vehicle from every [`VehicleSpawner`] whose timer has run out, heading to a
+/// random despawn node reachable from the end of the spawner's segment, with a class drawn
+/// from the [`VehicleMix`]. Spawners wait while [`SpawnSpacing`] doesn't allow a vehicle at
+/// the spawn point and skip arrivals that have nowhere to go.
+#[allow(clippy::too_many_arguments)]
+pub fn run_vehicle_spawners(
+    mut commands: Commands,
+    time: Res<Time>,
+    road: Res<Road>,
+    occupancy: Res<SegmentOccupancy>,
+    mut spacing: ResMut<SpawnSpacing>,
+    mut rng: ResMut<SimRng>,
+    mut spawners: Query<&mut VehicleSpawner>,
+    routing: Option<Res<RoutingMode>>,
+    routing_config: Option<Res<RoutingConfig>>,
+    mut routes: Option<ResMut<RouteCache>>,
+    demand: Option<Res<DemandProfile>>,
+    mix: Option<Res<VehicleMix>>,
+    mut spawned: Option<MessageWriter<VehicleSpawned>>,
+) {
+    let dt = sim_dt(&time);
+    let routing = routing.as_deref().copied().unwrap_or_default();
+    let routing_config = routing_config.as_deref().copied().unwrap_or_default();
+    let mix = mix.as_deref().copied().unwrap_or_default();
+    let now = sim_time(&time);
+    let demand = demand.map_or(1.0, |demand| demand.multiplier(now));
+
+    for mut spawner in &mut spawners {
+        spawner.timer -= dt;
+        if spawner.timer > 0.0 {
+            continue;
+        }
+        let Some(segment) = road.segments.get_checked(&spawner.segment) else {
+            continue;
+        };
+
+        // Too soon, or someone is still standing on the spawn point; try again next step
+        if !spacing.allows_at(
+            spawner.segment,
+            spawner.spawn_progress,
+            now,
+            &road,
+            &occupancy,
+        ) {
+            continue;
+        }
+        let interval = spawner.next_interval(&mut rng, demand);
+        if !interval.is_finite() {
+            // No demand right now; check again next step instead of never
+            spawner.timer = 0.0;
+            continue;
+        }
+        spawner.timer += interval;
+        let class = mix.sample(&mut rng);
+
+        let candidates: Vec<_> = road
+            .nodes
+            .iter_with_ids()
+            .filter(|(_, node)| node.is_despawn)
+            .filter_map(|(destination, _)| {
+                if destination == segment.to {
+                    return Some((destination, vec![]));
+                }
+                cached_route(
+                    routes.as_deref_mut(),
+                    &road,
+                    segment.to,
+                    destination,
+                    class,
+                    routing,
+                    routing_config,
+                )
+                .map(|(_, onward)| (destination, onward))
+            })
+            .collect();
+        let Some((destination, onward)) = rng.choose(&candidates) else {
+            continue;
+        };
+
+        let route: Vec<_> = std::iter::once(spawner.segment)
+            .chain(onward.iter().copied())
+            .collect();
+        let mut vehicle = Vehicle::new_with_class(spawner.segment, *destination, route, class)
+            .with_progress(spawner.spawn_progress)
+            .with_speed(spawner.vehicle_speed);
+        vehicle.free_flow_time = road.free_flow_time(&vehicle.route);
+        vehicle.lane = road.lane_for_route(&vehicle.route);
+        let entity = commands.spawn(vehicle).id();
+        if let Some(spawned) = spawned.as_mut() {
+            spawned.write(VehicleSpawned {
+                entity,
+                origin: segment.from,
+            });
+        }
+        spacing.record(segment.from, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{Occupant, VehicleClass};
+    use bevy_ecs::entity::Entity;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+    use std::time::Duration;
+
+    #[test]
+    fn test_spawner_produces_routed_vehicles() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let dead_end = road.add_node(Vec3::new(100.0, 100.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+        let nowhere = road.add_segment(a, dead_end, 13.9);
+
+        let mut world = World::new();
+        world.insert_resource(road);
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SpawnSpacing>();
+        world.insert_resource(SimRng::seeded(4));
+        world.insert_resource(VehicleMix::only(VehicleClass::Truck));
+        world.init_resource::<Time>();
+        world.spawn(
+            VehicleSpawner::new(ab, 1.0)
+                .with_speed(8.0)
+                .with_progress(0.25),
+        );
+        world.spawn(VehicleSpawner::new(nowhere, 1.0));
+
+        for _ in 0..3 {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            world
+                .run_system_once(crate::driver::update_occupancy)
+                .unwrap();
+            world.run_system_once(run_vehicle_spawners).unwrap();
+        }
+
+        let vehicles: Vec<_> = world.query::<&Vehicle>().iter(&world).collect();
+        assert_eq!(vehicles.len(), 1, "the spawn point stays occupied");
+        let vehicle = vehicles[0];
+        assert_eq!(vehicle.segment, ab);
+        assert_eq!(vehicle.route, vec![ab, bc]);
+        assert_eq!(vehicle.destination, c);
+        assert_eq!(vehicle.class, VehicleClass::Truck);
+        assert_eq!(vehicle.speed, 8.0);
+        assert_eq!(vehicle.progress, 0.25);
+    }
+
+    #[test]
+    fn test_poisson_arrivals_average_one_over_rate() {
+        let spawner = VehicleSpawner::new(Id::new(0), 0.5);
+        let mut rng = SimRng::seeded(11);
+        assert_eq!(spawner.next_interval(&mut rng, 1.0), 2.0);
+        assert_eq!(spawner.next_interval(&mut rng, 2.0), 1.0);
+        assert_eq!(spawner.next_interval(&mut rng, 0.0), f32::INFINITY);
+
+        let spawner = spawner.with_distribution(SpawnDistribution::Poisson);
+        let intervals: Vec<_> = (0..10_000)
+            .map(|_| spawner.next_interval(&mut rng, 1.0))
+            .collect();
+        let mean = intervals.iter().sum::<f32>() / intervals.len() as f32;
+        assert!((mean - 2.0).abs() < 0.1, "mean interval {mean}");
+        // Bursty: gaps well under and well over the mean both occur
+        assert!(intervals.iter().any(|&gap| gap < 0.5));
+        assert!(intervals.iter().any(|&gap| gap > 6.0));
+        assert!(intervals.iter().all(|&gap| gap >= 0.0));
+    }
+
+    #[test]
+    fn test_demand_profile_holds_or_wraps_past_last_keyframe() {
+        let profile = DemandProfile::new([(0.0, 0.5), (100.0, 2.0), (200.0, 1.0)]);
+        assert_eq!(profile.multiplier(-10.0), 0.5);
+        assert_eq!(profile.multiplier(50.0), 1.25);
+        assert_eq!(profile.multiplier(150.0), 1.5);
+        assert_eq!(profile.multiplier(500.0), 1.0);
+
+        let looping = profile.looping();
+        assert_eq!(looping.multiplier(250.0), 1.25);
+        assert_eq!(looping.multiplier(300.0), 2.0);
+
+        assert_eq!(DemandProfile::default().multiplier(1e6), 1.0);
+    }
+
+    #[test]
+    fn test_spawner_resumes_when_demand_returns() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_despawn_node(Vec3::new(100.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.insert_resource(road);
+        world.init_resource::<SegmentOccupancy>();
+        world.init_resource::<SpawnSpacing>();
+        world.insert_resource(SimRng::seeded(4));
+        world.init_resource::<Time>();
+        // No traffic for the first 10 s, then full demand
+        world.insert_resource(DemandProfile::new([(0.0, 0.0), (10.0, 0.0), (11.0, 1.0)]));
+        world.spawn(VehicleSpawner::new(segment, 1.0));
+
+        let mut spawned_by = |seconds| {
+            while world.resource::<Time>().elapsed_secs() < seconds {
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_millis(500));
+                world.run_system_once(run_vehicle_spawners).unwrap();
+            }
+            world.query::<&Vehicle>().iter(&world).count()
+        };
+        assert_eq!(spawned_by(10.0), 0);
+        assert!(spawned_by(12.0) > 0);
+    }
+
+    #[test]
+    fn test_consecutive_spawns_respect_headway() {
+        let mut road = Road::default();
+        let spawn = road.add_spawn_node(Vec3::ZERO);
+        let end = road.add_despawn_node(Vec3::new(100.0, 0.0, 0.0));
+        let segment = road.add_segment(spawn, end, 13.9);
+
+        let mut occupancy = SegmentOccupancy::default();
+        let mut spacing = SpawnSpacing::default();
+
+        assert!(spacing.allows(spawn, 0.0, &road, &occupancy));
+        spacing.record(spawn, 0.0);
+
+        // Too soon after the last spawn
+        assert!(!spacing.allows(spawn, 1.0, &road, &occupancy));
+
+        // Enough time has passed, but the previous vehicle is still close to the start
+        occupancy.vehicles.insert(
+            (segment, 0),
+            vec![Occupant {
+                progress: 0.05,
+                vehicle: Entity::from_raw_u32(1).unwrap(),
+                speed: 2.0,
+                segment,
+                lane: 0,
+                length: DEFAULT_CAR_LENGTH,
+            }],
+        );
+        assert!(!spacing.allows(spawn, 2.0, &road, &occupancy));
+
+        // Previous vehicle has moved on
+        occupancy.vehicles.get_mut(&(segment, 0)).unwrap()[0].progress = 0.5;
+        assert!(spacing.allows(spawn, 2.0, &road, &occupancy));
+
+        // A spawn point halfway along is still taken by it
+        assert!(!spacing.allows_at(segment, 0.5, 2.0, &road, &occupancy));
+        assert!(spacing.allows_at(segment, 0.0, 2.0, &road, &occupancy));
+    }
 }
@@ -0,0 +1,316 @@
+//! Aggregate simulation statistics.
+//!
+//! Units:
+//! - Time: seconds (s)
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy_ecs::prelude::*;
+use bevy_time::Time;
+
+use crate::{
+    driver::{IntersectionCleared, Vehicle, VehicleArrived, VehicleSpawned},
+    sim_dt, Id, Intersection, Road, Segment,
+};
+
+/// Vehicles slower than this (m/s) count as jammed
+pub const JAM_SPEED: f32 = 1.0;
+
+/// Number of most recent trips averaged by [`TrafficMetrics::average_travel_time`]
+pub const TRAVEL_TIME_WINDOW: usize = 100;
+
+#[derive(Resource, Default, Debug)]
+pub struct SimulationStats {
+    /// Number of simulation steps taken
+    pub tick: u64,
+    /// Vehicles currently on the road
+    pub active_vehicles: usize,
+    /// Mean speed of active vehicles in m/s
+    pub mean_speed: f32,
+    /// Fraction of active vehicles slower than [`JAM_SPEED`]
+    pub jam_fraction: f32,
+    /// Vehicles that reached their destination
+    pub completed_trips: usize,
+    /// Sum of actual travel times of completed trips
+    pub total_travel_time: f32,
+    /// Sum of free-flow travel times of completed trips
+    pub total_free_flow_time: f32,
+    /// Delay vehicles still on the road are already certain to have: time spent beyond
+    /// the free-flow time of their whole trip
+    pub unfinished_delay: f32,
+    /// Network-wide flow: sum of vehicle speeds divided by total lane length, in vehicles/s
+    pub flow: f32,
+    /// Network-wide density: active vehicles divided by total lane length, in vehicles/m
+    pub density: f32,
+    /// Per segment, how many active vehicles still have it on their route (anticipated demand)
+    pub route_load: HashMap<Id<Segment>, usize>,
+}
+
+impl SimulationStats {
+    pub fn record_trip(&mut self, travel_time: f32, free_flow_time: f32) {
+        self.completed_trips += 1;
+        self.total_travel_time += travel_time;
+        self.total_free_flow_time += free_flow_time;
+    }
+
+    /// Actual travel time divided by free-flow travel time (1.0 = no delay)
+    pub fn delay_ratio(&self) -> Option<f32> {
+        if self.completed_trips == 0 || self.total_free_flow_time <= 0.0 {
+            return None;
+        }
+
+        Some(self.total_travel_time / self.total_free_flow_time)
+    }
+
+    /// Average time lost per completed trip compared to free-flow
+    pub fn average_delay(&self) -> Option<f32> {
+        if self.completed_trips == 0 {
+            return None;
+        }
+
+        Some((self.total_travel_time - self.total_free_flow_time) / self.completed_trips as f32)
+    }
+
+    /// Seconds lost to traffic so far, over completed trips and vehicles still stuck on
+    /// the road alike
+    pub fn total_delay(&self) -> f32 {
+        self.total_travel_time - self.total_free_flow_time + self.unfinished_delay
+    }
+}
+
+/// Cumulative throughput and travel times, built from the vehicle lifecycle messages
+#[derive(Resource, Clone, Default, Debug)]
+pub struct TrafficMetrics {
+    pub spawned: usize,
+    pub arrived: usize,
+    /// Vehicles that entered each intersection
+    pub intersection_cleared: HashMap<Id<Intersection>, usize>,
+    /// Simulated seconds covered
+    pub elapsed: f32,
+    /// Travel times of the last [`TRAVEL_TIME_WINDOW`] arrivals
+    recent_travel_times: VecDeque<f32>,
+}
+
+impl TrafficMetrics {
+    pub fn record_arrival(&mut self, travel_time: f32) {
+        self.arrived += 1;
+        if self.recent_travel_times.len() == TRAVEL_TIME_WINDOW {
+            self.recent_travel_times.pop_front();
+        }
+        self.recent_travel_times.push_back(travel_time);
+    }
+
+    /// Mean travel time over the most recent arrivals
+    pub fn average_travel_time(&self) -> Option<f32> {
+        if self.recent_travel_times.is_empty() {
+            return None;
+        }
+
+        Some(self.recent_travel_times.iter().sum::<f32>() / self.recent_travel_times.len() as f32)
+    }
+
+    /// Arrivals per simulated minute over the whole run
+    pub fn throughput_per_minute(&self) -> f32 {
+        per_minute(self.arrived, self.elapsed)
+    }
+
+    /// Vehicles entering `intersection` per simulated minute over the whole run
+    pub fn intersection_throughput_per_minute(&self, intersection: Id<Intersection>) -> f32 {
+        let cleared = self
+            .intersection_cleared
+            .get(&intersection)
+            .copied()
+            .unwrap_or(0);
+        per_minute(cleared, self.elapsed)
+    }
+}
+
+fn per_minute(count: usize, seconds: f32) -> f32 {
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+
+    count as f32 * 60.0 / seconds
+}
+
+pub fn collect_metrics(
+    time: Res<Time>,
+    mut metrics: ResMut<TrafficMetrics>,
+    mut spawned: MessageReader<VehicleSpawned>,
+    mut arrived: MessageReader<VehicleArrived>,
+    mut cleared: MessageReader<IntersectionCleared>,
+) {
+    metrics.elapsed += sim_dt(&time);
+    metrics.spawned += spawned.read().count();
+    for arrival in arrived.read() {
+        metrics.record_arrival(arrival.travel_time);
+    }
+    for clearance in cleared.read() {
+        *metrics
+            .intersection_cleared
+            .entry(clearance.intersection)
+            .or_default() += 1;
+    }
+}
+
+/// Refresh the per-step snapshot values
+pub fn update_stats(
+    mut stats: ResMut<SimulationStats>,
+    vehicles: Query<&Vehicle>,
+    road: Res<Road>,
+) {
+    stats.tick += 1;
+
+    let active = vehicles.iter().count();
+    stats.active_vehicles = active;
+    stats.unfinished_delay = vehicles
+        .iter()
+        .map(|v| (v.travel_time - v.free_flow_time).max(0.0))
+        .sum();
+
+    if active == 0 {
+        stats.mean_speed = 0.0;
+        stats.jam_fraction = 0.0;
+        stats.flow = 0.0;
+        stats.density = 0.0;
+        return;
+    }
+
+    let total_speed: f32 = vehicles.iter().map(|v| v.speed).sum();
+    let jammed = vehicles.iter().filter(|v| v.speed < JAM_SPEED).count();
+
+    stats.mean_speed = total_speed / active as f32;
+    stats.jam_fraction = jammed as f32 / active as f32;
+
+    // Lane length, so a two-lane road holds twice the traffic of a single-lane one
+    let total_length: f32 = road
+        .segments
+        .iter()
+        .map(|s| s.length * s.lanes.max(1) as f32)
+        .sum();
+    if total_length > 0.0 {
+        stats.flow = total_speed / total_length;
+        stats.density = active as f32 / total_length;
+    } else {
+        stats.flow = 0.0;
+        stats.density = 0.0;
+    }
+}
+
+/// Count, per segment, the active vehicles whose remaining route includes it
+pub fn update_route_load(mut stats: ResMut<SimulationStats>, vehicles: Query<&Vehicle>) {
+    stats.route_load.clear();
+
+    for vehicle in &vehicles {
+        for segment in &vehicle.route {
+            *stats.route_load.entry(*segment).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use glam::Vec3;
+
+    #[test]
+    fn test_flow_and_density() {
+        let mut road = Road::default();
+        let a = road.add_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let segment = road.add_segment(a, b, 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<SimulationStats>();
+        world.insert_resource(road);
+
+        world.run_system_once(update_stats).unwrap();
+        let stats = world.resource::<SimulationStats>();
+        assert_eq!(stats.flow, 0.0);
+        assert_eq!(stats.density, 0.0);
+
+        for speed in [10.0, 6.0] {
+            let mut vehicle = Vehicle::new(segment, b, vec![segment]);
+            vehicle.speed = speed;
+            world.spawn(vehicle);
+        }
+
+        world.run_system_once(update_stats).unwrap();
+        let stats = world.resource::<SimulationStats>();
+        assert!((stats.flow - 0.16).abs() < 1e-6);
+        assert!((stats.density - 0.02).abs() < 1e-6);
+
+        // A second lane doubles the road's capacity, halving flow and density
+        world
+            .resource_mut::<Road>()
+            .segments
+            .get_mut(&segment)
+            .lanes = 2;
+        world.run_system_once(update_stats).unwrap();
+        let stats = world.resource::<SimulationStats>();
+        assert!((stats.flow - 0.08).abs() < 1e-6);
+        assert!((stats.density - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_delay_ratio() {
+        let mut stats = SimulationStats::default();
+        assert_eq!(stats.delay_ratio(), None);
+
+        stats.record_trip(30.0, 20.0);
+        stats.record_trip(50.0, 20.0);
+
+        assert_eq!(stats.delay_ratio(), Some(2.0));
+        assert_eq!(stats.average_delay(), Some(20.0));
+
+        // Vehicles still queued add what they have already lost
+        stats.unfinished_delay = 15.0;
+        assert_eq!(stats.total_delay(), 55.0);
+    }
+
+    #[test]
+    fn test_route_load_counts_shared_segments() {
+        let mut road = Road::default();
+        let nodes: Vec<_> = (0..4)
+            .map(|i| road.add_node(Vec3::new(i as f32 * 50.0, 0.0, 0.0)))
+            .collect();
+        let s0 = road.add_segment(nodes[0], nodes[1], 13.9);
+        let s1 = road.add_segment(nodes[1], nodes[2], 13.9);
+        let s2 = road.add_segment(nodes[2], nodes[3], 13.9);
+
+        let mut world = World::new();
+        world.init_resource::<SimulationStats>();
+        for _ in 0..5 {
+            world.spawn(Vehicle::new(s0, nodes[3], vec![s0, s1, s2]));
+        }
+        world.spawn(Vehicle::new(s1, nodes[2], vec![s1]));
+
+        world.run_system_once(update_route_load).unwrap();
+        let load = &world.resource::<SimulationStats>().route_load;
+        assert_eq!(load[&s0], 5);
+        assert_eq!(load[&s1], 6);
+        assert_eq!(load[&s2], 5);
+    }
+
+    #[test]
+    fn test_average_travel_time_covers_recent_trips() {
+        let mut metrics = TrafficMetrics::default();
+        assert_eq!(metrics.average_travel_time(), None);
+        assert_eq!(metrics.throughput_per_minute(), 0.0);
+
+        for _ in 0..TRAVEL_TIME_WINDOW {
+            metrics.record_arrival(100.0);
+        }
+        for _ in 0..TRAVEL_TIME_WINDOW {
+            metrics.record_arrival(20.0);
+        }
+        metrics.elapsed = 600.0;
+
+        assert_eq!(metrics.average_travel_time(), Some(20.0));
+        assert_eq!(
+            metrics.throughput_per_minute(),
+            2.0 * TRAVEL_TIME_WINDOW as f32 / 10.0
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Per-step CSV telemetry for offline analysis.
+//!
+//! Insert a [`CsvTelemetry`] resource to start recording; one row is written per step.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+
+use crate::SimulationStats;
+
+pub const CSV_HEADER: &str = "tick,active_vehicles,mean_speed,completed_trips,jam_fraction";
+
+/// Destination for telemetry lines (without trailing newline)
+pub trait TelemetrySink: Send + Sync {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()>;
+}
+
+/// Writes telemetry to a file on disk
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+}
+
+impl TelemetrySink for FileSink {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.writer, "{line}")
+    }
+}
+
+/// Collects telemetry lines in memory; clones share the same buffer
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    pub lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl TelemetrySink for MemorySink {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.lines.lock().unwrap().push(line.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+pub struct CsvTelemetry {
+    sink: Box<dyn TelemetrySink>,
+    header_written: bool,
+}
+
+impl CsvTelemetry {
+    pub fn new(sink: impl TelemetrySink + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+            header_written: false,
+        }
+    }
+}
+
+pub fn write_telemetry(stats: Res<SimulationStats>, telemetry: Option<ResMut<CsvTelemetry>>) {
+    let Some(mut telemetry) = telemetry else {
+        return;
+    };
+
+    if !telemetry.header_written {
+        if let Err(err) = telemetry.sink.write_line(CSV_HEADER) {
+            crate::log!("TELEMETRY: failed to write header: {}", err);
+            return;
+        }
+        telemetry.header_written = true;
+    }
+
+    let row = format!(
+        "{},{},{:.3},{},{:.3}",
+        stats.tick,
+        stats.active_vehicles,
+        stats.mean_speed,
+        stats.completed_trips,
+        stats.jam_fraction
+    );
+    if let Err(err) = telemetry.sink.write_line(&row) {
+        crate::log!("TELEMETRY: failed to write row: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+
+    #[test]
+    fn test_rows_captured_in_memory() {
+        let sink = MemorySink::default();
+        let mut world = World::new();
+        world.insert_resource(SimulationStats::default());
+        world.insert_resource(CsvTelemetry::new(sink.clone()));
+
+        for tick in 1..=3 {
+            {
+                let mut stats = world.resource_mut::<SimulationStats>();
+                stats.tick = tick;
+                stats.active_vehicles = tick as usize * 2;
+                stats.mean_speed = 5.0;
+                stats.completed_trips = tick as usize - 1;
+                stats.jam_fraction = 0.25;
+            }
+            world.run_system_once(write_telemetry).unwrap();
+        }
+
+        let lines = sink.lines.lock().unwrap();
+        assert_eq!(
+            *lines,
+            vec![
+                CSV_HEADER.to_string(),
+                "1,2,5.000,0,0.250".to_string(),
+                "2,4,5.000,1,0.250".to_string(),
+                "3,6,5.000,2,0.250".to_string(),
+            ]
+        );
+    }
+}
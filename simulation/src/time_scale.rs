@@ -0,0 +1,58 @@
+//! Fast-forward and slow motion.
+//!
+//! [`SimulationSpeed`] sets how fast the virtual clock runs against real time. The simulation
+//! steps on a fixed timestep fed by that clock, so fast-forward runs more steps of the same
+//! size rather than longer ones, and everything reading the clock (signals, stop order,
+//! demand, spawn headways) speeds up, slows down or stops along with the vehicles.
+//! Systems take their time from [`sim_dt`] and [`sim_time`] so they all see the same clock.
+
+use bevy_ecs::prelude::*;
+use bevy_time::{Time, Virtual};
+
+/// Multiplier on simulated time per real second; 0.0 pauses the simulation
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct SimulationSpeed(pub f32);
+
+impl Default for SimulationSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl SimulationSpeed {
+    /// The multiplier, with negative or NaN values treated as paused
+    pub fn factor(self) -> f32 {
+        self.0.max(0.0)
+    }
+}
+
+/// Seconds of simulated time in this step
+pub fn sim_dt(time: &Time) -> f32 {
+    time.delta_secs()
+}
+
+/// Seconds of simulated time since the start
+pub fn sim_time(time: &Time) -> f32 {
+    time.elapsed_secs()
+}
+
+/// Run the virtual clock at the current [`SimulationSpeed`]. Runs before the clocks update
+/// each frame, so a change applies from that frame on.
+pub fn apply_simulation_speed(speed: Res<SimulationSpeed>, mut time: ResMut<Time<Virtual>>) {
+    let factor = speed.factor();
+    if time.relative_speed() != factor {
+        time.set_relative_speed(if factor.is_finite() { factor } else { 1.0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negative_speed_pauses() {
+        assert_eq!(SimulationSpeed(3.0).factor(), 3.0);
+        assert_eq!(SimulationSpeed(-1.0).factor(), 0.0);
+        assert_eq!(SimulationSpeed(f32::NAN).factor(), 0.0);
+    }
+}
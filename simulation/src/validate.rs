@@ -0,0 +1,237 @@
+//! Consistency checks for hand-built road networks.
+//!
+//! Run [`Road::validate`] in tests or before [`Road::finalize`] to catch wiring mistakes
+//! that would otherwise surface as panics or vehicles despawning mid-trip.
+
+use std::fmt;
+
+use crate::{driver::next_segment_toward, Id, Node, Road, Segment};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoadError {
+    /// A segment starts or ends at a node that doesn't exist
+    DanglingSegment {
+        segment: Id<Segment>,
+        node: Id<Node>,
+    },
+    /// A node lists an incoming or outgoing segment that doesn't exist
+    DanglingNodeReference {
+        node: Id<Node>,
+        segment: Id<Segment>,
+    },
+    /// A node's incoming/outgoing list and a segment's endpoints disagree
+    MismatchedWiring {
+        node: Id<Node>,
+        segment: Id<Segment>,
+    },
+    /// No despawn node can be reached from this spawn node
+    UnreachableSpawn(Id<Node>),
+    ZeroLengthSegment(Id<Segment>),
+}
+
+impl fmt::Display for RoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoadError::DanglingSegment { segment, node } => {
+                write!(f, "segment {segment} references missing node {node}")
+            }
+            RoadError::DanglingNodeReference { node, segment } => {
+                write!(f, "node {node} lists missing segment {segment}")
+            }
+            RoadError::MismatchedWiring { node, segment } => {
+                write!(
+                    f,
+                    "node {node} and segment {segment} disagree on their connection"
+                )
+            }
+            RoadError::UnreachableSpawn(node) => {
+                write!(f, "spawn node {node} cannot reach any despawn node")
+            }
+            RoadError::ZeroLengthSegment(segment) => write!(f, "segment {segment} has no length"),
+        }
+    }
+}
+
+impl std::error::Error for RoadError {}
+
+impl Road {
+    /// Check the graph for broken references, inconsistent wiring, stranded spawn points and
+    /// degenerate segments, returning every problem found
+    pub fn validate(&self) -> Result<(), Vec<RoadError>> {
+        let mut errors = vec![];
+
+        for (id, segment) in self.segments.iter_with_ids() {
+            for (node_id, listed) in [(segment.from, true), (segment.to, false)] {
+                match self.nodes.get_checked(&node_id) {
+                    None => errors.push(RoadError::DanglingSegment {
+                        segment: id,
+                        node: node_id,
+                    }),
+                    Some(node) => {
+                        let list = if listed {
+                            &node.outgoing
+                        } else {
+                            &node.incoming
+                        };
+                        if !list.contains(&id) {
+                            errors.push(RoadError::MismatchedWiring {
+                                node: node_id,
+                                segment: id,
+                            });
+                        }
+                    }
+                }
+            }
+
+            if segment.length <= f32::EPSILON {
+                errors.push(RoadError::ZeroLengthSegment(id));
+            }
+        }
+
+        for (id, node) in self.nodes.iter_with_ids() {
+            let listed = node
+                .outgoing
+                .iter()
+                .map(|segment| (segment, true))
+                .chain(node.incoming.iter().map(|segment| (segment, false)));
+            for (&segment_id, outgoing) in listed {
+                match self.segments.get_checked(&segment_id) {
+                    None => errors.push(RoadError::DanglingNodeReference {
+                        node: id,
+                        segment: segment_id,
+                    }),
+                    Some(segment) => {
+                        let end = if outgoing { segment.from } else { segment.to };
+                        if end != id {
+                            errors.push(RoadError::MismatchedWiring {
+                                node: id,
+                                segment: segment_id,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // Routing over broken wiring would panic, so only check reachability on a sound graph
+        if errors.is_empty() {
+            let despawns: Vec<_> = self
+                .nodes
+                .iter_with_ids()
+                .filter(|(_, node)| node.is_despawn)
+                .map(|(id, _)| id)
+                .collect();
+            for (id, _) in self.nodes.iter_with_ids().filter(|(_, n)| n.is_spawn) {
+                let reachable = despawns.iter().any(|&destination| {
+                    destination != id && next_segment_toward(self, id, destination).is_some()
+                });
+                if !reachable {
+                    errors.push(RoadError::UnreachableSpawn(id));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::YieldResolver;
+    use glam::Vec3;
+
+    /// Spawn node feeding a despawn node through a middle node
+    fn corridor() -> (Road, [Id<Node>; 3], [Id<Segment>; 2]) {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::ZERO);
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(200.0, 0.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        let bc = road.add_segment(b, c, 13.9);
+        (road, [a, b, c], [ab, bc])
+    }
+
+    #[test]
+    fn test_well_formed_roads_pass() {
+        assert_eq!(corridor().0.validate(), Ok(()));
+        let grid = Road::generate_grid(2, 2, 100.0, 13.9, YieldResolver::default());
+        assert_eq!(grid.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_removed_node_leaves_dangling_segment() {
+        let (mut road, [_, _, c], [_, bc]) = corridor();
+        road.nodes.remove(&c);
+        let errors = road.validate().unwrap_err();
+        assert!(errors.contains(&RoadError::DanglingSegment {
+            segment: bc,
+            node: c
+        }));
+    }
+
+    #[test]
+    fn test_removed_segment_leaves_dangling_node_reference() {
+        let (mut road, [_, b, c], [_, bc]) = corridor();
+        road.segments.remove(&bc);
+        let errors = road.validate().unwrap_err();
+        assert!(errors.contains(&RoadError::DanglingNodeReference {
+            node: b,
+            segment: bc
+        }));
+        assert!(errors.contains(&RoadError::DanglingNodeReference {
+            node: c,
+            segment: bc
+        }));
+    }
+
+    #[test]
+    fn test_rewired_segment_mismatches_its_nodes() {
+        let (mut road, [a, b, _], [ab, _]) = corridor();
+        road.segments.get_mut(&ab).from = b;
+        let errors = road.validate().unwrap_err();
+        assert!(errors.contains(&RoadError::MismatchedWiring {
+            node: b,
+            segment: ab
+        }));
+        assert!(errors.contains(&RoadError::MismatchedWiring {
+            node: a,
+            segment: ab
+        }));
+    }
+
+    #[test]
+    fn test_stranded_spawn_is_reported() {
+        let (mut road, [_, b, _], _) = corridor();
+        // A spawn point that only leads back into itself, and one with no road at all
+        let loop_start = road.add_spawn_node(Vec3::new(0.0, 100.0, 0.0));
+        let loop_end = road.add_node(Vec3::new(50.0, 100.0, 0.0));
+        road.add_bidirectional(loop_start, loop_end, 13.9);
+        let isolated = road.add_spawn_node(Vec3::new(0.0, -100.0, 0.0));
+        // Reachable spawn points are fine
+        road.nodes.get_mut(&b).is_spawn = true;
+
+        assert_eq!(
+            road.validate(),
+            Err(vec![
+                RoadError::UnreachableSpawn(loop_start),
+                RoadError::UnreachableSpawn(isolated)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_zero_length_segment_is_reported() {
+        let (mut road, [_, b, _], _) = corridor();
+        let twin = road.add_despawn_node(road.nodes.get(&b).position);
+        let degenerate = road.add_segment(b, twin, 13.9);
+        assert_eq!(
+            road.validate(),
+            Err(vec![RoadError::ZeroLengthSegment(degenerate)])
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Character-grid rendering of a road network for headless debugging.
+//!
+//! The network bounds are stretched over the whole grid, so the X and Y scales may differ.
+//! North (+Y) is up.
+
+use glam::Vec3;
+
+use crate::Road;
+
+pub const ROAD_CHAR: char = '.';
+pub const INTERSECTION_CHAR: char = '+';
+pub const VEHICLE_CHAR: char = 'o';
+/// Shown when more than one vehicle falls into the same cell
+pub const CROWD_CHAR: char = '#';
+
+/// Maps world positions onto a grid of `columns` x `rows` cells
+pub struct AsciiGrid {
+    pub columns: usize,
+    pub rows: usize,
+    min: Vec3,
+    max: Vec3,
+}
+
+impl AsciiGrid {
+    /// Grid covering the road's bounds, or `None` for an empty road
+    pub fn fit(road: &Road, columns: usize, rows: usize) -> Option<Self> {
+        let (min, max) = road.bounds()?;
+        Some(Self {
+            columns: columns.max(1),
+            rows: rows.max(1),
+            min,
+            max,
+        })
+    }
+
+    /// (column, row) of the cell containing a position, row 0 being the top
+    pub fn cell_of(&self, position: Vec3) -> Option<(usize, usize)> {
+        let normalized = |value: f32, min: f32, max: f32| {
+            if max - min <= f32::EPSILON {
+                0.0
+            } else {
+                (value - min) / (max - min)
+            }
+        };
+
+        let x = normalized(position.x, self.min.x, self.max.x);
+        let y = normalized(position.y, self.min.y, self.max.y);
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return None;
+        }
+
+        let column = (x * (self.columns - 1) as f32).round() as usize;
+        let row = ((1.0 - y) * (self.rows - 1) as f32).round() as usize;
+        Some((column, row))
+    }
+
+    /// Draw the road and vehicle positions, one line per row
+    pub fn render(&self, road: &Road, vehicles: &[Vec3]) -> String {
+        let mut cells = vec![vec![' '; self.columns]; self.rows];
+        let mut put = |position: Vec3, char: char| {
+            if let Some((column, row)) = self.cell_of(position) {
+                cells[row][column] = char;
+            }
+        };
+
+        // Sample each segment at roughly half a cell so lines have no gaps
+        let cell_size = ((self.max.x - self.min.x) / self.columns as f32)
+            .min((self.max.y - self.min.y) / self.rows as f32)
+            .max(0.01);
+        for segment in road.segments.iter() {
+            let from = road.nodes.get(&segment.from).position;
+            let to = road.nodes.get(&segment.to).position;
+            let samples = (segment.length / (cell_size * 0.5)).ceil() as usize;
+            for point in segment.geometry.sample(from, to, samples) {
+                put(point, ROAD_CHAR);
+            }
+        }
+
+        for node in road.nodes.iter() {
+            if node.yield_resolver.is_some() {
+                put(node.position, INTERSECTION_CHAR);
+            }
+        }
+
+        for &position in vehicles {
+            if let Some((column, row)) = self.cell_of(position) {
+                let cell = &mut cells[row][column];
+                *cell = match *cell {
+                    VEHICLE_CHAR | CROWD_CHAR => CROWD_CHAR,
+                    _ => VEHICLE_CHAR,
+                };
+            }
+        }
+
+        cells
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Render a road and vehicle positions to a `columns` x `rows` character grid
+pub fn render_ascii(road: &Road, vehicles: &[Vec3], columns: usize, rows: usize) -> String {
+    match AsciiGrid::fit(road, columns, rows) {
+        Some(grid) => grid.render(road, vehicles),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{vehicle_world_positions, Vehicle};
+
+    #[test]
+    fn test_vehicle_drawn_in_expected_cell() {
+        let mut road = Road::default();
+        let a = road.add_spawn_node(Vec3::new(0.0, 0.0, 0.0));
+        let b = road.add_node(Vec3::new(100.0, 0.0, 0.0));
+        let c = road.add_despawn_node(Vec3::new(100.0, 50.0, 0.0));
+        let ab = road.add_segment(a, b, 13.9);
+        road.add_segment(b, c, 13.9);
+
+        let mut vehicle = Vehicle::new(ab, c, vec![]);
+        vehicle.progress = 0.3;
+        let positions = vehicle_world_positions(&road, [&vehicle]);
+
+        // 11 columns over 100 m -> 10 m per column; 6 rows over 50 m -> 10 m per row
+        let text = render_ascii(&road, &positions, 11, 6);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 6);
+
+        // 30 m along the bottom edge
+        assert_eq!(lines[5].chars().nth(3), Some(VEHICLE_CHAR));
+        assert_eq!(lines[5].chars().nth(4), Some(ROAD_CHAR));
+        // The northbound leg runs up the rightmost column
+        assert_eq!(lines[0].chars().nth(10), Some(ROAD_CHAR));
+        assert_eq!(lines[0].chars().next(), Some(' '));
+    }
+}